@@ -0,0 +1,270 @@
+//! # Implementation of the ADFGX and ADFGVX ciphers
+//!
+//! The German army's field ciphers of 1918 combine two of the crate's pieces. First, every letter
+//! is replaced by its row and column in a keyword-mixed Polybius square (see [`polybius`]), with
+//! the rows and columns labelled A D F G X. Then the letters of the result are scrambled with a
+//! column transposition (see [`column_transposition`]).
+//!
+//! The ADFGX cipher used a 5×5 square of the letters, J written as I. Its successor, the ADFGVX
+//! cipher, added a sixth label, V, and a 6×6 square of the letters and digits.
+//!
+//! # Example:
+//!
+//! Square keyword: PRIVACY (ADFGVX)
+//!
+//! ```text
+//!   A D F G V X
+//! A P R I V A C
+//! D Y B D E F G
+//! F H J K L M N
+//! G O Q S T U W
+//! V X Z 0 1 2 3
+//! X 4 5 6 7 8 9
+//! ```
+//!
+//! Message to encipher: "ATTACK AT 1200"
+//!
+//! Substitution: AV GG GG AV AX FF AV GG VG VV VF VF
+//!
+//! Transposition keyphrase: CARGO
+//!
+//! Cipher text: VAFVF AGFGV GAVVF GXGVG VAGV
+
+use crate::cipher::{Cipher, KeyString, KeyedCipher};
+use crate::column_transposition;
+use crate::common::{self, AsciiUppercaseByte};
+use crate::errors::Error;
+use crate::polybius::Grid;
+use std::convert::TryFrom;
+
+/// The labels of the rows and columns of a 6×6 square. A 5×5 square leaves out the V.
+pub const LABELS: &[u8; 6] = b"ADFGVX";
+
+/// Enciphers `plain_text` with the ADFGVX cipher: the 6×6 square mixed with `square_keyword`,
+/// then a column transposition under `keyphrase`
+pub fn encipher(square_keyword: &[u8], keyphrase: &[u8], plain_text: &[u8]) -> Result<String, Error> {
+    Adfgvx::with_key((Grid::square(6, square_keyword)?, transposition_key(keyphrase)?))?.encipher(plain_text)
+}
+
+/// Reverses [`encipher`]
+pub fn decipher(square_keyword: &[u8], keyphrase: &[u8], cipher_text: &[u8]) -> Result<String, Error> {
+    Adfgvx::with_key((Grid::square(6, square_keyword)?, transposition_key(keyphrase)?))?.decipher(cipher_text)
+}
+
+/// Enciphers `plain_text` with the ADFGX cipher: the 5×5 square mixed with `square_keyword`, J
+/// written as I, then a column transposition under `keyphrase`
+pub fn encipher_adfgx(square_keyword: &[u8], keyphrase: &[u8], plain_text: &[u8]) -> Result<String, Error> {
+    Adfgvx::with_key((Grid::square(5, square_keyword)?, transposition_key(keyphrase)?))?.encipher(plain_text)
+}
+
+/// Reverses [`encipher_adfgx`]
+pub fn decipher_adfgx(square_keyword: &[u8], keyphrase: &[u8], cipher_text: &[u8]) -> Result<String, Error> {
+    Adfgvx::with_key((Grid::square(5, square_keyword)?, transposition_key(keyphrase)?))?.decipher(cipher_text)
+}
+
+/// The labels of a square `side` cells wide: ADFGX or ADFGVX
+pub fn labels(side: usize) -> Vec<AsciiUppercaseByte> {
+    LABELS.iter()
+        .filter(|&&label| side == 6 || label != b'V')
+        .map(|&label| AsciiUppercaseByte::try_from(label).unwrap())
+        .collect()
+}
+
+/// The ADFGVX cipher (or ADFGX, with a 5×5 square), for use through the [`Cipher`] trait
+///
+/// The key is the square and the numeric key of the transposition (see
+/// [`column_transposition::create_key`]). As a string, it is the transposition keyphrase, with
+/// the setting `square` (the keyword mixing the square).
+pub struct Adfgvx {
+    grid: Grid,
+    key: Vec<usize>,
+}
+
+impl KeyedCipher for Adfgvx {
+    type Key = (Grid, Vec<usize>);
+
+    fn parse_key(key: &str) -> Result<Self::Key, Error> {
+        parse_key(key, 6)
+    }
+
+    fn with_key((grid, key): Self::Key) -> Result<Self, Error> {
+        if grid.dimensions() != 2 || !(5..=6).contains(&grid.side()) {
+            return Err(Error::KeyError("The ADFGVX square must be 6×6, or 5×5 for ADFGX".to_string()));
+        }
+
+        if key.is_empty() || !column_transposition::is_permutation(&key) {
+            return Err(Error::KeyError("The transposition key must number its columns 0, 1, 2... once each".to_string()));
+        }
+
+        Ok(Adfgvx { grid, key })
+    }
+}
+
+impl Adfgvx {
+    /// The key square
+    pub fn grid(&self) -> &Grid {
+        &self.grid
+    }
+
+    /// Replaces every symbol of `plain_text` with the labels of its row and column
+    pub fn substitute(&self, plain_text: &[u8]) -> Vec<AsciiUppercaseByte> {
+        let labels = labels(self.grid.side());
+
+        common::sanitize_text_with(plain_text, self.grid.alphabet())
+            .into_iter()
+            .flat_map(|symbol| self.grid.coordinates(symbol))
+            .map(|c| labels[c])
+            .collect()
+    }
+
+    /// Reverses [`substitute`](Adfgvx::substitute)
+    ///
+    /// Returns an error if `text` has an odd number of letters, or a letter that is not a label
+    pub fn unsubstitute(&self, text: &[AsciiUppercaseByte]) -> Result<String, Error> {
+        let labels = labels(self.grid.side());
+
+        if !text.len().is_multiple_of(2) {
+            return Err(Error::DecipheringError("ADFGVX cipher text must have an even number of letters".to_string()));
+        }
+
+        let coordinates = text.iter()
+            .map(|letter| {
+                labels.iter()
+                    .position(|label| label == letter)
+                    .ok_or_else(|| Error::DecipheringError(format!("{} is not a row or column label", char::from(*letter))))
+            })
+            .collect::<Result<Vec<usize>, Error>>()?;
+
+        let symbols = coordinates.chunks(2).map(|c| self.grid.symbol(c)).collect::<Vec<_>>();
+
+        Ok(common::format_output(self.grid.alphabet().render(&symbols)))
+    }
+}
+
+impl Cipher for Adfgvx {
+    fn encipher(&self, plain_text: &[u8]) -> Result<String, Error> {
+        let substituted = self.substitute(plain_text);
+
+        Ok(common::format_output(column_transposition::transpose(&self.key, &substituted)))
+    }
+
+    fn decipher(&self, cipher_text: &[u8]) -> Result<String, Error> {
+        let cipher_text = common::sanitize_text(cipher_text)?;
+
+        self.unsubstitute(&column_transposition::untranspose(&self.key, &cipher_text))
+    }
+}
+
+/// The ADFGX cipher, for use through the [`Cipher`] trait
+///
+/// The key is written as for [`Adfgvx`], and builds a 5×5 square.
+pub struct Adfgx(Adfgvx);
+
+impl KeyedCipher for Adfgx {
+    type Key = (Grid, Vec<usize>);
+
+    fn parse_key(key: &str) -> Result<Self::Key, Error> {
+        parse_key(key, 5)
+    }
+
+    fn with_key(key: Self::Key) -> Result<Self, Error> {
+        if key.0.side() != 5 {
+            return Err(Error::KeyError("The ADFGX square must be 5×5".to_string()));
+        }
+
+        Ok(Adfgx(Adfgvx::with_key(key)?))
+    }
+}
+
+impl Adfgx {
+    /// The key square
+    pub fn grid(&self) -> &Grid {
+        self.0.grid()
+    }
+}
+
+impl Cipher for Adfgx {
+    fn encipher(&self, plain_text: &[u8]) -> Result<String, Error> {
+        self.0.encipher(plain_text)
+    }
+
+    fn decipher(&self, cipher_text: &[u8]) -> Result<String, Error> {
+        self.0.decipher(cipher_text)
+    }
+}
+
+fn parse_key(key: &str, side: usize) -> Result<(Grid, Vec<usize>), Error> {
+    let key = KeyString::parse(key)?;
+
+    Ok((Grid::square(side, key.setting("square").unwrap_or("").as_bytes())?, transposition_key(key.main.as_bytes())?))
+}
+
+fn transposition_key(keyphrase: &[u8]) -> Result<Vec<usize>, Error> {
+    let key = column_transposition::create_key(&common::sanitize_text(keyphrase)?);
+
+    if key.is_empty() {
+        return Err(Error::KeyError("The transposition keyphrase needs at least one letter".to_string()));
+    }
+
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::adfgvx::{self, Adfgvx, Adfgx};
+    use crate::alphabet::Alphabet;
+    use crate::cipher::{Cipher, KeyedCipher};
+    use crate::common;
+    use crate::polybius::Grid;
+    use quickcheck::quickcheck;
+
+    #[test]
+    fn test_adfgvx() {
+        let enciphered = adfgvx::encipher(b"PRIVACY", b"CARGO", b"ATTACK AT 1200").unwrap();
+
+        assert_eq!(enciphered, "VAFVF AGFGV GAVVF GXGVG VAGV");
+
+        assert_eq!(adfgvx::decipher(b"PRIVACY", b"CARGO", enciphered.as_bytes()).unwrap(), "ATTAC KAT12 00");
+    }
+
+    #[test]
+    fn test_adfgx() {
+        let adfgx = Adfgx::from_key_str("CARGO;square=PRIVACY").unwrap();
+
+        let enciphered = adfgx.encipher(b"Jump at dawn").unwrap();
+
+        assert!(enciphered.bytes().all(|c| b"ADFGX ".contains(&c)));
+
+        assert_eq!(adfgx.decipher(enciphered.as_bytes()).unwrap(), "IUMPA TDAWN");
+    }
+
+    #[test]
+    fn test_invalid_cipher_text() {
+        let adfgvx = Adfgvx::from_key_str("CARGO;square=PRIVACY").unwrap();
+
+        assert!(adfgvx.decipher(b"ADF").is_err());
+        assert!(adfgvx.decipher(b"ADFGVB").is_err());
+        assert!(Adfgx::from_key_str("CARGO").unwrap().decipher(b"AV").is_err());
+        assert!(Adfgvx::from_key_str("123").is_err());
+
+        // Keys built in code are checked too
+        assert!(Adfgx::with_key((Grid::square(6, b"").unwrap(), vec![1, 0])).is_err());
+        assert!(Adfgvx::with_key((Grid::square(6, b"").unwrap(), vec![2, 0])).is_err());
+        assert!(Adfgvx::with_key((Grid::square(6, b"").unwrap(), Vec::new())).is_err());
+    }
+
+    quickcheck! {
+        fn adfgvx_round_trip(square_keyword: Vec<u8>, keyphrase: Vec<u8>, plain_text: Vec<u8>) -> bool {
+            if !keyphrase.iter().any(|c| c.is_ascii_alphabetic()) {
+                return true;
+            }
+
+            let enciphered = adfgvx::encipher(&square_keyword, &keyphrase, &plain_text).unwrap();
+            let deciphered = adfgvx::decipher(&square_keyword, &keyphrase, enciphered.as_bytes()).unwrap();
+
+            let alphabet = Alphabet::alphanumeric();
+
+            deciphered == common::format_output(alphabet.render(&common::sanitize_text_with(&plain_text, &alphabet)))
+        }
+    }
+}
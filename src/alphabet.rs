@@ -0,0 +1,325 @@
+//! # Alphabets other than A-Z
+//!
+//! Most of the crate works with the 26 letters A-Z, but historical material rarely does.
+//! Renaissance ciphers often merge I/J and U/V, Playfair-style squares merge I/J, and
+//! nomenclators in other languages use letters like Ñ or Ä.
+//!
+//! An [`Alphabet`] defines which symbols exist and in what order. Text is turned into
+//! [`Symbol`]s with [`common::sanitize_text_with`], and symbols of the same alphabet add,
+//! subtract and multiply modulo its size. The letters A-Z of `AsciiUppercaseByte` are the symbols of the
+//! [standard](Alphabet::standard) alphabet, and do their arithmetic the same way.
+
+use crate::common::{self, AsciiUppercaseByte};
+use crate::errors::Error;
+use std::ops::{Add, Mul, Sub};
+
+/// Number of symbols of the standard alphabet, A-Z
+pub const STANDARD_LEN: usize = 26;
+
+/// An ordered set of symbols
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alphabet {
+    symbols: Vec<char>,
+    // Characters that are written as another symbol of the alphabet (e.g. J as I)
+    merges: Vec<(char, char)>,
+}
+
+/// A symbol of an [`Alphabet`], stored as its position in the alphabet
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Symbol {
+    index: usize,
+    size: usize,
+}
+
+impl Symbol {
+    /// Returns the position of the symbol in its alphabet
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns the number of symbols in the alphabet of the symbol
+    pub fn alphabet_len(&self) -> usize {
+        self.size
+    }
+}
+
+// A letter A-Z is a symbol of the standard alphabet
+impl From<AsciiUppercaseByte> for Symbol {
+    fn from(letter: AsciiUppercaseByte) -> Self {
+        Symbol { index: letter.index(), size: STANDARD_LEN }
+    }
+}
+
+impl Add for Symbol {
+    type Output = Symbol;
+
+    fn add(self, other: Self) -> Self {
+        same_alphabet(self, other);
+
+        Symbol { index: (self.index + other.index) % self.size, size: self.size }
+    }
+}
+
+impl Sub for Symbol {
+    type Output = Symbol;
+
+    fn sub(self, other: Self) -> Self {
+        same_alphabet(self, other);
+
+        Symbol { index: (self.index + self.size - other.index) % self.size, size: self.size }
+    }
+}
+
+// Multiplies the symbols as numbers modulo the size of the alphabet, the first symbol being 0
+impl Mul for Symbol {
+    type Output = Symbol;
+
+    fn mul(self, other: Self) -> Self {
+        same_alphabet(self, other);
+
+        Symbol { index: self.index * other.index % self.size, size: self.size }
+    }
+}
+
+// Symbols of alphabets of different sizes cannot be combined: the result would depend on the order
+fn same_alphabet(a: Symbol, b: Symbol) {
+    assert_eq!(a.size, b.size, "Cannot combine symbols of alphabets of {} and {} symbols", a.size, b.size);
+}
+
+impl Alphabet {
+    /// Creates an alphabet from its symbols, in order
+    ///
+    /// Symbols are uppercased. Returns an error if the alphabet is empty or has a repeated symbol.
+    pub fn new(symbols: &str) -> Result<Alphabet, Error> {
+        let symbols = symbols.chars().map(uppercase).collect::<Vec<char>>();
+
+        if symbols.is_empty() {
+            return Err(Error::AlphabetError("An alphabet needs at least one symbol".to_string()));
+        }
+
+        for (i, symbol) in symbols.iter().enumerate() {
+            if symbols[..i].contains(symbol) {
+                return Err(Error::AlphabetError(format!("Symbol {} appears twice in the alphabet", symbol)));
+            }
+        }
+
+        Ok(Alphabet { symbols, merges: Vec::new() })
+    }
+
+    /// Writes the character `from` as the symbol `to` whenever text is sanitized
+    ///
+    /// Returns an error if `to` is not in the alphabet
+    pub fn with_merge(mut self, from: char, to: char) -> Result<Alphabet, Error> {
+        let (from, to) = (uppercase(from), uppercase(to));
+
+        if !self.symbols.contains(&to) {
+            return Err(Error::AlphabetError(format!("Cannot merge {} into {}: {} is not in the alphabet", from, to, to)));
+        }
+
+        self.merges.push((from, to));
+
+        Ok(self)
+    }
+
+    /// The 26 letters A-Z
+    ///
+    /// Its symbols are the letters of [`AsciiUppercaseByte`], which convert to and from them.
+    pub fn standard() -> Alphabet {
+        Alphabet::new("ABCDEFGHIJKLMNOPQRSTUVWXYZ").unwrap()
+    }
+
+    /// The 24 letter alphabet of many Renaissance ciphers, where J is written as I and U as V
+    pub fn latin() -> Alphabet {
+        Alphabet::new("ABCDEFGHIKLMNOPQRSTVWXYZ").unwrap()
+            .with_merge('J', 'I').unwrap()
+            .with_merge('U', 'V').unwrap()
+    }
+
+    /// The 25 letters of a Playfair-style square, where J is written as I
+    pub fn playfair() -> Alphabet {
+        Alphabet::new("ABCDEFGHIKLMNOPQRSTUVWXYZ").unwrap()
+            .with_merge('J', 'I').unwrap()
+    }
+
+    /// The letters A-Z followed by the digits 0-9
+    pub fn alphanumeric() -> Alphabet {
+        Alphabet::new("ABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789").unwrap()
+    }
+
+    /// The letters A-Z followed by +, the 27 symbols of a Trifid cube
+    pub fn trifid() -> Alphabet {
+        Alphabet::new("ABCDEFGHIJKLMNOPQRSTUVWXYZ+").unwrap()
+    }
+
+    /// The letters A-Z followed by Ä, Ö, Ü and ß
+    pub fn german() -> Alphabet {
+        Alphabet::new("ABCDEFGHIJKLMNOPQRSTUVWXYZÄÖÜß").unwrap()
+    }
+
+    /// The letters A-Z with Ñ between N and O
+    pub fn spanish() -> Alphabet {
+        Alphabet::new("ABCDEFGHIJKLMNÑOPQRSTUVWXYZ").unwrap()
+    }
+
+    /// Mixes the alphabet with `keyword`: the symbols of the keyword, repeats dropped, followed by
+    /// the rest of the alphabet in order
+    ///
+    /// Merges are kept, and characters of the keyword that are not in the alphabet are ignored.
+    pub fn keyed(&self, keyword: &[u8]) -> Alphabet {
+        let mut symbols = Vec::with_capacity(self.len());
+
+        for symbol in common::sanitize_text_with(keyword, self).into_iter().chain((0..self.len()).map(|i| self.symbol(i))) {
+            let character = self.character(symbol);
+
+            if !symbols.contains(&character) {
+                symbols.push(character);
+            }
+        }
+
+        Alphabet { symbols, merges: self.merges.clone() }
+    }
+
+    /// Swaps the symbols at positions `a` and `b`
+    pub(crate) fn swap(&mut self, a: usize, b: usize) {
+        self.symbols.swap(a, b);
+    }
+
+    /// Number of symbols in the alphabet
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    /// Always false: an alphabet has at least one symbol
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    /// Returns the symbol written as `character`, if there is one
+    ///
+    /// A symbol is written as itself or in lower case. Characters that only uppercase to a symbol,
+    /// like the dotless ı to I, are not symbols: they could not be written back.
+    pub fn symbol_of(&self, character: char) -> Option<Symbol> {
+        let upper = uppercase(character);
+
+        if upper != character && lowercase(upper) != character {
+            return None;
+        }
+
+        let character = upper;
+
+        let character = self.merges.iter()
+                            .find(|&&(from, _)| from == character)
+                            .map_or(character, |&(_, to)| to);
+
+        self.symbols.iter()
+            .position(|&s| s == character)
+            .map(|index| Symbol { index, size: self.len() })
+    }
+
+    /// Returns the symbol at position `index`, wrapping around the end of the alphabet
+    pub fn symbol(&self, index: usize) -> Symbol {
+        Symbol { index: index % self.len(), size: self.len() }
+    }
+
+    /// Returns the character a symbol is written as
+    ///
+    /// # Panics
+    ///
+    /// Panics if `symbol` comes from a larger alphabet
+    pub fn character(&self, symbol: Symbol) -> char {
+        self.symbols[symbol.index]
+    }
+
+    /// Turns symbols back into characters
+    pub fn render(&self, symbols: &[Symbol]) -> Vec<char> {
+        symbols.iter().map(|&s| self.character(s)).collect()
+    }
+}
+
+// Uppercases a character, leaving it alone if its uppercase form is more than one character (e.g. ß)
+fn uppercase(character: char) -> char {
+    let mut upper = character.to_uppercase();
+
+    match (upper.next(), upper.next()) {
+        (Some(u), None) => u,
+        _ => character,
+    }
+}
+
+// Lowercases a character, leaving it alone if its lowercase form is more than one character
+fn lowercase(character: char) -> char {
+    let mut lower = character.to_lowercase();
+
+    match (lower.next(), lower.next()) {
+        (Some(l), None) => l,
+        _ => character,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::alphabet::Alphabet;
+    use crate::common;
+
+    #[test]
+    fn test_sanitize() {
+        let spanish = Alphabet::spanish();
+
+        let text = common::sanitize_text_with("El niño, ¡mañana!".as_bytes(), &spanish);
+
+        assert_eq!(spanish.render(&text).into_iter().collect::<String>(), "ELNIÑOMAÑANA");
+
+        let latin = Alphabet::latin();
+
+        let text = common::sanitize_text_with(b"Julius", &latin);
+
+        assert_eq!(latin.render(&text).into_iter().collect::<String>(), "IVLIVS");
+
+        // The dotless i and the long s uppercase to I and S, but are not the letters i and s
+        let standard = Alphabet::standard();
+
+        assert!(common::sanitize_text_with("ıſ".as_bytes(), &standard).is_empty());
+    }
+
+    #[test]
+    fn test_arithmetic_wraps_around_alphabet() {
+        let latin = Alphabet::latin();
+
+        let z = latin.symbol_of('Z').unwrap();
+        let b = latin.symbol_of('B').unwrap();
+
+        assert_eq!(latin.character(z + b), 'A');
+        assert_eq!(latin.character(b - z), 'C');
+
+        let alphanumeric = Alphabet::alphanumeric();
+
+        let z = alphanumeric.symbol_of('Z').unwrap();
+        let b = alphanumeric.symbol_of('B').unwrap();
+
+        assert_eq!(alphanumeric.character(z + b), '0');
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_arithmetic_needs_same_alphabet() {
+        let _ = Alphabet::latin().symbol(1) + Alphabet::standard().symbol(1);
+    }
+
+    #[test]
+    fn test_keyed() {
+        let keyed = Alphabet::playfair().keyed(b"Jabberwock!");
+
+        let symbols = (0..keyed.len()).map(|i| keyed.symbol(i)).collect::<Vec<_>>();
+
+        // J is written as I, and the repeated B is dropped
+        assert_eq!(keyed.render(&symbols).into_iter().collect::<String>(), "IABERWOCKDFGHLMNPQSTUVXYZ");
+        assert_eq!(keyed.character(keyed.symbol_of('j').unwrap()), 'I');
+    }
+
+    #[test]
+    fn test_invalid_alphabets() {
+        assert!(Alphabet::new("").is_err());
+        assert!(Alphabet::new("ABCA").is_err());
+        assert!(Alphabet::new("ABC").unwrap().with_merge('D', 'E').is_err());
+    }
+}
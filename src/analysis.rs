@@ -10,19 +10,56 @@ use crate::common::AsciiUppercaseByte;
 use crate::errors;
 use std::convert::TryFrom;
 use std::collections::HashMap;
+use std::ops::RangeInclusive;
+
+/// Relative frequencies of the letters A-Z in ordinary English text
+pub const ENGLISH_FREQUENCIES: [f64; 26] = [
+    0.08167, 0.01492, 0.02782, 0.04253, 0.12702, 0.02228, 0.02015, 0.06094, 0.06966,
+    0.00153, 0.00772, 0.04025, 0.02406, 0.06749, 0.07507, 0.01929, 0.00095, 0.05987,
+    0.06327, 0.09056, 0.02758, 0.00978, 0.02360, 0.00150, 0.01974, 0.00074,
+];
+
+/// Probability that two letters drawn at random from English text are the same (kappa plaintext)
+pub const ENGLISH_KAPPA: f64 = 0.0667;
+
+/// Probability that two letters drawn at random from uniformly random text are the same (kappa random)
+pub const RANDOM_KAPPA: f64 = 1.0 / 26.0;
+
+/// Summary statistics of a cryptogram
+#[derive(Debug, Clone, PartialEq)]
+pub struct Statistics {
+    /// Number of letters in the text
+    pub length: usize,
+    /// Index of coincidence of the text
+    pub index_of_coincidence: f64,
+    /// Index of coincidence divided by kappa plaintext. Close to 1 for English and monoalphabetic ciphers.
+    pub kappa_plaintext_ratio: f64,
+    /// Index of coincidence divided by kappa random. Close to 1 for random text and long-period ciphers.
+    pub kappa_random_ratio: f64,
+    /// Friedman's estimate of the period of a polyalphabetic cipher, if the text allows one
+    pub friedman_period: Option<f64>,
+    /// Shannon entropy, in bits per letter
+    pub entropy: f64,
+}
 
 /// Creates a dictionary of letter frequency counts for each letter that appears in `text`
 pub fn single_letter(text: &[u8]) -> Result<HashMap<AsciiUppercaseByte, usize>, errors::Error> {
     let text = common::sanitize_text(text)?;
+
+    Ok(count_letters(&text))
+}
+
+/// Creates a dictionary of letter frequency counts from already sanitized text
+pub fn count_letters(text: &[AsciiUppercaseByte]) -> HashMap<AsciiUppercaseByte, usize> {
     let mut counts = HashMap::new();
 
-    for character in text {
+    for &character in text {
         counts.entry(character)
                 .and_modify(|count| { *count += 1 })
                 .or_insert(1usize);
     }
 
-    Ok(counts)
+    counts
 }
 
 type AsciiUppercaseDigram = (AsciiUppercaseByte, AsciiUppercaseByte);
@@ -34,7 +71,7 @@ pub fn digram(text: &[u8]) -> Result<HashMap<AsciiUppercaseDigram, usize>, error
 
     // Note that i'm stopping iteration at the next to last character since the loop
     // uses i and i + 1
-    for i in 0..(text.len() - 2) {
+    for i in 0..text.len().saturating_sub(1) {
         let digram = (text[i], text[i + 1]);
         counts.entry(digram)
                 .and_modify(|count| { *count += 1 })
@@ -44,6 +81,132 @@ pub fn digram(text: &[u8]) -> Result<HashMap<AsciiUppercaseDigram, usize>, error
     Ok(counts)
 }
 
+/// Computes the index of coincidence from a single letter frequency map
+///
+/// This is the probability that two letters drawn at random (without replacement) from the
+/// text are the same. It is 0 if there are fewer than two letters.
+pub fn index_of_coincidence(map: &HashMap<AsciiUppercaseByte, usize>) -> f64 {
+    let total = map.values().sum::<usize>();
+
+    if total < 2 {
+        return 0.0;
+    }
+
+    let coincidences = map.values().map(|&n| n * (n - 1)).sum::<usize>();
+
+    coincidences as f64 / (total * (total - 1)) as f64
+}
+
+/// Estimates the period of a polyalphabetic cipher from a single letter frequency map
+///
+/// This is William Friedman's formula, which compares the observed index of coincidence
+/// with kappa plaintext and kappa random. Treat the result as a rough guide: it is most
+/// useful for telling a short period from a long one.
+///
+/// There is no estimate (`None`) for fewer than two letters, or when the index of coincidence
+/// is no higher than kappa random: the text looks random, and the formula breaks down.
+pub fn friedman_period(map: &HashMap<AsciiUppercaseByte, usize>) -> Option<f64> {
+    let total = map.values().sum::<usize>() as f64;
+
+    let index_of_coincidence = index_of_coincidence(map);
+
+    let denominator = (total - 1.0) * index_of_coincidence - RANDOM_KAPPA * total + ENGLISH_KAPPA;
+
+    if total < 2.0 || index_of_coincidence <= RANDOM_KAPPA || denominator <= 0.0 {
+        return None;
+    }
+
+    Some((ENGLISH_KAPPA - RANDOM_KAPPA) * total / denominator)
+}
+
+/// Computes the Shannon entropy, in bits per letter, of a single letter frequency map
+///
+/// English sits at about 4.2 bits; uniformly random letters approach log2(26) = 4.7 bits
+pub fn entropy(map: &HashMap<AsciiUppercaseByte, usize>) -> f64 {
+    let total = map.values().sum::<usize>() as f64;
+
+    map.values()
+        .filter(|&&n| n > 0)
+        .map(|&n| {
+            let p = n as f64 / total;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+/// Computes all the summary statistics of `text` at once
+pub fn statistics(text: &[u8]) -> Result<Statistics, errors::Error> {
+    let map = single_letter(text)?;
+
+    let index_of_coincidence = index_of_coincidence(&map);
+
+    Ok(Statistics {
+        length: map.values().sum(),
+        index_of_coincidence,
+        kappa_plaintext_ratio: index_of_coincidence / ENGLISH_KAPPA,
+        kappa_random_ratio: index_of_coincidence / RANDOM_KAPPA,
+        friedman_period: friedman_period(&map),
+        entropy: entropy(&map),
+    })
+}
+
+/// Computes the chi-squared statistic of `text` against the letter frequencies of English
+///
+/// The smaller the result, the more `text` looks like English
+pub fn chi_squared(text: &[AsciiUppercaseByte]) -> f64 {
+    let mut counts = [0usize; 26];
+
+    for letter in text {
+        counts[(letter.get_byte() - b'A') as usize] += 1;
+    }
+
+    let total = text.len() as f64;
+
+    counts.iter()
+            .zip(ENGLISH_FREQUENCIES.iter())
+            .map(|(&observed, &frequency)| {
+                let expected = frequency * total;
+                (observed as f64 - expected).powi(2) / expected
+            })
+            .sum()
+}
+
+/// Scores each of `periods` as the period of a Bifid cipher; the highest score is the likeliest period
+///
+/// In a Bifid block of even period p, the cipher letters at positions i and i + p/2 are made of
+/// the rows and the columns of the same two plain letters. Paired up, they are a digraphic
+/// substitution of the plain text, and repeat as often as its digrams do. At any other distance,
+/// the pairs mix coordinates of unrelated letters. An odd period shifts the columns by half a
+/// letter, so each pair only shares one plain letter, and stands out less.
+///
+/// The score is the index of coincidence of the pairs, times 676: about 1 for random pairs, and
+/// several times more at the right period.
+pub fn bifid_periods(text: &[u8], periods: RangeInclusive<usize>) -> Result<Vec<(usize, f64)>, errors::Error> {
+    let text = common::sanitize_text(text)?;
+
+    Ok(periods.filter(|&period| period > 1)
+        .map(|period| {
+            let mut counts = HashMap::new();
+
+            for block in text.chunks(period) {
+                let half = block.len().div_ceil(2);
+
+                for i in 0..block.len() - half {
+                    *counts.entry((block[i], block[i + half])).or_insert(0usize) += 1;
+                }
+            }
+
+            let total = counts.values().sum::<usize>();
+
+            let coincidences = counts.values().map(|&n| n * (n - 1)).sum::<usize>();
+
+            let score = if total < 2 { 0.0 } else { 676.0 * coincidences as f64 / (total * (total - 1)) as f64 };
+
+            (period, score)
+        })
+        .collect())
+}
+
 /// Prints a single letter frequency map to the console
 pub fn print_single_letter_histogram(map: &HashMap<AsciiUppercaseByte, usize>) {
     for key in b'A'..=b'Z' {
@@ -51,15 +214,34 @@ pub fn print_single_letter_histogram(map: &HashMap<AsciiUppercaseByte, usize>) {
         
         let key = AsciiUppercaseByte::try_from(key).unwrap();
 
-        match map.get(&key) {
-            Some(&count) => { for _ in 0..count { print!("|"); } }
-            None => {}
+        if let Some(&count) = map.get(&key) {
+            for _ in 0..count { print!("|"); }
         }
 
         println!();
     }
 }
 
+/// Prints the scores of [`bifid_periods`] to the console, one bar per period
+pub fn print_bifid_periods(scores: &[(usize, f64)]) {
+    for &(period, score) in scores {
+        println!("{:3} {:5.2} {}", period, score, "|".repeat((score * 10.0).round() as usize));
+    }
+}
+
+/// Prints summary statistics to the console
+pub fn print_statistics(stats: &Statistics) {
+    println!("Length:                 {}", stats.length);
+    println!("Index of coincidence:   {:.4}", stats.index_of_coincidence);
+    println!("  vs. kappa plaintext:  {:.4} ({:.2}x)", ENGLISH_KAPPA, stats.kappa_plaintext_ratio);
+    println!("  vs. kappa random:     {:.4} ({:.2}x)", RANDOM_KAPPA, stats.kappa_random_ratio);
+    match stats.friedman_period {
+        Some(period) => println!("Friedman period:        {:.2}", period),
+        None => println!("Friedman period:        n/a"),
+    }
+    println!("Entropy:                {:.4} bits/letter", stats.entropy);
+}
+
 /// Prints the digram frequency map to the console
 pub fn print_digram_frequencies(map: &HashMap<AsciiUppercaseDigram, usize>) {
     for left in b'A'..=b'Z' {
@@ -87,7 +269,11 @@ pub fn print_digram_frequencies(map: &HashMap<AsciiUppercaseDigram, usize>) {
 #[cfg(test)]
 mod tests {
     use crate::analysis;
+    use crate::bifid;
+    use crate::common;
     use crate::common::AsciiUppercaseByte;
+    use crate::crack::PLAIN_TEXT;
+    use crate::vigenere_standard;
     use std::convert::TryFrom;
 
     #[test]
@@ -108,5 +294,84 @@ mod tests {
         let in_digram = (AsciiUppercaseByte::try_from(b'I').unwrap(), AsciiUppercaseByte::try_from(b'N').unwrap());
 
         assert_eq!(freq.get(&in_digram), Some(&2));
+
+        // The last two letters make a digram too
+        let ll_digram = (AsciiUppercaseByte::try_from(b'L').unwrap(), AsciiUppercaseByte::try_from(b'L').unwrap());
+
+        assert_eq!(freq.get(&ll_digram), Some(&1));
+        assert_eq!(freq.values().sum::<usize>(), 36);
+
+        assert!(analysis::digram(b"A").unwrap().is_empty());
+        assert!(analysis::digram(b"").unwrap().is_empty());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_index_of_coincidence() {
+        let freq = analysis::single_letter(b"AABB").unwrap();
+
+        // 2 * (2 * 1) coincidences out of 4 * 3 pairs
+        assert!((analysis::index_of_coincidence(&freq) - 4.0 / 12.0).abs() < 1e-9);
+
+        let freq = analysis::single_letter(b"A").unwrap();
+
+        assert_eq!(analysis::index_of_coincidence(&freq), 0.0);
+    }
+
+    #[test]
+    fn test_entropy() {
+        let freq = analysis::single_letter(b"AAAA").unwrap();
+
+        assert_eq!(analysis::entropy(&freq), 0.0);
+
+        let freq = analysis::single_letter(b"ABCD").unwrap();
+
+        assert!((analysis::entropy(&freq) - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_friedman_period() {
+        let plain_text = b"It was the best of times, it was the worst of times, it was the age of wisdom, \
+            it was the age of foolishness, it was the epoch of belief, it was the epoch of incredulity, it was \
+            the season of Light, it was the season of Darkness, it was the spring of hope, it was the winter of \
+            despair, we had everything before us, we had nothing before us";
+
+        let stats = analysis::statistics(plain_text).unwrap();
+
+        assert!(stats.friedman_period.unwrap() < 1.5);
+
+        let cipher_text = vigenere_standard::encipher(b"DICKENS", plain_text).unwrap();
+
+        let stats = analysis::statistics(cipher_text.as_bytes()).unwrap();
+
+        assert!(stats.friedman_period.unwrap() > 3.0);
+    }
+
+    #[test]
+    fn test_friedman_period_undefined() {
+        // Too short, or no repeated letters: no better than random
+        assert_eq!(analysis::statistics(b"a").unwrap().friedman_period, None);
+        assert_eq!(analysis::statistics(b"ab").unwrap().friedman_period, None);
+        assert_eq!(analysis::statistics(b"abcdefghij").unwrap().friedman_period, None);
+    }
+
+    #[test]
+    fn test_chi_squared() {
+        let english = common::sanitize_text(b"It was the best of times, it was the worst of times").unwrap();
+        let gibberish = common::sanitize_text(b"Qzxj vkqw zzxq jjkv qxzw vvqj xkzq").unwrap();
+
+        assert!(analysis::chi_squared(&english) < analysis::chi_squared(&gibberish));
+    }
+
+    #[test]
+    fn test_bifid_periods() {
+        for period in [6, 7] {
+            let cipher_text = bifid::encipher(b"KEYWORD", period, PLAIN_TEXT).unwrap();
+
+            let scores = analysis::bifid_periods(cipher_text.as_bytes(), 2..=20).unwrap();
+
+            let best = scores.iter().max_by(|a, b| a.1.partial_cmp(&b.1).unwrap()).unwrap();
+
+            assert_eq!(best.0, period);
+        }
+    }
+}
@@ -0,0 +1,179 @@
+//! # Implementation of the Beaufort and variant Beaufort ciphers
+//!
+//! Both use the Vigenere tableau and a repeating key, but combine the key and the text the
+//! other way round. If _P_ is the plain text, _C_ the cipher text and _K_ the key, the
+//! Beaufort cipher enciphers with:
+//!
+//! `C = K - P`
+//!
+//! Deciphering is the same operation, `P = K - C`, so the Beaufort cipher is its own inverse.
+//!
+//! The variant Beaufort cipher enciphers with:
+//!
+//! `C = P - K`
+//!
+//! which is deciphering with the standard Vigenere cipher. A variant Beaufort key is therefore
+//! a Vigenere key in disguise: replacing every letter of it by its negative (A stays A, B
+//! becomes Z, C becomes Y...) gives the Vigenere key that deciphers the message.
+//!
+//! # Example:
+//!
+//! Key: FORTIFICATION
+//!
+//! Plain text: DEFEND THE EAST WALL OF THE CASTLE
+//!
+//! Cipher text (Beaufort): CKMPV CPVWP IWUJO GIUAP VWRIW UUK
+
+use crate::cipher::{Cipher, KeyedCipher};
+use crate::common::{self, AsciiUppercaseByte};
+use crate::errors::Error;
+use crate::passthrough::KeyAdvance;
+use crate::vigenere_standard::{self, add_bytes, subtract_bytes};
+
+/// Encipher (or decipher) `text` with the Beaufort cipher under the key `key`
+pub fn encipher(key: &[u8], text: &[u8]) -> Result<String, Error> {
+    Beaufort::with_key(common::sanitize_text(key)?)?.encipher(text)
+}
+
+/// Encipher `plain_text` with the variant Beaufort cipher under the key `key`
+pub fn encipher_variant(key: &[u8], plain_text: &[u8]) -> Result<String, Error> {
+    VariantBeaufort::with_key(common::sanitize_text(key)?)?.encipher(plain_text)
+}
+
+/// Decipher `cipher_text` with the variant Beaufort cipher under the key `key`
+pub fn decipher_variant(key: &[u8], cipher_text: &[u8]) -> Result<String, Error> {
+    VariantBeaufort::with_key(common::sanitize_text(key)?)?.decipher(cipher_text)
+}
+
+/// The Beaufort cipher, for use through the [`Cipher`] trait
+pub struct Beaufort {
+    key: Vec<AsciiUppercaseByte>,
+}
+
+impl KeyedCipher for Beaufort {
+    type Key = Vec<AsciiUppercaseByte>;
+
+    fn parse_key(key: &str) -> Result<Self::Key, Error> {
+        common::sanitize_text(key.as_bytes())
+    }
+
+    fn with_key(key: Self::Key) -> Result<Self, Error> {
+        Ok(Beaufort { key })
+    }
+}
+
+impl Cipher for Beaufort {
+    fn encipher(&self, plain_text: &[u8]) -> Result<String, Error> {
+        vigenere_standard::combine(&self.key, plain_text, |text, key| subtract_bytes(key, text))
+    }
+
+    fn decipher(&self, cipher_text: &[u8]) -> Result<String, Error> {
+        self.encipher(cipher_text)
+    }
+
+    fn encipher_passthrough(&self, plain_text: &[u8], advance: KeyAdvance) -> Result<String, Error> {
+        Ok(vigenere_standard::passthrough(&common::to_symbols(&self.key), plain_text, advance, |text, key| subtract_bytes(key, text)))
+    }
+
+    fn decipher_passthrough(&self, cipher_text: &[u8], advance: KeyAdvance) -> Result<String, Error> {
+        self.encipher_passthrough(cipher_text, advance)
+    }
+}
+
+/// The variant Beaufort cipher, for use through the [`Cipher`] trait
+pub struct VariantBeaufort {
+    key: Vec<AsciiUppercaseByte>,
+}
+
+impl KeyedCipher for VariantBeaufort {
+    type Key = Vec<AsciiUppercaseByte>;
+
+    fn parse_key(key: &str) -> Result<Self::Key, Error> {
+        common::sanitize_text(key.as_bytes())
+    }
+
+    fn with_key(key: Self::Key) -> Result<Self, Error> {
+        Ok(VariantBeaufort { key })
+    }
+}
+
+impl Cipher for VariantBeaufort {
+    fn encipher(&self, plain_text: &[u8]) -> Result<String, Error> {
+        vigenere_standard::combine(&self.key, plain_text, subtract_bytes)
+    }
+
+    fn decipher(&self, cipher_text: &[u8]) -> Result<String, Error> {
+        vigenere_standard::combine(&self.key, cipher_text, add_bytes)
+    }
+
+    fn encipher_passthrough(&self, plain_text: &[u8], advance: KeyAdvance) -> Result<String, Error> {
+        Ok(vigenere_standard::passthrough(&common::to_symbols(&self.key), plain_text, advance, subtract_bytes))
+    }
+
+    fn decipher_passthrough(&self, cipher_text: &[u8], advance: KeyAdvance) -> Result<String, Error> {
+        Ok(vigenere_standard::passthrough(&common::to_symbols(&self.key), cipher_text, advance, add_bytes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::beaufort;
+    use crate::cipher::{Cipher, KeyedCipher};
+    use crate::common;
+    use crate::passthrough::KeyAdvance;
+    use crate::vigenere_standard;
+    use quickcheck::quickcheck;
+
+    #[test]
+    fn test_beaufort() {
+        let enciphered = beaufort::encipher(b"FORTIFICATION", b"DEFEND THE EAST WALL OF THE CASTLE").unwrap();
+
+        assert_eq!(enciphered, "CKMPV CPVWP IWUJO GIUAP VWRIW \nUUK");
+
+        let deciphered = beaufort::encipher(b"FORTIFICATION", enciphered.as_bytes()).unwrap();
+
+        assert_eq!(deciphered, "DEFEN DTHEE ASTWA LLOFT HECAS \nTLE");
+    }
+
+    #[test]
+    fn test_variant_beaufort() {
+        let enciphered = beaufort::encipher_variant(b"FORTIFICATION", b"DEFEND THE EAST WALL OF THE CASTLE").unwrap();
+
+        // Enciphering with the variant is deciphering with the Vigenere cipher
+        assert_eq!(enciphered, vigenere_standard::decipher(b"FORTIFICATION", b"DEFEND THE EAST WALL OF THE CASTLE").unwrap());
+
+        // And the negated key deciphers it as a Vigenere
+        assert_eq!(vigenere_standard::decipher(b"VMJHSVSYAHSMN", enciphered.as_bytes()).unwrap(), "DEFEN DTHEE ASTWA LLOFT HECAS \nTLE");
+
+        assert_eq!(beaufort::decipher_variant(b"FORTIFICATION", enciphered.as_bytes()).unwrap(), "DEFEN DTHEE ASTWA LLOFT HECAS \nTLE");
+    }
+
+    #[test]
+    fn test_beaufort_passthrough() {
+        let beaufort = beaufort::Beaufort::from_key_str("FORTIFICATION").unwrap();
+
+        let enciphered = beaufort.encipher_passthrough(b"Defend the east wall!", KeyAdvance::LettersOnly).unwrap();
+
+        assert_eq!(enciphered, "Ckmpvc pvw piwu jogi!");
+
+        assert_eq!(beaufort.decipher_passthrough(enciphered.as_bytes(), KeyAdvance::LettersOnly).unwrap(), "Defend the east wall!");
+    }
+
+    quickcheck! {
+        fn beaufort_round_trip(key: Vec<u8>, plain_text: Vec<u8>) -> bool {
+            let enciphered = beaufort::encipher(&key, &plain_text).unwrap();
+            let deciphered = beaufort::encipher(&key, enciphered.as_bytes()).unwrap();
+
+            deciphered == common::format_output(common::sanitize_text(&plain_text).unwrap())
+        }
+    }
+
+    quickcheck! {
+        fn variant_beaufort_round_trip(key: Vec<u8>, plain_text: Vec<u8>) -> bool {
+            let enciphered = beaufort::encipher_variant(&key, &plain_text).unwrap();
+            let deciphered = beaufort::decipher_variant(&key, enciphered.as_bytes()).unwrap();
+
+            deciphered == common::format_output(common::sanitize_text(&plain_text).unwrap())
+        }
+    }
+}
@@ -0,0 +1,148 @@
+//! # Implementation of Delastelle's Bifid cipher
+//!
+//! The Bifid _fractionates_ the message with a Polybius square (see [`polybius`]): each letter is
+//! split into its row and column, the halves are mixed with those of its neighbours, and the
+//! result is put back together into letters.
+//!
+//! The message is split into blocks of a fixed length, the _period_. Under each block, the row
+//! and then the column of every letter are written out, one below the other. The digits are read
+//! along the rows, two at a time, and each pair becomes the letter at that row and column.
+//!
+//! # Example:
+//!
+//! ```text
+//! B G W K Z
+//! Q P N D S
+//! I O A X E
+//! F C L U M
+//! T H Y V R
+//! ```
+//!
+//! Message to encipher: "FLEE AT ONCE", as a single block
+//!
+//! ```text
+//!         F L E E A T O N C E
+//! Row:    4 4 3 3 3 5 3 2 4 3
+//! Column: 1 3 5 5 3 1 2 3 2 5
+//! ```
+//!
+//! Pairs: 44 33 35 32 43 13 55 31 23 25
+//!
+//! Cipher text: UAEOL WRINS
+//!
+//! A 6×6 square of the letters and digits can be used instead of the 5×5 one.
+
+use crate::cipher::{Cipher, KeyString, KeyedCipher};
+use crate::common;
+use crate::errors::Error;
+use crate::polybius::{self, Grid};
+
+/// Enciphers `plain_text` with the 5×5 square built from `keyword`, `period` letters at a time
+/// (the whole message at once if `period` is 0)
+pub fn encipher(keyword: &[u8], period: usize, plain_text: &[u8]) -> Result<String, Error> {
+    Bifid::with_key((Grid::square(5, keyword)?, period))?.encipher(plain_text)
+}
+
+/// Reverses [`encipher`]
+pub fn decipher(keyword: &[u8], period: usize, cipher_text: &[u8]) -> Result<String, Error> {
+    Bifid::with_key((Grid::square(5, keyword)?, period))?.decipher(cipher_text)
+}
+
+/// The Bifid cipher, for use through the [`Cipher`] trait
+///
+/// The key is the square and the period. As a string, it is the keyword, with the settings
+/// `period` (default 0, the whole message at once) and `size` (5 or 6, default 5).
+pub struct Bifid {
+    grid: Grid,
+    period: usize,
+}
+
+impl KeyedCipher for Bifid {
+    type Key = (Grid, usize);
+
+    fn parse_key(key: &str) -> Result<Self::Key, Error> {
+        let key = KeyString::parse(key)?;
+
+        Ok((Grid::square(key.parsed_setting("size", 5)?, key.main.as_bytes())?, key.parsed_setting("period", 0)?))
+    }
+
+    fn with_key((grid, period): Self::Key) -> Result<Self, Error> {
+        Ok(Bifid { grid, period })
+    }
+}
+
+impl Bifid {
+    /// The key square
+    pub fn grid(&self) -> &Grid {
+        &self.grid
+    }
+}
+
+impl Cipher for Bifid {
+    fn encipher(&self, plain_text: &[u8]) -> Result<String, Error> {
+        let plain_text = common::sanitize_text_with(plain_text, self.grid.alphabet());
+
+        let cipher_text = polybius::fractionate(&self.grid, &plain_text, self.period);
+
+        Ok(common::format_output(self.grid.alphabet().render(&cipher_text)))
+    }
+
+    fn decipher(&self, cipher_text: &[u8]) -> Result<String, Error> {
+        let cipher_text = common::sanitize_text_with(cipher_text, self.grid.alphabet());
+
+        let plain_text = polybius::unfractionate(&self.grid, &cipher_text, self.period);
+
+        Ok(common::format_output(self.grid.alphabet().render(&plain_text)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::alphabet::Alphabet;
+    use crate::bifid::{self, Bifid};
+    use crate::cipher::{Cipher, KeyedCipher};
+    use crate::common;
+    use quickcheck::quickcheck;
+
+    #[test]
+    fn test_bifid() {
+        let enciphered = bifid::encipher(b"BGWKZQPNDSIOAXEFCLUMTHYVR", 0, b"FLEE AT ONCE").unwrap();
+
+        assert_eq!(enciphered, "UAEOL WRINS");
+
+        assert_eq!(bifid::decipher(b"BGWKZQPNDSIOAXEFCLUMTHYVR", 0, enciphered.as_bytes()).unwrap(), "FLEEA TONCE");
+    }
+
+    #[test]
+    fn test_period() {
+        // With a period of 5, FLEEA and TONCE are fractionated separately
+        let enciphered = bifid::encipher(b"BGWKZQPNDSIOAXEFCLUMTHYVR", 5, b"FLEE AT ONCE").unwrap();
+
+        assert_eq!(enciphered, bifid::encipher(b"BGWKZQPNDSIOAXEFCLUMTHYVR", 0, b"FLEEA").unwrap() + " "
+            + &bifid::encipher(b"BGWKZQPNDSIOAXEFCLUMTHYVR", 0, b"TONCE").unwrap());
+
+        let bifid = Bifid::from_key_str("ALPHABET 123;size=6;period=7").unwrap();
+
+        let enciphered = bifid.encipher(b"Meet at 10 pm").unwrap();
+
+        assert_eq!(bifid.decipher(enciphered.as_bytes()).unwrap(), "MEETA T10PM");
+    }
+
+    #[test]
+    fn test_invalid_keys() {
+        assert!(Bifid::from_key_str("KEY;size=4").is_err());
+        assert!(Bifid::from_key_str("KEY;period=-1").is_err());
+    }
+
+    quickcheck! {
+        fn bifid_round_trip(keyword: Vec<u8>, period: u8, plain_text: Vec<u8>) -> bool {
+            let enciphered = bifid::encipher(&keyword, period as usize, &plain_text).unwrap();
+            let deciphered = bifid::decipher(&keyword, period as usize, enciphered.as_bytes()).unwrap();
+
+            // J is written as I
+            let alphabet = Alphabet::playfair();
+
+            deciphered == common::format_output(alphabet.render(&common::sanitize_text_with(&plain_text, &alphabet)))
+        }
+    }
+}
@@ -0,0 +1,127 @@
+//! # Layering ciphers
+//!
+//! Most of the systems used in the World Wars were layered: a substitution _superenciphered_
+//! with a transposition, or two transpositions in a row. A [`Chain`] runs several ciphers one
+//! after the other, each one enciphering the output of the previous one.
+//!
+//! A chain is written as its stages separated by `|`. Each stage is written `name:KEY`, or
+//! `name/variant:KEY` to pick a variant, with the names of the [registry](crate::cipher::REGISTRY):
+//!
+//! ```
+//! use codebreakers::chain::Chain;
+//! use codebreakers::cipher::Cipher;
+//!
+//! let chain = Chain::parse("vigenere:LEMON | column-transposition:ZEBRAS").unwrap();
+//!
+//! let cipher_text = chain.encipher(b"ATTACK AT DAWN").unwrap();
+//!
+//! assert_eq!(chain.decipher(cipher_text.as_bytes()).unwrap(), "ATTAC KATDA WN");
+//! ```
+//!
+//! Deciphering runs the stages in reverse order, each one deciphering the output of the next.
+
+use crate::cipher::{self, Cipher};
+use crate::errors::Error;
+
+/// Ciphers applied one after the other
+pub struct Chain {
+    stages: Vec<Box<dyn Cipher>>,
+}
+
+impl Chain {
+    /// Creates a chain from its stages, in enciphering order
+    pub fn new(stages: Vec<Box<dyn Cipher>>) -> Chain {
+        Chain { stages }
+    }
+
+    /// Creates a chain written as `name:KEY | name/variant:KEY | ...`
+    ///
+    /// Returns an error if the chain is empty, or if a stage names an unknown cipher or has an invalid key
+    pub fn parse(chain: &str) -> Result<Chain, Error> {
+        let stages = chain.split('|')
+            .map(|stage| {
+                let stage = stage.trim();
+
+                let mut parts = stage.splitn(2, ':');
+
+                let (cipher, key) = match (parts.next(), parts.next()) {
+                    (Some(cipher), Some(key)) if !cipher.trim().is_empty() => (cipher.trim(), key.trim()),
+                    _ => return Err(Error::KeyError(format!("Stage '{}' should look like name:KEY", stage))),
+                };
+
+                let mut parts = cipher.splitn(2, '/');
+
+                let name = parts.next().unwrap_or("");
+                let variant = parts.next();
+
+                cipher::build(name, variant, key)
+            })
+            .collect::<Result<Vec<Box<dyn Cipher>>, Error>>()?;
+
+        Ok(Chain::new(stages))
+    }
+}
+
+impl Cipher for Chain {
+    fn encipher(&self, plain_text: &[u8]) -> Result<String, Error> {
+        let mut text = String::from_utf8_lossy(plain_text).into_owned();
+
+        for stage in &self.stages {
+            text = stage.encipher(text.as_bytes())?;
+        }
+
+        Ok(text)
+    }
+
+    fn decipher(&self, cipher_text: &[u8]) -> Result<String, Error> {
+        let mut text = String::from_utf8_lossy(cipher_text).into_owned();
+
+        for stage in self.stages.iter().rev() {
+            text = stage.decipher(text.as_bytes())?;
+        }
+
+        Ok(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::chain::Chain;
+    use crate::cipher::Cipher;
+    use crate::column_transposition;
+    use crate::vigenere_autokey;
+    use crate::vigenere_standard;
+
+    #[test]
+    fn test_chain() {
+        let chain = Chain::parse("vigenere:LEMON | column-transposition:ZEBRAS").unwrap();
+
+        let vigenere = vigenere_standard::encipher(b"LEMON", b"WE ARE DISCOVERED. FLEE AT ONCE").unwrap();
+
+        let enciphered = chain.encipher(b"WE ARE DISCOVERED. FLEE AT ONCE").unwrap();
+
+        assert_eq!(enciphered, column_transposition::encipher(b"ZEBRAS", vigenere.as_bytes()).unwrap());
+
+        assert_eq!(chain.decipher(enciphered.as_bytes()).unwrap(), "WEARE DISCO VERED FLEEA TONCE");
+    }
+
+    #[test]
+    fn test_chain_variants_and_settings() {
+        let chain = Chain::parse("vigenere/autokey:QUEEN | double-transposition:ZEBRAS;second=STRIPE").unwrap();
+
+        let autokey = vigenere_autokey::encipher(b"QUEEN", b"WE ARE DISCOVERED").unwrap();
+
+        assert_eq!(
+            chain.encipher(b"WE ARE DISCOVERED").unwrap(),
+            column_transposition::encipher_double(b"ZEBRAS", b"STRIPE", autokey.as_bytes()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_invalid_chains() {
+        assert!(Chain::parse("").is_err());
+        assert!(Chain::parse("vigenere").is_err());
+        assert!(Chain::parse("vigenere:LEMON | no-such-cipher:KEY").is_err());
+        assert!(Chain::parse("vigenere/no-such-variant:LEMON").is_err());
+    }
+}
@@ -0,0 +1,521 @@
+//! # A common interface to every cipher in the crate
+//!
+//! Each cipher module exposes a type implementing [`Cipher`] (enciphering and deciphering)
+//! and [`KeyedCipher`] (building the cipher from a typed key, or from a key written as a string).
+//!
+//! Keys are written as strings. Ciphers that need more than a keyword take extra _settings_
+//! after the main part of the key, separated by semicolons: `MAINKEY;name=value;name=value`
+//! (see [`KeyString`]).
+//!
+//! The [`REGISTRY`] lists every cipher by name, so a cipher can be chosen at runtime:
+//!
+//! ```
+//! use codebreakers::cipher;
+//!
+//! let vigenere = cipher::build("vigenere", None, "LEMON").unwrap();
+//!
+//! assert_eq!(vigenere.encipher(b"ATTACK AT DAWN").unwrap(), "LXFOP VEFRN HR");
+//! ```
+
+use crate::adfgvx::{Adfgvx, Adfgx};
+use crate::beaufort::{Beaufort, VariantBeaufort};
+use crate::bifid::Bifid;
+use crate::column_transposition::{ColumnTransposition, DoubleTransposition, MyszkowskiTransposition};
+use crate::enigma::{Enigma, EnigmaM4};
+use crate::errors::Error;
+use crate::four_square::FourSquare;
+use crate::gronsfeld::Gronsfeld;
+use crate::hill::Hill;
+use crate::passthrough::KeyAdvance;
+use crate::playfair::Playfair;
+use crate::polybius::Polybius;
+use crate::porta::Porta;
+use crate::quagmire::{Kind, Quagmire};
+use crate::rail_fence::RailFence;
+use crate::route::RouteTransposition;
+use crate::running_key::RunningKey;
+use crate::substitution::Substitution;
+use crate::tableau::{Tableau, TableauCipher};
+use crate::trifid::Trifid;
+use crate::two_square::{HorizontalTwoSquare, TwoSquare};
+use crate::vigenere_autokey::{AutokeyVigenere, CiphertextAutokeyVigenere};
+use crate::vigenere_standard::StandardVigenere;
+
+/// A cipher whose key has already been set
+pub trait Cipher {
+    /// Enciphers `plain_text`
+    fn encipher(&self, plain_text: &[u8]) -> Result<String, Error>;
+
+    /// Deciphers `cipher_text`
+    fn decipher(&self, cipher_text: &[u8]) -> Result<String, Error>;
+
+    /// Enciphers only the letters of `plain_text`, leaving everything else in place
+    ///
+    /// Ciphers that do not support passthrough mode return an error
+    fn encipher_passthrough(&self, _plain_text: &[u8], _advance: KeyAdvance) -> Result<String, Error> {
+        Err(Error::EncipheringError("This cipher does not support passthrough mode".to_string()))
+    }
+
+    /// Deciphers only the letters of `cipher_text`, leaving everything else in place
+    ///
+    /// Ciphers that do not support passthrough mode return an error
+    fn decipher_passthrough(&self, _cipher_text: &[u8], _advance: KeyAdvance) -> Result<String, Error> {
+        Err(Error::DecipheringError("This cipher does not support passthrough mode".to_string()))
+    }
+}
+
+/// A cipher that can be built from a key
+pub trait KeyedCipher: Cipher + Sized {
+    /// The key, in the form the cipher works with
+    type Key;
+
+    /// Parses a key written as a string
+    fn parse_key(key: &str) -> Result<Self::Key, Error>;
+
+    /// Creates the cipher with the key `key`
+    ///
+    /// Fails if the key breaks a rule of the cipher, the same way [`KeyedCipher::parse_key`]
+    /// would for a key written as a string
+    fn with_key(key: Self::Key) -> Result<Self, Error>;
+
+    /// Creates the cipher from a key written as a string
+    fn from_key_str(key: &str) -> Result<Self, Error> {
+        Self::with_key(Self::parse_key(key)?)
+    }
+}
+
+/// A key written as a string, split into its main part and its settings
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyString<'a> {
+    /// Everything before the first semicolon
+    pub main: &'a str,
+    settings: Vec<(&'a str, &'a str)>,
+}
+
+impl<'a> KeyString<'a> {
+    /// Splits `key` into its main part and its `name=value` settings
+    pub fn parse(key: &'a str) -> Result<KeyString<'a>, Error> {
+        let mut parts = key.split(';');
+
+        let main = parts.next().unwrap_or("").trim();
+
+        let settings = parts
+            .map(|part| {
+                let mut pair = part.splitn(2, '=');
+
+                match (pair.next(), pair.next()) {
+                    (Some(name), Some(value)) => Ok((name.trim(), value.trim())),
+                    _ => Err(Error::KeyError(format!("Setting '{}' should look like name=value", part))),
+                }
+            })
+            .collect::<Result<Vec<(&str, &str)>, Error>>()?;
+
+        Ok(KeyString { main, settings })
+    }
+
+    /// Returns the value of the setting called `name`, if it was given
+    pub fn setting(&self, name: &str) -> Option<&'a str> {
+        self.settings.iter().rev().find(|&&(n, _)| n == name).map(|&(_, value)| value)
+    }
+
+    /// Returns the value of the setting called `name` parsed as a `T`, or `default` if it was not given
+    pub fn parsed_setting<T: std::str::FromStr>(&self, name: &str, default: T) -> Result<T, Error> {
+        match self.setting(name) {
+            Some(value) => value.parse::<T>()
+                .map_err(|_| Error::KeyError(format!("Invalid value '{}' for setting '{}'", value, name))),
+            None => Ok(default),
+        }
+    }
+}
+
+/// A setting a cipher accepts in its key, besides the main part
+pub struct Setting {
+    /// The name of the setting
+    pub name: &'static str,
+    /// A one line description of the setting
+    pub help: &'static str,
+}
+
+/// A named flavour of a cipher
+pub struct Variant {
+    /// The name of the variant
+    pub name: &'static str,
+    /// Creates the cipher from a key written as a string
+    pub build: fn(&str) -> Result<Box<dyn Cipher>, Error>,
+}
+
+/// A table that shows what a key looks like, such as a key square
+pub struct KeyTable {
+    /// The name of the command line flag that prints the table
+    pub flag: &'static str,
+    /// A one line description of the flag
+    pub help: &'static str,
+    /// Writes out the table for a key written as a string
+    pub show: fn(&str) -> Result<String, Error>,
+}
+
+/// A cipher known to the registry
+pub struct Entry {
+    /// The name the cipher is looked up by
+    pub name: &'static str,
+    /// A one line description of the cipher
+    pub about: &'static str,
+    /// The flavours of the cipher. The first one is the default.
+    pub variants: &'static [Variant],
+    /// The settings the cipher accepts in its key
+    pub settings: &'static [Setting],
+    /// The table the cipher can print for its key, if any
+    pub key_table: Option<KeyTable>,
+}
+
+/// Every cipher in the crate
+pub const REGISTRY: &[Entry] = &[
+    Entry {
+        name: "vigenere",
+        about: "Standard, Autokey & Ciphertext autokey vigenere cipher, and the Beaufort, variant Beaufort and Gronsfeld (numeric key) ciphers",
+        variants: &[
+            Variant { name: "standard", build: build_boxed::<StandardVigenere> },
+            Variant { name: "autokey", build: build_boxed::<AutokeyVigenere> },
+            Variant { name: "ciphertext-autokey", build: build_boxed::<CiphertextAutokeyVigenere> },
+            Variant { name: "beaufort", build: build_boxed::<Beaufort> },
+            Variant { name: "variant-beaufort", build: build_boxed::<VariantBeaufort> },
+            Variant { name: "gronsfeld", build: build_boxed::<Gronsfeld> },
+        ],
+        settings: &[],
+        key_table: None,
+    },
+    Entry {
+        name: "running-key",
+        about: "Running key cipher, the Vigenere cipher with a key text as long as the message (the key text is given with --key or --key-file)",
+        variants: &[
+            Variant { name: "standard", build: build_boxed::<RunningKey> },
+        ],
+        settings: &[
+            Setting { name: "key-file", help: "File to read the key text from, such as a book" },
+            Setting { name: "offset", help: "Number of letters of the key text skipped before the key starts (default: 0)" },
+        ],
+        key_table: None,
+    },
+    Entry {
+        name: "porta",
+        about: "Porta's reciprocal cipher, with 13 alphabets each picked by two key letters",
+        variants: &[
+            Variant { name: "standard", build: build_boxed::<Porta> },
+        ],
+        settings: &[],
+        key_table: Some(KeyTable { flag: "show-tableau", help: "Print the tableau before the text", show: porta_tableau }),
+    },
+    Entry {
+        name: "tableau",
+        about: "Any polyalphabetic cipher given by a tableau of alphabets picked by the key letters",
+        variants: &[
+            Variant { name: "standard", build: build_boxed::<TableauCipher> },
+        ],
+        settings: &[
+            Setting { name: "tableau", help: "Built-in tableau: vigenere, beaufort, variant-beaufort or porta (default: vigenere)" },
+            Setting { name: "rows", help: "Rows of a tableau of your own, as KEYS:ALPHABET separated by commas, e.g. \"AB:NOPQ...,CD:ZNOP...\"" },
+        ],
+        key_table: Some(KeyTable { flag: "show-tableau", help: "Print the tableau before the text", show: tableau_table }),
+    },
+    Entry {
+        name: "quagmire",
+        about: "Quagmire I, II, III and IV ciphers, periodic ciphers sliding keyed alphabets (the key is the indicator keyword)",
+        variants: &[
+            Variant { name: "i", build: |key| Ok(Box::new(Quagmire::parse(Kind::I, key)?)) },
+            Variant { name: "ii", build: |key| Ok(Box::new(Quagmire::parse(Kind::II, key)?)) },
+            Variant { name: "iii", build: |key| Ok(Box::new(Quagmire::parse(Kind::III, key)?)) },
+            Variant { name: "iv", build: |key| Ok(Box::new(Quagmire::parse(Kind::IV, key)?)) },
+        ],
+        settings: &[
+            Setting { name: "keyword", help: "Keyword mixing the plain alphabet (i, iii, iv), or the cipher alphabet (ii)" },
+            Setting { name: "cipher-keyword", help: "Keyword mixing the cipher alphabet (iv)" },
+            Setting { name: "position", help: "Plain letter the indicator letters stand under (default: A for i and ii, the first plain letter for iii and iv)" },
+        ],
+        key_table: None,
+    },
+    Entry {
+        name: "hill",
+        about: "Hill cipher, multiplying blocks of letters by a key matrix modulo 26",
+        variants: &[
+            Variant { name: "standard", build: build_boxed::<Hill> },
+        ],
+        settings: &[
+            Setting { name: "pad", help: "Letter filling up the last block (default: X)" },
+        ],
+        key_table: Some(KeyTable { flag: "show-matrix", help: "Print the key matrix and its inverse before the text", show: hill_matrix }),
+    },
+    Entry {
+        name: "column-transposition",
+        about: "Column transposition cipher, with Myszkowski and disrupted variants",
+        variants: &[
+            Variant { name: "standard", build: build_boxed::<ColumnTransposition> },
+            Variant { name: "myszkowski", build: build_boxed::<MyszkowskiTransposition> },
+        ],
+        settings: &[
+            Setting { name: "fill", help: "How the text is written into the table: rows, columns or disrupted (default: rows)" },
+            Setting { name: "read", help: "How the columns are read out: down or up (default: down)" },
+        ],
+        key_table: None,
+    },
+    Entry {
+        name: "double-transposition",
+        about: "Double column transposition, with two independent keys",
+        variants: &[
+            Variant { name: "standard", build: build_boxed::<DoubleTransposition> },
+        ],
+        settings: &[
+            Setting { name: "second", help: "Keyphrase of the second transposition (the first is given with --key)" },
+        ],
+        key_table: None,
+    },
+    Entry {
+        name: "rail-fence",
+        about: "Rail fence cipher (the number of rails is given with --key)",
+        variants: &[
+            Variant { name: "standard", build: build_boxed::<RailFence> },
+        ],
+        settings: &[
+            Setting { name: "offset", help: "How many letters into the zigzag the text starts (default: 0)" },
+        ],
+        key_table: None,
+    },
+    Entry {
+        name: "route",
+        about: "Route transposition (the number of columns is given with --key)",
+        variants: &[
+            Variant { name: "standard", build: build_boxed::<RouteTransposition> },
+        ],
+        settings: &[
+            Setting { name: "path", help: "Route through the rectangle: spiral, boustrophedon, diagonal or snake (default: spiral)" },
+        ],
+        key_table: None,
+    },
+    Entry {
+        name: "playfair",
+        about: "Playfair digraphic cipher",
+        variants: &[
+            Variant { name: "standard", build: build_boxed::<Playfair> },
+        ],
+        settings: &[
+            Setting { name: "merge", help: "The letter left out of the square, then the letter it is written as (default: JI)" },
+            Setting { name: "pad", help: "The padding letter, then the one used next to it (default: XQ)" },
+        ],
+        key_table: Some(KeyTable { flag: "show-square", help: "Print the key square before the text", show: playfair_square }),
+    },
+    Entry {
+        name: "two-square",
+        about: "Wheatstone's two-square digraphic cipher",
+        variants: &[
+            Variant { name: "vertical", build: build_boxed::<TwoSquare> },
+            Variant { name: "horizontal", build: build_boxed::<HorizontalTwoSquare> },
+        ],
+        settings: &[
+            Setting { name: "second", help: "Keyphrase of the second square (the first is given with --key)" },
+            Setting { name: "merge", help: "The letter left out of the squares, then the letter it is written as (default: JI)" },
+            Setting { name: "pad", help: "The letter completing the last digraph (default: X)" },
+        ],
+        key_table: None,
+    },
+    Entry {
+        name: "four-square",
+        about: "Delastelle's four-square digraphic cipher",
+        variants: &[
+            Variant { name: "standard", build: build_boxed::<FourSquare> },
+        ],
+        settings: &[
+            Setting { name: "second", help: "Keyphrase of the bottom left square (the top right one is given with --key)" },
+            Setting { name: "merge", help: "The letter left out of the squares, then the letter it is written as (default: JI)" },
+            Setting { name: "pad", help: "The letter completing the last digraph (default: X)" },
+        ],
+        key_table: None,
+    },
+    Entry {
+        name: "polybius",
+        about: "Polybius square, replacing each letter with its row and column",
+        variants: &[
+            Variant { name: "standard", build: build_boxed::<Polybius> },
+        ],
+        settings: &[
+            Setting { name: "size", help: "5 for the letters, J written as I, or 6 for the letters and digits (default: 5)" },
+        ],
+        key_table: Some(KeyTable { flag: "show-square", help: "Print the key square before the text", show: polybius_square }),
+    },
+    Entry {
+        name: "bifid",
+        about: "Delastelle's Bifid fractionating cipher",
+        variants: &[
+            Variant { name: "standard", build: build_boxed::<Bifid> },
+        ],
+        settings: &[
+            Setting { name: "period", help: "Number of letters fractionated together, 0 for the whole message (default: 0)" },
+            Setting { name: "size", help: "5 for the letters, J written as I, or 6 for the letters and digits (default: 5)" },
+        ],
+        key_table: Some(KeyTable { flag: "show-square", help: "Print the key square before the text", show: bifid_square }),
+    },
+    Entry {
+        name: "trifid",
+        about: "Delastelle's Trifid fractionating cipher, with a 3×3×3 cube of the letters and +",
+        variants: &[
+            Variant { name: "standard", build: build_boxed::<Trifid> },
+        ],
+        settings: &[
+            Setting { name: "period", help: "Number of letters fractionated together, 0 for the whole message (default: 5)" },
+        ],
+        key_table: Some(KeyTable { flag: "show-cube", help: "Print the key cube, layer by layer, before the text", show: trifid_cube }),
+    },
+    Entry {
+        name: "adfgvx",
+        about: "ADFGVX and ADFGX ciphers: a Polybius square, then a column transposition under the keyphrase",
+        variants: &[
+            Variant { name: "adfgvx", build: build_boxed::<Adfgvx> },
+            Variant { name: "adfgx", build: build_boxed::<Adfgx> },
+        ],
+        settings: &[
+            Setting { name: "square", help: "Keyword mixing the square: 6×6 of the letters and digits (adfgvx), or 5×5 of the letters, J written as I (adfgx)" },
+        ],
+        key_table: None,
+    },
+    Entry {
+        name: "enigma",
+        about: "The Enigma rotor machine, which is its own inverse: the key is the start positions of the rotors (default: all A)",
+        variants: &[
+            Variant { name: "m3", build: build_boxed::<Enigma> },
+            Variant { name: "m4", build: build_boxed::<EnigmaM4> },
+        ],
+        settings: &[
+            Setting { name: "rotors", help: "Rotors from left to right, from I to VIII, with Beta or Gamma first on the m4 (default: I II III, or Beta I II III)" },
+            Setting { name: "reflector", help: "B or C on the m3, B-thin or C-thin on the m4 (default: B, or B-thin)" },
+            Setting { name: "rings", help: "Ring settings from left to right, as letters (BUL) or numbers (02 21 12) (default: all A)" },
+            Setting { name: "plugboard", help: "Pairs of letters swapped by the plugboard, e.g. \"AV BS CG\" (default: none)" },
+        ],
+        key_table: None,
+    },
+    Entry {
+        name: "substitution",
+        about: "Simple monoalphabetic substitution",
+        variants: &[
+            Variant { name: "standard", build: build_boxed::<Substitution> },
+        ],
+        settings: &[
+            Setting { name: "keyword", help: "Build the cipher alphabet from this keyword instead of giving it with --key" },
+            Setting { name: "mixing", help: "How the keyword mixes the alphabet: keyword or column (default: keyword)" },
+            Setting { name: "arrangement", help: "Which alphabet is mixed: k1 (plain), k2 (cipher) or k3 (both) (default: k2)" },
+            Setting { name: "shift", help: "How far the K3 cipher alphabet is shifted against the plain alphabet (default: 1)" },
+        ],
+        key_table: None,
+    },
+];
+
+impl Entry {
+    /// Finds the variant called `name`, or the default variant if `name` is `None`
+    pub fn variant(&self, name: Option<&str>) -> Option<&Variant> {
+        match name {
+            Some(name) => self.variants.iter().find(|v| v.name == name),
+            None => self.variants.first(),
+        }
+    }
+}
+
+/// Finds the cipher called `name` in the registry
+pub fn lookup(name: &str) -> Option<&'static Entry> {
+    REGISTRY.iter().find(|entry| entry.name == name)
+}
+
+/// Creates the cipher called `name`, in the flavour `variant`, with the key `key`
+///
+/// The default variant is used if `variant` is `None`
+pub fn build(name: &str, variant: Option<&str>, key: &str) -> Result<Box<dyn Cipher>, Error> {
+    let entry = lookup(name)
+        .ok_or_else(|| Error::KeyError(format!("Unknown cipher '{}'", name)))?;
+
+    let variant = entry.variant(variant)
+        .ok_or_else(|| Error::KeyError(format!("Unknown variant '{}' of cipher '{}'", variant.unwrap_or_default(), name)))?;
+
+    (variant.build)(key)
+}
+
+fn playfair_square(key: &str) -> Result<String, Error> {
+    Ok(Playfair::from_key_str(key)?.square().to_string())
+}
+
+fn porta_tableau(_key: &str) -> Result<String, Error> {
+    Ok(Tableau::porta().to_string())
+}
+
+fn tableau_table(key: &str) -> Result<String, Error> {
+    Ok(TableauCipher::from_key_str(key)?.tableau().to_string())
+}
+
+fn hill_matrix(key: &str) -> Result<String, Error> {
+    let hill = Hill::from_key_str(key)?;
+
+    Ok(format!("Key:\n{}\n\nInverse:\n{}", hill.key(), hill.key().inverse().unwrap()))
+}
+
+fn polybius_square(key: &str) -> Result<String, Error> {
+    Ok(Polybius::from_key_str(key)?.grid().to_string())
+}
+
+fn bifid_square(key: &str) -> Result<String, Error> {
+    Ok(Bifid::from_key_str(key)?.grid().to_string())
+}
+
+fn trifid_cube(key: &str) -> Result<String, Error> {
+    Ok(Trifid::from_key_str(key)?.grid().to_string())
+}
+
+fn build_boxed<C: KeyedCipher + 'static>(key: &str) -> Result<Box<dyn Cipher>, Error> {
+    Ok(Box::new(C::from_key_str(key)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cipher;
+    use crate::vigenere_autokey;
+    use crate::column_transposition;
+
+    #[test]
+    fn test_build() {
+        let autokey = cipher::build("vigenere", Some("autokey"), "ZZZ").unwrap();
+
+        assert_eq!(
+            autokey.encipher(b"AAAAAA").unwrap(),
+            vigenere_autokey::encipher(b"ZZZ", b"AAAAAA").unwrap()
+        );
+
+        let transposition = cipher::build("column-transposition", None, "ZEBRAS").unwrap();
+
+        assert_eq!(
+            transposition.decipher(b"EVLNA CDTES EAROF ODEEC WIREE").unwrap(),
+            column_transposition::decipher(b"ZEBRAS", b"EVLNA CDTES EAROF ODEEC WIREE").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_key_string() {
+        let key = cipher::KeyString::parse("SECRET; keyword=ZEBRAS;shift=3").unwrap();
+
+        assert_eq!(key.main, "SECRET");
+        assert_eq!(key.setting("keyword"), Some("ZEBRAS"));
+        assert_eq!(key.parsed_setting("shift", 1).unwrap(), 3);
+        assert_eq!(key.parsed_setting("rails", 2).unwrap(), 2);
+        assert_eq!(key.setting("mixing"), None);
+
+        assert!(cipher::KeyString::parse("SECRET;keyword").is_err());
+    }
+
+    #[test]
+    fn test_key_table() {
+        let table = cipher::lookup("playfair").unwrap().key_table.as_ref().unwrap();
+
+        assert_eq!((table.show)("PLAYFAIR EXAMPLE").unwrap(), "P L A Y F\nI R E X M\nB C D G H\nK N O Q S\nT U V W Z\n");
+
+        assert!(cipher::lookup("vigenere").unwrap().key_table.is_none());
+    }
+
+    #[test]
+    fn test_unknown_cipher() {
+        assert!(cipher::build("no-such-cipher", None, "KEY").is_err());
+        assert!(cipher::build("vigenere", Some("no-such-variant"), "KEY").is_err());
+    }
+}
@@ -62,11 +62,41 @@
 //! symbols (3 & 1) are longer. And so on.
 //! 
 //! 
+//! # Variants
+//!
+//! The table can be filled and read in other ways (see [`Layout`]):
+//!
+//! - The plain text can be written down the columns instead of along the rows ([`Fill::Columns`]).
+//! - In a _disrupted_ transposition ([`Fill::Disrupted`]), the table is first filled row by row,
+//!   but only up to a staircase of triangular _disruption areas_. The first area starts in the top
+//!   row, right of the column numbered 1, and narrows by one column on every row until a row is full.
+//!   The next area starts on the following row, right of the column numbered 2, and so on. The rest
+//!   of the plain text then fills the disruption areas, row by row.
+//! - The columns can be read from the bottom up ([`Read::Up`]).
+//!
+//! In a _Myszkowski_ transposition, repeated letters of the keyphrase get the same number
+//! (see [`create_myszkowski_key`]). Columns that share a number are read together, row by row,
+//! left to right:
+//!
+//! ```text
+//! T  O  M  A  T  O
+//! 4  3  2  1  4  3
+//! ----------------
+//! W  E  A  R  E  D
+//! I  S  C  O  V  E
+//! R  E  D  F  L  E
+//! E  A  T  O  N  C
+//! E
+//! ```
+//!
+//! Cipher text: ROFOA CDTED SEEEA CWEIV RLENE
+//!
 //! Enough talk. Let's get to work.
 
 
 use crate::errors::Error;
-use crate::common::{AsciiUppercaseByte, sanitize_text, format_output};
+use crate::cipher::{Cipher, KeyString, KeyedCipher};
+use crate::common::{AsciiUppercaseByte, sanitize_text, format_output, permute, unpermute};
 use std::collections::VecDeque;
 
 /// Enciphers `plain_text` with `keyphrase` using regular column transposition
@@ -76,6 +106,237 @@ pub fn encipher(keyphrase: &[u8], plain_text: &[u8]) -> Result<String, Error> {
 
     let plain_text = sanitize_text(plain_text)?;
 
+    Ok(format_output(transpose(&key, &plain_text)))
+}
+
+/// Deciphers `cipher_text` with `keyphrase` using regular columna transposition
+#[allow(clippy::needless_borrow)]
+pub fn decipher(keyphrase: &[u8], cipher_text: &[u8]) -> Result<String, Error> {
+    let key = create_key(&sanitize_text(keyphrase)?);
+
+    let cipher_text = sanitize_text(&cipher_text)?;
+
+    Ok(format_output(untranspose(&key, &cipher_text)))
+}
+
+/// Enciphers `plain_text` with `keyphrase` using Myszkowski transposition
+pub fn encipher_myszkowski(keyphrase: &[u8], plain_text: &[u8]) -> Result<String, Error> {
+    let key = create_myszkowski_key(&sanitize_text(keyphrase)?);
+
+    ColumnTransposition::with_key((key, Layout::default()))?.encipher(plain_text)
+}
+
+/// Deciphers `cipher_text` with `keyphrase` using Myszkowski transposition
+pub fn decipher_myszkowski(keyphrase: &[u8], cipher_text: &[u8]) -> Result<String, Error> {
+    let key = create_myszkowski_key(&sanitize_text(keyphrase)?);
+
+    ColumnTransposition::with_key((key, Layout::default()))?.decipher(cipher_text)
+}
+
+/// How the plain text is written into the table
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Fill {
+    /// Row by row, left to right
+    Rows,
+    /// Column by column, top to bottom
+    Columns,
+    /// Row by row, first around the triangular disruption areas and then into them
+    Disrupted,
+}
+
+/// Which way the columns are read out
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Read {
+    /// From the top row to the bottom row
+    Down,
+    /// From the bottom row to the top row
+    Up,
+}
+
+/// How a column transposition fills and reads its table
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Layout {
+    /// How the plain text is written in
+    pub fill: Fill,
+    /// How the columns are read out
+    pub read: Read,
+}
+
+impl Default for Layout {
+    /// Written in by rows, read out down the columns
+    fn default() -> Self {
+        Layout { fill: Fill::Rows, read: Read::Down }
+    }
+}
+
+/// Column transposition, for use through the [`Cipher`] trait
+///
+/// The key is the numeric key produced from a keyphrase (see [`create_key`]), and the layout
+/// of the table. It is written `KEYPHRASE;fill=rows|columns|disrupted;read=down|up`.
+pub struct ColumnTransposition {
+    key: Vec<usize>,
+    layout: Layout,
+}
+
+impl KeyedCipher for ColumnTransposition {
+    type Key = (Vec<usize>, Layout);
+
+    fn parse_key(key: &str) -> Result<Self::Key, Error> {
+        let key = KeyString::parse(key)?;
+
+        Ok((create_key(&sanitize_text(key.main.as_bytes())?), parse_layout(&key)?))
+    }
+
+    fn with_key((key, layout): Self::Key) -> Result<Self, Error> {
+        Ok(ColumnTransposition { key: non_empty(key)?, layout })
+    }
+}
+
+impl Cipher for ColumnTransposition {
+    fn encipher(&self, plain_text: &[u8]) -> Result<String, Error> {
+        let plain_text = sanitize_text(plain_text)?;
+
+        let order = reading_order(&self.key, plain_text.len(), &self.layout);
+
+        Ok(format_output(permute(&plain_text, &order)))
+    }
+
+    fn decipher(&self, cipher_text: &[u8]) -> Result<String, Error> {
+        let cipher_text = sanitize_text(cipher_text)?;
+
+        let order = reading_order(&self.key, cipher_text.len(), &self.layout);
+
+        Ok(format_output(unpermute(&cipher_text, &order)))
+    }
+}
+
+/// Myszkowski transposition, for use through the [`Cipher`] trait
+///
+/// The key is written like the key of [`ColumnTransposition`], but repeated letters of the
+/// keyphrase share a column number (see [`create_myszkowski_key`])
+pub struct MyszkowskiTransposition(ColumnTransposition);
+
+impl KeyedCipher for MyszkowskiTransposition {
+    type Key = (Vec<usize>, Layout);
+
+    fn parse_key(key: &str) -> Result<Self::Key, Error> {
+        let key = KeyString::parse(key)?;
+
+        Ok((create_myszkowski_key(&sanitize_text(key.main.as_bytes())?), parse_layout(&key)?))
+    }
+
+    fn with_key(key: Self::Key) -> Result<Self, Error> {
+        Ok(MyszkowskiTransposition(ColumnTransposition::with_key(key)?))
+    }
+}
+
+impl Cipher for MyszkowskiTransposition {
+    fn encipher(&self, plain_text: &[u8]) -> Result<String, Error> {
+        self.0.encipher(plain_text)
+    }
+
+    fn decipher(&self, cipher_text: &[u8]) -> Result<String, Error> {
+        self.0.decipher(cipher_text)
+    }
+}
+
+fn parse_layout(key: &KeyString) -> Result<Layout, Error> {
+    let fill = match key.setting("fill") {
+        None | Some("rows") => Fill::Rows,
+        Some("columns") => Fill::Columns,
+        Some("disrupted") => Fill::Disrupted,
+        Some(other) => return Err(Error::KeyError(format!("Unknown fill '{}'", other))),
+    };
+
+    let read = match key.setting("read") {
+        None | Some("down") => Read::Down,
+        Some("up") => Read::Up,
+        Some(other) => return Err(Error::KeyError(format!("Unknown read-out '{}'", other))),
+    };
+
+    Ok(Layout { fill, read })
+}
+
+fn non_empty(key: Vec<usize>) -> Result<Vec<usize>, Error> {
+    if key.is_empty() {
+        return Err(Error::KeyError("The keyphrase needs at least one letter".to_string()));
+    }
+
+    Ok(key)
+}
+
+/// Enciphers `plain_text` twice: first with `first_keyphrase`, then with `second_keyphrase`
+///
+/// This is the _double transposition_ used by both sides in the World Wars. The second pass
+/// scatters the columns of the first, so the cipher is much harder to anagram than a single pass.
+pub fn encipher_double(first_keyphrase: &[u8], second_keyphrase: &[u8], plain_text: &[u8]) -> Result<String, Error> {
+    DoubleTransposition::with_key(double_key(first_keyphrase, second_keyphrase)?)?.encipher(plain_text)
+}
+
+/// Reverses [`encipher_double`]: undoes the transposition with `second_keyphrase`, then the one with `first_keyphrase`
+pub fn decipher_double(first_keyphrase: &[u8], second_keyphrase: &[u8], cipher_text: &[u8]) -> Result<String, Error> {
+    DoubleTransposition::with_key(double_key(first_keyphrase, second_keyphrase)?)?.decipher(cipher_text)
+}
+
+/// Double column transposition, for use through the [`Cipher`] trait
+///
+/// The key is written `FIRST;second=SECOND`, with the keyphrases of the first and second pass
+pub struct DoubleTransposition {
+    first: Vec<usize>,
+    second: Vec<usize>,
+}
+
+impl KeyedCipher for DoubleTransposition {
+    type Key = (Vec<usize>, Vec<usize>);
+
+    fn parse_key(key: &str) -> Result<Self::Key, Error> {
+        let key = KeyString::parse(key)?;
+
+        double_key(key.main.as_bytes(), key.setting("second").unwrap_or("").as_bytes())
+    }
+
+    fn with_key((first, second): Self::Key) -> Result<Self, Error> {
+        if first.is_empty() || second.is_empty() {
+            return Err(Error::KeyError("Double transposition needs two keyphrases, written FIRST;second=SECOND".to_string()));
+        }
+
+        if !is_permutation(&first) || !is_permutation(&second) {
+            return Err(Error::KeyError("The keys of a double transposition must number their columns 0, 1, 2... once each".to_string()));
+        }
+
+        Ok(DoubleTransposition { first, second })
+    }
+}
+
+impl Cipher for DoubleTransposition {
+    fn encipher(&self, plain_text: &[u8]) -> Result<String, Error> {
+        let once = transpose(&self.first, &sanitize_text(plain_text)?);
+
+        Ok(format_output(transpose(&self.second, &once)))
+    }
+
+    fn decipher(&self, cipher_text: &[u8]) -> Result<String, Error> {
+        let once = untranspose(&self.second, &sanitize_text(cipher_text)?);
+
+        Ok(format_output(untranspose(&self.first, &once)))
+    }
+}
+
+// Numeric keys of both passes of a double transposition
+fn double_key(first_keyphrase: &[u8], second_keyphrase: &[u8]) -> Result<(Vec<usize>, Vec<usize>), Error> {
+    Ok((create_key(&sanitize_text(first_keyphrase)?), create_key(&sanitize_text(second_keyphrase)?)))
+}
+
+// Whether `key` holds each of the numbers 0 to its length - 1 once
+pub(crate) fn is_permutation(key: &[usize]) -> bool {
+    let mut sorted = key.to_vec();
+    sorted.sort_unstable();
+
+    sorted.iter().enumerate().all(|(i, &k)| i == k)
+}
+
+// Steps 2 to 4 of the enciphering process, with an already numeric key
+pub(crate) fn transpose(key: &[usize], plain_text: &[AsciiUppercaseByte]) -> Vec<AsciiUppercaseByte> {
     let mut tagged_text = Vec::new();
 
     // Step 2: Tag every character in the plan text with its column number
@@ -86,17 +347,12 @@ pub fn encipher(keyphrase: &[u8], plain_text: &[u8]) -> Result<String, Error> {
     // Step 3 & 4
     tagged_text.sort_by_key(|k| k.0);
 
-    let enciphered = tagged_text.iter().map(|x| x.1).collect::<Vec<AsciiUppercaseByte>>();
-
-    Ok(format_output(enciphered))
+    tagged_text.iter().map(|x| x.1).collect::<Vec<AsciiUppercaseByte>>()
 }
 
-/// Deciphers `cipher_text` with `keyphrase` using regular columna transposition
-pub fn decipher(keyphrase: &[u8], cipher_text: &[u8]) -> Result<String, Error> {
-    let key = create_key(&sanitize_text(keyphrase)?);
-
-    let cipher_text = sanitize_text(&cipher_text)?;
-
+// Reverses `transpose`
+#[allow(clippy::needless_range_loop)]
+pub(crate) fn untranspose(key: &[usize], cipher_text: &[AsciiUppercaseByte]) -> Vec<AsciiUppercaseByte> {
     // This represents matrix we will try to fill with our cipher text
     // It is a list of queues where the inner queue represents a single column
     // The outer list has `key-length` elements, since there are `key-length` columns
@@ -104,8 +360,6 @@ pub fn decipher(keyphrase: &[u8], cipher_text: &[u8]) -> Result<String, Error> {
 
     {
         // Our goal here is to place the cipher text in the correct columns.
-        let cipher_text = cipher_text.clone();
-
         let mut cursor = 0;
 
         for i in 0..key.len() {
@@ -148,8 +402,96 @@ pub fn decipher(keyphrase: &[u8], cipher_text: &[u8]) -> Result<String, Error> {
 
         deciphered.push(p);
     }
-    
-    Ok(format_output(deciphered))
+
+    deciphered
+}
+
+// For every letter of the cipher text, the position in the plain text of the letter written there
+//
+// Works for any layout, and for keys where several columns share a number (Myszkowski)
+pub(crate) fn reading_order(key: &[usize], length: usize, layout: &Layout) -> Vec<usize> {
+    let width = key.len();
+    let height = length.div_ceil(width);
+
+    // The table has the same shape whatever the fill: full rows, then a last row
+    // that only has its leftmost columns
+    let in_table = |&(row, column): &(usize, usize)| row * width + column < length;
+
+    let by_rows = (0..height)
+                    .flat_map(|row| (0..width).map(move |column| (row, column)))
+                    .filter(in_table);
+
+    let cells = match layout.fill {
+        Fill::Rows => by_rows.collect::<Vec<(usize, usize)>>(),
+        Fill::Columns => {
+            (0..width)
+                .flat_map(|column| (0..height).map(move |row| (row, column)))
+                .filter(in_table)
+                .collect()
+        },
+        Fill::Disrupted => {
+            let open = open_cells(key, height);
+
+            let (before, after): (Vec<_>, Vec<_>) = by_rows.partition(|&(row, column)| column <= open[row]);
+
+            before.into_iter().chain(after).collect()
+        },
+    };
+
+    // The position in the plain text of the letter in every cell
+    let mut table = vec![vec![None; width]; height];
+
+    for (i, &(row, column)) in cells.iter().enumerate() {
+        table[row][column] = Some(i);
+    }
+
+    let rows = match layout.read {
+        Read::Down => (0..height).collect::<Vec<usize>>(),
+        Read::Up => (0..height).rev().collect(),
+    };
+
+    let mut numbers = key.to_vec();
+    numbers.sort_unstable();
+    numbers.dedup();
+
+    let mut order = Vec::with_capacity(length);
+
+    // Columns that share a number are read together, row by row
+    for number in numbers {
+        let columns = (0..width).filter(|&column| key[column] == number).collect::<Vec<usize>>();
+
+        for &row in &rows {
+            order.extend(columns.iter().filter_map(|&column| table[row][column]));
+        }
+    }
+
+    order
+}
+
+// For every row of a disrupted table, the last column filled before the disruption areas
+fn open_cells(key: &[usize], height: usize) -> Vec<usize> {
+    let width = key.len();
+
+    let mut numbers = key.to_vec();
+    numbers.sort_unstable();
+    numbers.dedup();
+
+    let mut open = Vec::with_capacity(height);
+
+    for &number in numbers.iter().cycle() {
+        if open.len() >= height {
+            break;
+        }
+
+        // The area starts right of the column numbered `number`, and closes one column per row
+        let start = key.iter().position(|&k| k == number).unwrap();
+
+        open.extend(start..width);
+    }
+
+    open.truncate(height);
+
+    open
 }
 
 /// Create a column transposition key out of a keyphrase
@@ -161,7 +503,8 @@ pub fn decipher(keyphrase: &[u8], cipher_text: &[u8]) -> Result<String, Error> {
 /// - The key phrase "BACD" corresponds to the key  "1023"
 /// 
 /// - The key phrase "BAACDD" corresponds to the key "201345"
-fn create_key(keyphrase: &[AsciiUppercaseByte]) -> Vec<usize> {
+#[allow(clippy::needless_range_loop)]
+pub fn create_key(keyphrase: &[AsciiUppercaseByte]) -> Vec<usize> {
     let keyphrase = keyphrase.iter().map(|x| x.get_byte()).collect::<Vec<u8>>();
 
     let mut sorted_keyphrase = keyphrase.clone();
@@ -184,10 +527,30 @@ fn create_key(keyphrase: &[AsciiUppercaseByte]) -> Vec<usize> {
     key
 }
 
+/// Create a Myszkowski transposition key out of a keyphrase
+///
+/// Unlike [`create_key`], repeated letters get the same number.
+///
+/// # Examples:
+///
+/// - The key phrase "TOMATO" corresponds to the key "321032"
+pub fn create_myszkowski_key(keyphrase: &[AsciiUppercaseByte]) -> Vec<usize> {
+    let mut letters = keyphrase.iter().map(|x| x.get_byte()).collect::<Vec<u8>>();
+
+    letters.sort_unstable();
+    letters.dedup();
+
+    keyphrase.iter()
+        .map(|letter| letters.iter().position(|&x| x == letter.get_byte()).unwrap())
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use crate::common;
-    use crate::column_transposition::{create_key, encipher, decipher};
+    use crate::cipher::{Cipher, KeyedCipher};
+    use crate::column_transposition::{create_key, create_myszkowski_key, encipher, decipher, encipher_double, decipher_double};
+    use crate::column_transposition::{encipher_myszkowski, decipher_myszkowski, ColumnTransposition, DoubleTransposition, Fill, Layout, Read};
     use quickcheck::quickcheck;
 
     #[test]
@@ -239,7 +602,125 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_double_transposition() {
+        let once = encipher(b"ZEBRAS", b"WE ARE DISCOVERED. FLEE AT ONCE").unwrap();
+        let twice = encipher(b"STRIPE", once.as_bytes()).unwrap();
+
+        let enciphered = encipher_double(b"ZEBRAS", b"STRIPE", b"WE ARE DISCOVERED. FLEE AT ONCE").unwrap();
+
+        assert_eq!(enciphered, twice);
+
+        assert_eq!(
+            decipher_double(b"ZEBRAS", b"STRIPE", enciphered.as_bytes()).unwrap(),
+            "WEARE DISCO VERED FLEEA TONCE"
+        );
+    }
+
+    #[test]
+    fn test_myszkowski() {
+        let key_phrase = common::sanitize_text(b"TOMATO").unwrap();
+
+        assert_eq!(create_myszkowski_key(&key_phrase), vec![3, 2, 1, 0, 3, 2]);
+
+        let enciphered = encipher_myszkowski(b"TOMATO", b"WE ARE DISCOVERED. FLEE AT ONCE").unwrap();
+
+        assert_eq!(enciphered, "ROFOA CDTED SEEEA CWEIV RLENE");
+
+        assert_eq!(
+            decipher_myszkowski(b"TOMATO", enciphered.as_bytes()).unwrap(),
+            "WEARE DISCO VERED FLEEA TONCE"
+        );
+    }
+
+    #[test]
+    fn test_disrupted() {
+        // 5 2 1 3 0 4     The first disruption area starts right of the column numbered 0 (A),
+        // -----------     the second right of the column numbered 1 (B)
+        // W E A R E|A
+        // D I S C O V
+        // E R E|T O N
+        // D F L E|C E
+        // E
+        let cipher = ColumnTransposition::from_key_str("ZEBRAS;fill=disrupted").unwrap();
+
+        let enciphered = cipher.encipher(b"WE ARE DISCOVERED. FLEE AT ONCE").unwrap();
+
+        assert_eq!(enciphered, "EOOCA SELEI RFRCT EAVNE WDEDE");
+
+        assert_eq!(cipher.decipher(enciphered.as_bytes()).unwrap(), "WEARE DISCO VERED FLEEA TONCE");
+    }
+
+    #[test]
+    fn test_read_up_and_fill_columns() {
+        let cipher = ColumnTransposition::from_key_str("CAB;read=up").unwrap();
+
+        assert_eq!(cipher.encipher(b"ATTACK AT DAWN").unwrap(), "WTCTN DKTAA AA");
+
+        let cipher = ColumnTransposition::from_key_str("CAB;fill=columns").unwrap();
+
+        // 2 0 1
+        // -----
+        // A C D
+        // T K A
+        // T A W
+        // A T N
+        assert_eq!(cipher.encipher(b"ATTACK AT DAWN").unwrap(), "CKATD AWNAT TA");
+
+        assert!(ColumnTransposition::from_key_str("CAB;fill=diagonal").is_err());
+        assert!(ColumnTransposition::from_key_str(";read=up").is_err());
+
+        // Keys built in code are checked too
+        assert!(ColumnTransposition::with_key((Vec::new(), Layout::default())).is_err());
+        assert!(DoubleTransposition::with_key((vec![1, 0], vec![0, 0])).is_err());
+        assert!(DoubleTransposition::with_key((vec![1, 0], Vec::new())).is_err());
+    }
+
     quickcheck! {
+        fn layouts_round_trip(plain_text: Vec<u8>, key_phrase: Vec<u8>, fill: u8, up: bool) -> bool {
+            let key = create_key(&common::sanitize_text(&key_phrase).unwrap());
+
+            if key.is_empty() {
+                return true;
+            }
+
+            let fill = [Fill::Rows, Fill::Columns, Fill::Disrupted][fill as usize % 3];
+            let read = if up { Read::Up } else { Read::Down };
+
+            let cipher = ColumnTransposition::with_key((key, Layout { fill, read })).unwrap();
+
+            let enciphered = cipher.encipher(&plain_text).unwrap();
+
+            cipher.decipher(enciphered.as_bytes()).unwrap() == common::format_output(common::sanitize_text(&plain_text).unwrap())
+        }
+
+        fn default_layout_is_regular_transposition(plain_text: Vec<u8>, key_phrase: Vec<u8>) -> bool {
+            let key = create_key(&common::sanitize_text(&key_phrase).unwrap());
+
+            if key.is_empty() {
+                return true;
+            }
+
+            let cipher = ColumnTransposition::with_key((key.clone(), Layout::default())).unwrap();
+
+            let plain_text = common::sanitize_text(&plain_text).unwrap();
+
+            cipher.encipher(&plain_text.iter().map(|l| l.get_byte()).collect::<Vec<u8>>()).unwrap()
+                == common::format_output(super::transpose(&key, &plain_text))
+        }
+
+        fn double_transposition_round_trip(plain_text: Vec<u8>, first: Vec<u8>, second: Vec<u8>) -> bool {
+            if common::sanitize_text(&first).unwrap().is_empty() || common::sanitize_text(&second).unwrap().is_empty() {
+                return true;
+            }
+
+            let enciphered = encipher_double(&first, &second, &plain_text).unwrap();
+
+            decipher_double(&first, &second, enciphered.as_bytes()).unwrap()
+                == common::format_output(common::sanitize_text(&plain_text).unwrap())
+        }
+
+        #[allow(clippy::needless_range_loop)]
         fn key_is_always_increasing(key_phrase: Vec<u8>) -> bool {
             let key_phrase = common::sanitize_text(&key_phrase).unwrap();
 
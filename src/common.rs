@@ -1,7 +1,8 @@
 //! Common operations
 
-use std::ops::{Add, Sub};
+use std::ops::{Add, Mul, Sub};
 use std::convert::TryFrom;
+use crate::alphabet::{Alphabet, Symbol, STANDARD_LEN};
 use crate::errors;
 
 /// A byte that is guaranteed to in the range A-Z
@@ -13,18 +14,38 @@ impl AsciiUppercaseByte {
     pub fn get_byte(&self) -> u8 {
         self.0
     }
+
+    /// Returns the position of the letter in the alphabet, counting from A as 0
+    pub fn index(&self) -> usize {
+        (self.0 - b'A') as usize
+    }
+
+    /// Returns the letter at position `index` in the alphabet, counting from A as 0
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is not less than 26
+    pub fn from_index(index: usize) -> AsciiUppercaseByte {
+        assert!(index < STANDARD_LEN, "No letter at index {}", index);
+
+        AsciiUppercaseByte(b'A' + index as u8)
+    }
+}
+
+// The letters A-Z are the symbols of the standard alphabet, and add, subtract and multiply as they do
+impl From<Symbol> for AsciiUppercaseByte {
+    fn from(symbol: Symbol) -> Self {
+        debug_assert_eq!(symbol.alphabet_len(), STANDARD_LEN);
+
+        AsciiUppercaseByte::from_index(symbol.index())
+    }
 }
 
 impl Add for AsciiUppercaseByte {
     type Output = AsciiUppercaseByte;
 
     fn add(self, other: Self) -> Self {
-        let me = self.0 - b'A';
-        let other = other.0 - b'A';
-
-        let result = (me + other) % 26;
-
-        AsciiUppercaseByte(result + b'A')
+        AsciiUppercaseByte::from(Symbol::from(self) + Symbol::from(other))
     }
 }
 
@@ -32,18 +53,23 @@ impl Sub for AsciiUppercaseByte {
     type Output = AsciiUppercaseByte;
 
     fn sub(self, other: Self) -> Self {
-        let me = self.0 - b'A';
-        let other = 26 - (other.0 - b'A');
+        AsciiUppercaseByte::from(Symbol::from(self) - Symbol::from(other))
+    }
+}
 
-        let result = (me + other) % 26;
+// Multiplies the letters as numbers modulo 26, A being 0
+impl Mul for AsciiUppercaseByte {
+    type Output = AsciiUppercaseByte;
 
-        AsciiUppercaseByte(result + b'A')
+    fn mul(self, other: Self) -> Self {
+        AsciiUppercaseByte::from(Symbol::from(self) * Symbol::from(other))
     }
 }
 
 impl TryFrom<u8> for AsciiUppercaseByte {
     type Error = errors::Error;
 
+    #[allow(clippy::manual_range_contains)]
     fn try_from(value: u8) -> Result<Self, Self::Error> {
         if value >= b'A' && value <= b'Z' {
             Ok(Self(value))
@@ -54,38 +80,240 @@ impl TryFrom<u8> for AsciiUppercaseByte {
     }
 }
 
+impl From<AsciiUppercaseByte> for char {
+    fn from(value: AsciiUppercaseByte) -> char {
+        value.0 as char
+    }
+}
+
 /// Uppercases, and removes any non-alphabetic characters from the text
+///
+/// This is [`sanitize_text_with`] the standard A-Z alphabet
 pub fn sanitize_text(input: &[u8]) -> Result<Vec<AsciiUppercaseByte>, errors::Error> {
-    // Uppercase everything
-    let uppercased = input.to_ascii_uppercase();
+    Ok(sanitize_text_with(input, &Alphabet::standard())
+        .into_iter()
+        .map(AsciiUppercaseByte::from)
+        .collect())
+}
 
-    // Remove any non-alphabetic characters
-    let filtered = uppercased.into_iter()
-                                .filter(|&elem| { elem >= b'A' && elem <= b'Z' })
-                                .map(|elem| Ok(AsciiUppercaseByte::try_from(elem)?))
-                                .collect::<Result<Vec<AsciiUppercaseByte>, errors::Error>>();
+/// Uppercases, and removes any character that is not a symbol of `alphabet` from the text
+///
+/// Invalid UTF-8 sequences are ignored
+pub fn sanitize_text_with(input: &[u8], alphabet: &Alphabet) -> Vec<Symbol> {
+    String::from_utf8_lossy(input)
+        .chars()
+        .filter_map(|c| alphabet.symbol_of(c))
+        .collect()
+}
+
+/// Converts A-Z text to symbols of the standard alphabet
+pub fn to_symbols(text: &[AsciiUppercaseByte]) -> Vec<Symbol> {
+    text.iter().map(|&letter| Symbol::from(letter)).collect()
+}
 
-    filtered
+// Reads `text` in the order given by `order`: the ith letter of the result is `text[order[i]]`
+//
+// This is how every transposition enciphers, once it has worked out its reading order
+pub(crate) fn permute(text: &[AsciiUppercaseByte], order: &[usize]) -> Vec<AsciiUppercaseByte> {
+    order.iter().map(|&i| text[i]).collect()
+}
+
+// Reverses `permute`
+pub(crate) fn unpermute(text: &[AsciiUppercaseByte], order: &[usize]) -> Vec<AsciiUppercaseByte> {
+    let mut result = text.to_vec();
+
+    for (&i, &letter) in order.iter().zip(text) {
+        result[i] = letter;
+    }
+
+    result
+}
+
+/// Whether letters are written in upper or lower case
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LetterCase {
+    Upper,
+    Lower,
+}
+
+/// Controls how [`format_output_with`] lays out text
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OutputFormat {
+    /// Number of letters in a group. `None`, or `Some(0)`, writes the letters without any spaces.
+    pub group_size: Option<usize>,
+    /// Number of groups on a line. `None`, or `Some(0)`, writes everything on a single line.
+    ///
+    /// Without groups, this is the number of letters on a line.
+    pub groups_per_line: Option<usize>,
+    /// The case letters are written in
+    pub case: LetterCase,
+    /// Whether each line starts with its line number
+    pub number_lines: bool,
+}
+
+impl Default for OutputFormat {
+    /// Uppercase groups of 5, with 5 groups on a line
+    fn default() -> Self {
+        OutputFormat {
+            group_size: Some(5),
+            groups_per_line: Some(5),
+            case: LetterCase::Upper,
+            number_lines: false,
+        }
+    }
+}
+
+impl OutputFormat {
+    /// Uppercase letters, all run together on a single line
+    pub fn raw() -> Self {
+        OutputFormat {
+            group_size: None,
+            groups_per_line: None,
+            ..OutputFormat::default()
+        }
+    }
 }
 
 /// Format output for pretty-printing to the console
-pub fn format_output(output: Vec<AsciiUppercaseByte>) -> String {
-    // Split the output into chunks of 5 bytes seperated by a space
-    let mut formatted = Vec::new();
+pub fn format_output<T: Into<char>>(output: Vec<T>) -> String {
+    format_output_with(output, &OutputFormat::default())
+}
+
+/// Format output for pretty-printing to the console, laid out according to `format`
+pub fn format_output_with<T: Into<char>>(output: Vec<T>, format: &OutputFormat) -> String {
+    let group_size = format.group_size.filter(|&size| size > 0);
+    let line_length = format.groups_per_line
+                        .map(|groups| groups * group_size.unwrap_or(1))
+                        .filter(|&length| length > 0);
+
+    let line_count = match line_length {
+        Some(length) => output.len().saturating_sub(1) / length + 1,
+        None => 1,
+    };
+    let number_width = line_count.to_string().len();
+
+    // Split the output into groups seperated by a space
+    let mut formatted = String::new();
+
+    if format.number_lines {
+        formatted.push_str(&format!("{:>width$}  ", 1, width = number_width));
+    }
 
     for (i, value) in output.into_iter().enumerate() {
-        // Add white space every 5 elements
-        if i % 5 == 0 && i != 0 { 
-            formatted.push(b' ');
+        // Add white space between groups
+        if let Some(size) = group_size {
+            if i % size == 0 && i != 0 {
+                formatted.push(' ');
+            }
         }
 
-        // Add a new line every 25 elements
-        if i % 25 == 0 && i!= 0 {
-            formatted.push(b'\n');
+        // Add a new line at the end of every line
+        if let Some(length) = line_length {
+            if i % length == 0 && i != 0 {
+                formatted.push('\n');
+
+                if format.number_lines {
+                    formatted.push_str(&format!("{:>width$}  ", i / length + 1, width = number_width));
+                }
+            }
         }
 
-        formatted.push(value.get_byte());
+        let value = value.into();
+
+        match format.case {
+            LetterCase::Upper => formatted.push(value),
+            LetterCase::Lower => formatted.extend(value.to_lowercase()),
+        }
     }
 
-    String::from_utf8(formatted).unwrap()
-}
\ No newline at end of file
+    formatted
+}
+
+/// Starts every line of `text` with its line number, as [`format_output_with`] does
+///
+/// This is for text that keeps its own layout, like the output of passthrough mode.
+pub fn number_lines(text: &str) -> String {
+    let number_width = text.lines().count().to_string().len();
+
+    let mut numbered = text.lines()
+                        .enumerate()
+                        .map(|(i, line)| format!("{:>width$}  {}", i + 1, line, width = number_width))
+                        .collect::<Vec<String>>()
+                        .join("\n");
+
+    // A final line break ends the last line, and does not start a new one
+    if text.ends_with('\n') {
+        numbered.push('\n');
+    }
+
+    numbered
+}
+
+/// Lays out already formatted text again according to `format`
+///
+/// Whitespace is dropped, every other character is kept.
+pub fn reformat(text: &str, format: &OutputFormat) -> String {
+    format_output_with(text.chars().filter(|c| !c.is_whitespace()).collect(), format)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::alphabet::Alphabet;
+    use crate::common::{self, LetterCase, OutputFormat};
+    use quickcheck::quickcheck;
+
+    #[test]
+    fn test_sanitize_text_is_ascii_only() {
+        let text = common::sanitize_text("Ça ıſ ñ-ok".as_bytes()).unwrap();
+
+        assert_eq!(common::format_output(text), "AOK");
+    }
+
+    quickcheck! {
+        fn arithmetic_is_that_of_the_standard_alphabet(a: u8, b: u8) -> bool {
+            let standard = Alphabet::standard();
+
+            let (x, y) = (common::sanitize_text(&[b'A' + a % 26]).unwrap()[0], common::sanitize_text(&[b'A' + b % 26]).unwrap()[0]);
+            let (s, t) = (standard.symbol((a % 26) as usize), standard.symbol((b % 26) as usize));
+
+            common::to_symbols(&[x + y, x - y, x * y]) == [s + t, s - t, s * t]
+        }
+    }
+
+    #[test]
+    fn test_format_output_with() {
+        let text = common::sanitize_text(b"ABCDEFGHIJKLMN").unwrap();
+
+        let format = OutputFormat { group_size: Some(4), groups_per_line: Some(2), ..OutputFormat::default() };
+
+        assert_eq!(common::format_output_with(text.clone(), &format), "ABCD EFGH \nIJKL MN");
+
+        let format = OutputFormat { group_size: None, groups_per_line: Some(6), case: LetterCase::Lower, number_lines: true };
+
+        assert_eq!(common::format_output_with(text.clone(), &format), "1  abcdef\n2  ghijkl\n3  mn");
+
+        assert_eq!(common::format_output_with(text.clone(), &OutputFormat::raw()), "ABCDEFGHIJKLMN");
+
+        // Groups of 0 letters are no groups at all, and lines of 0 groups a single line
+        let format = OutputFormat { group_size: Some(0), groups_per_line: Some(0), ..OutputFormat::default() };
+
+        assert_eq!(common::format_output_with(text, &format), "ABCDEFGHIJKLMN");
+    }
+
+    #[test]
+    fn test_number_lines() {
+        assert_eq!(common::number_lines("Meet me\nat the\n\ngate!\n"), "1  Meet me\n2  at the\n3  \n4  gate!\n");
+
+        let text = "x\n".repeat(10);
+
+        assert_eq!(common::number_lines(&text).lines().last(), Some("10  x"));
+        assert_eq!(common::number_lines(&text).lines().next(), Some(" 1  x"));
+    }
+
+    #[test]
+    fn test_reformat() {
+        let format = OutputFormat { group_size: Some(6), groups_per_line: None, ..OutputFormat::default() };
+
+        assert_eq!(common::reformat("GMLML RWIMG BIYMG\nEEJVS", &format), "GMLMLR WIMGBI YMGEEJ VS");
+    }
+}
@@ -0,0 +1,65 @@
+//! # Automated attacks on the ciphers implemented in this crate
+//!
+//! Each sub-module breaks one cipher system and returns a list of [`Candidate`] solutions,
+//! ranked from most to least likely.
+
+pub mod adfgvx;
+pub mod autokey;
+pub mod bombe;
+pub mod column_transposition;
+pub mod hill;
+pub mod playfair;
+pub mod quagmire;
+pub mod rail_fence;
+pub mod route;
+pub mod running_key;
+pub mod substitution;
+pub mod vigenere;
+
+/// A possible solution to a cryptogram
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candidate {
+    /// The recovered key
+    pub key: String,
+    /// The cipher text deciphered under `key`
+    pub plain_text: String,
+    /// How plausible this candidate is. Higher is better.
+    ///
+    /// Scores are only comparable between candidates produced by the same attack
+    pub score: f64,
+}
+
+/// How long a randomised search runs, and where it starts
+///
+/// All randomness comes from a generator seeded with `seed`, so the same settings always give
+/// the same answer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchSettings {
+    /// Seed of the random number generator
+    pub seed: u64,
+    /// Number of times the search starts over from a different key
+    pub restarts: usize,
+    /// Number of key changes tried after each restart
+    pub iterations: usize,
+}
+
+impl Default for SearchSettings {
+    /// 20 restarts of 5000 iterations, with seed 0
+    fn default() -> Self {
+        SearchSettings {
+            seed: 0,
+            restarts: 20,
+            iterations: 5000,
+        }
+    }
+}
+
+// The opening of Pride and Prejudice, which the solvers are tested on
+#[cfg(test)]
+pub(crate) const PLAIN_TEXT: &[u8] = b"It is a truth universally acknowledged, that a single man in possession \
+    of a good fortune, must be in want of a wife. However little known the feelings or views of such \
+    a man may be on his first entering a neighbourhood, this truth is so well fixed in the minds of the \
+    surrounding families, that he is considered as the rightful property of some one or other of their \
+    daughters. My dear Mr. Bennet, said his lady to him one day, have you heard that Netherfield Park is \
+    let at last? Mr. Bennet replied that he had not. But it is, returned she; for Mrs. Long has just been \
+    here, and she told me all about it.";
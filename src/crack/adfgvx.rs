@@ -0,0 +1,468 @@
+//! # Breaking the ADFGX and ADFGVX ciphers
+//!
+//! The cipher hides a Polybius substitution under a column transposition, and neither can be
+//! attacked on its own. What gives it away is that the substitution is _monoalphabetic_: once
+//! the columns are back in their places, every pair of letters stands for one plain letter, and
+//! the pairs repeat as often as the letters of English do.
+//!
+//! 1. For every number of columns, climb over orders of the columns, looking for the one whose
+//!    pairs have the highest index of coincidence. Pairs cut from the wrong places mix the
+//!    coordinates of different letters, and are close to random. The width whose best order has
+//!    the highest index of coincidence is kept ([`rank_widths`]).
+//! 2. The index of coincidence only says which columns go together. With an even number of
+//!    columns, the pairs of columns can be moved around as a whole without changing it. So the
+//!    climb goes on over the index of coincidence of _digrams_ of pairs, which is only high when
+//!    neighbouring pairs are the letters that follow each other in the plain text.
+//! 3. Match the pairs, from most to least frequent, with the letters of English. This gives a
+//!    first guess at the square.
+//! 4. Climb on the quadgram fitness of the text, swapping two cells of the square. When no swap
+//!    helps any more, the best square so far is kicked with a few random swaps, and the climb
+//!    goes on from there.
+//! 5. Rotating the order of the columns keeps almost every pair and digram, and shifts the text
+//!    by a letter or so. Every rotation is tried with the square found, and the best one kept.
+//!
+//! A few hundred letters of plain text are needed for the index of coincidence to stand out, and
+//! for frequency matching to give the climb a good start.
+
+use crate::adfgvx::{self, Adfgvx, Adfgx};
+use crate::analysis;
+use crate::cipher::{Cipher, KeyedCipher};
+use crate::common::{self, AsciiUppercaseByte};
+use crate::crack::{Candidate, SearchSettings};
+use crate::errors::Error;
+use crate::language_model::{LanguageModel, MAX_ORDER};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use std::convert::TryFrom;
+use std::ops::RangeInclusive;
+
+/// A good number of iterations for each restart
+pub const ITERATIONS: usize = 20_000;
+
+// Climbs over column orders for every width (each climb goes on until no neighbouring order helps)
+const WIDTH_RESTARTS: usize = 10;
+
+// Number of random swaps applied to the frequency-matched square at the start of every climb
+const PERTURBATIONS: usize = 4;
+
+// Number of random swaps that kick the climb out of a square no swap improves
+const KICK: usize = 3;
+
+/// A number of columns, and how monoalphabetic the pairs look under the best order found
+#[derive(Debug, Clone, PartialEq)]
+pub struct WidthEstimate {
+    /// Number of columns of the transposition
+    pub width: usize,
+    /// The best order found, as a numeric key of the transposition
+    pub key: Vec<usize>,
+    /// Index of coincidence of the pairs under `key`
+    pub index_of_coincidence: f64,
+    /// Index of coincidence of the digrams of pairs under `key`, which tells apart orders with the
+    /// same pairs
+    pub digram_index_of_coincidence: f64,
+}
+
+/// Attempts to break `cipher_text`, enciphered with the ADFGX or ADFGVX cipher, without knowing
+/// the key
+///
+/// `side` is the side of the square: 6 for ADFGVX, 5 for ADFGX. Every climb over the square
+/// yields one candidate, whose key can be given to the cipher of the same name: the
+/// transposition keyphrase, with the setting `square` holding the whole square, row by row.
+/// Candidates are returned best first, and a key is never reported twice.
+///
+/// Returns an error if the side is neither 5 nor 6, or if the text has letters other than the
+/// labels, or an odd number of letters.
+pub fn crack(cipher_text: &[u8], side: usize, widths: RangeInclusive<usize>, model: &LanguageModel, settings: &SearchSettings) -> Result<Vec<Candidate>, Error> {
+    let text = common::sanitize_text(cipher_text)?;
+
+    let coordinates = label_coordinates(&text, side)?;
+
+    if *widths.end() > 26 {
+        return Err(Error::KeyError(format!("Cannot search tables of more than 26 columns (asked for {})", widths.end())));
+    }
+
+    let estimate = match rank_widths(&text, side, widths, settings.seed)?.into_iter().next() {
+        Some(estimate) => estimate,
+        None => return Ok(Vec::new()),
+    };
+
+    let scorer = Scorer::new(model);
+
+    let cells = cells(&coordinates, &estimate.key, side);
+
+    let mut rng = StdRng::seed_from_u64(settings.seed);
+
+    let mut candidates: Vec<Candidate> = Vec::new();
+
+    for _ in 0..settings.restarts.max(1) {
+        let (decryption, _) = climb(&cells, side, &scorer, &mut rng, settings.iterations);
+
+        // A rotated order of the columns reads almost the same text, shifted by a letter or so.
+        // Only the ends of the text tell which rotation is right.
+        let (column_key, score) = (0..estimate.key.len())
+            .map(|shift| {
+                let mut rotated = estimate.key.clone();
+                rotated.rotate_left(shift);
+
+                let score = scorer.score(&self::cells(&coordinates, &rotated, side), &decryption);
+
+                (rotated, score)
+            })
+            .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+            .unwrap();
+
+        let key = format!(
+            "{};square={}",
+            column_key.iter().map(|&k| (b'A' + k as u8) as char).collect::<String>(),
+            decryption.iter().map(|&symbol| symbol as char).collect::<String>()
+        );
+
+        if candidates.iter().any(|c| c.key == key) {
+            continue;
+        }
+
+        let plain_text = if side == 6 {
+            Adfgvx::from_key_str(&key)?.decipher(cipher_text)?
+        } else {
+            Adfgx::from_key_str(&key)?.decipher(cipher_text)?
+        };
+
+        candidates.push(Candidate { key, plain_text, score });
+    }
+
+    candidates.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
+
+    Ok(candidates)
+}
+
+/// Ranks the numbers of columns in `widths` by how monoalphabetic the pairs of `text` can be
+/// made to look, best first
+///
+/// `side` is the side of the square, 6 for ADFGVX or 5 for ADFGX. The search is seeded with
+/// `seed`. Returns an error if the side is neither 5 nor 6, or if the text has letters other than
+/// the labels, or an odd number of letters.
+pub fn rank_widths(text: &[AsciiUppercaseByte], side: usize, widths: RangeInclusive<usize>, seed: u64) -> Result<Vec<WidthEstimate>, Error> {
+    let coordinates = label_coordinates(text, side)?;
+
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let mut estimates = widths
+        .filter(|&width| width > 1 && width <= text.len())
+        .map(|width| {
+            let mut best = WidthEstimate { width, key: Vec::new(), index_of_coincidence: f64::MIN, digram_index_of_coincidence: f64::MIN };
+
+            for _ in 0..WIDTH_RESTARTS {
+                let mut key = (0..width).collect::<Vec<usize>>();
+                key.shuffle(&mut rng);
+
+                let (key, _) = improve(key, |key| index_of_coincidence(&cells(&coordinates, key, side), side), &mut rng);
+                let (key, digrams) = improve(key, |key| digram_coincidence(&cells(&coordinates, key, side), side), &mut rng);
+                let current = index_of_coincidence(&cells(&coordinates, &key, side), side);
+
+                if (current, digrams) > (best.index_of_coincidence, best.digram_index_of_coincidence) {
+                    best = WidthEstimate { width, key, index_of_coincidence: current, digram_index_of_coincidence: digrams };
+                }
+            }
+
+            best
+        })
+        .collect::<Vec<WidthEstimate>>();
+
+    estimates.sort_by(|a, b| b.index_of_coincidence.partial_cmp(&a.index_of_coincidence).unwrap());
+
+    Ok(estimates)
+}
+
+// Climbs from `key` to an order of the columns that no neighbour improves, taking the first better
+// neighbour in a shuffled neighbourhood
+fn improve(mut key: Vec<usize>, objective: impl Fn(&[usize]) -> f64, rng: &mut StdRng) -> (Vec<usize>, f64) {
+    let mut current = objective(&key);
+
+    'climb: loop {
+        let mut candidates = neighbours(&key);
+        candidates.shuffle(rng);
+
+        for candidate in candidates {
+            let score = objective(&candidate);
+
+            if score > current {
+                current = score;
+                key = candidate;
+                continue 'climb;
+            }
+        }
+
+        return (key, current);
+    }
+}
+
+// Every order of the columns close to `key`: two columns swapped, or a run of columns moved
+// elsewhere (which includes rotating the whole order)
+fn neighbours(key: &[usize]) -> Vec<Vec<usize>> {
+    let width = key.len();
+
+    let mut neighbours = Vec::new();
+
+    for i in 0..width {
+        for j in i + 1..width {
+            let mut neighbour = key.to_vec();
+            neighbour.swap(i, j);
+            neighbours.push(neighbour);
+        }
+    }
+
+    for start in 0..width {
+        for end in start + 1..=width {
+            let mut rest = key.to_vec();
+            let run = rest.drain(start..end).collect::<Vec<usize>>();
+
+            for at in (0..=rest.len()).filter(|&at| at != start) {
+                let mut neighbour = rest.clone();
+                neighbour.splice(at..at, run.iter().copied());
+                neighbours.push(neighbour);
+            }
+        }
+    }
+
+    neighbours
+}
+
+// The row or column every letter of `text` labels
+fn label_coordinates(text: &[AsciiUppercaseByte], side: usize) -> Result<Vec<usize>, Error> {
+    if side != 5 && side != 6 {
+        return Err(Error::KeyError(format!("The square of the cipher is 5×5 (ADFGX) or 6×6 (ADFGVX), not {}×{}", side, side)));
+    }
+
+    let labels = adfgvx::labels(side);
+
+    if !text.len().is_multiple_of(2) {
+        return Err(Error::DecipheringError("ADFGVX cipher text must have an even number of letters".to_string()));
+    }
+
+    text.iter()
+        .map(|letter| {
+            labels.iter()
+                .position(|label| label == letter)
+                .ok_or_else(|| Error::DecipheringError(format!("{} is not a row or column label", char::from(*letter))))
+        })
+        .collect()
+}
+
+// The cell of the square every pair points to, once `coordinates` are untransposed with `key`
+//
+// This is `column_transposition::untranspose` followed by pairing, without building the table:
+// every column of the table is a run of the cipher text, and the first `length % width` columns
+// are one letter longer.
+fn cells(coordinates: &[usize], key: &[usize], side: usize) -> Vec<usize> {
+    let (width, length) = (key.len(), coordinates.len());
+
+    let mut starts = vec![0; width];
+    let mut cursor = 0;
+
+    for rank in 0..width {
+        let column = key.iter().position(|&k| k == rank).unwrap();
+
+        starts[column] = cursor;
+        cursor += length / width + usize::from(column < length % width);
+    }
+
+    let at = |i: usize| coordinates[starts[i % width] + i / width];
+
+    (0..length / 2).map(|i| at(2 * i) * side + at(2 * i + 1)).collect()
+}
+
+// Index of coincidence of the digrams of cells (every cell and the next)
+fn digram_coincidence(cells: &[usize], side: usize) -> f64 {
+    let symbols = side * side;
+    let mut counts = vec![0usize; symbols * symbols];
+
+    for digram in cells.windows(2) {
+        counts[digram[0] * symbols + digram[1]] += 1;
+    }
+
+    if cells.len() < 3 {
+        return 0.0;
+    }
+
+    let digrams = cells.len() - 1;
+
+    counts.iter().map(|&n| n * n.saturating_sub(1)).sum::<usize>() as f64 / (digrams * (digrams - 1)) as f64
+}
+
+// Index of coincidence of the cells
+fn index_of_coincidence(cells: &[usize], side: usize) -> f64 {
+    let mut counts = vec![0usize; side * side];
+
+    for &cell in cells {
+        counts[cell] += 1;
+    }
+
+    if cells.len() < 2 {
+        return 0.0;
+    }
+
+    counts.iter().map(|&n| n * n.saturating_sub(1)).sum::<usize>() as f64 / (cells.len() * (cells.len() - 1)) as f64
+}
+
+// Climbs from a perturbed frequency-matched square. Returns the best square found, as the plain
+// symbol in every cell, and its fitness.
+fn climb(cells: &[usize], side: usize, scorer: &Scorer, rng: &mut StdRng, iterations: usize) -> (Vec<u8>, f64) {
+    let mut decryption = frequency_key(cells, side);
+
+    for _ in 0..PERTURBATIONS {
+        decryption.swap(rng.gen_range(0, side * side), rng.gen_range(0, side * side));
+    }
+
+    let mut swaps = (0..side * side)
+        .flat_map(|i| (i + 1..side * side).map(move |j| (i, j)))
+        .collect::<Vec<(usize, usize)>>();
+
+    let mut best = (decryption.clone(), f64::MIN);
+    let mut tried = 0;
+
+    // Sweep through every swap of two cells until a whole sweep improves nothing, then kick the
+    // best square found so far with a few random swaps and sweep again
+    while tried < iterations {
+        let mut current = scorer.score(cells, &decryption);
+
+        loop {
+            let mut improved = false;
+
+            swaps.shuffle(rng);
+
+            for &(i, j) in &swaps {
+                if tried == iterations {
+                    break;
+                }
+
+                tried += 1;
+
+                decryption.swap(i, j);
+
+                let score = scorer.score(cells, &decryption);
+
+                if score > current {
+                    current = score;
+                    improved = true;
+                } else {
+                    decryption.swap(i, j);
+                }
+            }
+
+            if !improved || tried == iterations {
+                break;
+            }
+        }
+
+        if current > best.1 {
+            best = (decryption.clone(), current);
+        }
+
+        decryption = best.0.clone();
+
+        for _ in 0..KICK {
+            decryption.swap(rng.gen_range(0, side * side), rng.gen_range(0, side * side));
+        }
+    }
+
+    best
+}
+
+// Scores deciphered cells with the quadgram fitness of their letters
+//
+// The plain text of an ADFGVX message is mostly letters, and a digit is scored as a quadgram the
+// model has never seen. Otherwise the climb would do best by hiding awkward letters behind the
+// digits of the square: the fewer letters, the fewer log probabilities to add up.
+struct Scorer<'a> {
+    model: &'a LanguageModel,
+    digit_penalty: f64,
+}
+
+impl<'a> Scorer<'a> {
+    fn new(model: &'a LanguageModel) -> Scorer<'a> {
+        Scorer { model, digit_penalty: model.floor(MAX_ORDER) }
+    }
+
+    fn score(&self, cells: &[usize], decryption: &[u8]) -> f64 {
+        let text = cells.iter()
+            .filter_map(|&cell| AsciiUppercaseByte::try_from(decryption[cell]).ok())
+            .collect::<Vec<AsciiUppercaseByte>>();
+
+        self.model.fitness(&text) + (cells.len() - text.len()) as f64 * self.digit_penalty
+    }
+}
+
+// The symbols of the square: the letters (without J in a 5×5 square), then the digits
+fn plain_symbols(side: usize) -> Vec<u8> {
+    (b'A'..=b'Z')
+        .filter(|&letter| side == 6 || letter != b'J')
+        .chain((b'0'..=b'9').filter(|_| side == 6))
+        .collect()
+}
+
+// Matches the cells, from most to least frequent, with the letters of English. The digits of a
+// 6×6 square go to the rarest cells.
+fn frequency_key(cells: &[usize], side: usize) -> Vec<u8> {
+    let mut counts = vec![0usize; side * side];
+
+    for &cell in cells {
+        counts[cell] += 1;
+    }
+
+    let mut by_count = (0..side * side).collect::<Vec<usize>>();
+    by_count.sort_by(|&a, &b| counts[b].cmp(&counts[a]));
+
+    let mut by_frequency = plain_symbols(side);
+    by_frequency.sort_by(|&a, &b| frequency(b).partial_cmp(&frequency(a)).unwrap());
+
+    let mut decryption = vec![0; side * side];
+
+    for (&cell, &symbol) in by_count.iter().zip(&by_frequency) {
+        decryption[cell] = symbol;
+    }
+
+    decryption
+}
+
+// Frequency of `symbol` in English. Digits come after every letter.
+fn frequency(symbol: u8) -> f64 {
+    if symbol.is_ascii_uppercase() { analysis::ENGLISH_FREQUENCIES[(symbol - b'A') as usize] } else { -1.0 }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::adfgvx;
+    use crate::common;
+    use crate::crack::adfgvx::{crack, rank_widths, ITERATIONS};
+    use crate::crack::PLAIN_TEXT;
+    use crate::crack::SearchSettings;
+    use crate::language_model::LanguageModel;
+
+    #[test]
+    fn test_rank_widths() {
+        let cipher_text = adfgvx::encipher(b"PEMBERLEY", b"LONGBOURN", PLAIN_TEXT).unwrap();
+
+        let text = common::sanitize_text(cipher_text.as_bytes()).unwrap();
+
+        assert_eq!(rank_widths(&text, 6, 2..=12, 0).unwrap()[0].width, 9);
+
+        assert!(rank_widths(&text, 7, 2..=12, 0).is_err());
+    }
+
+    #[test]
+    fn test_crack() {
+        let cipher_text = adfgvx::encipher_adfgx(b"PEMBERLEY", b"NETHERFIELD", PLAIN_TEXT).unwrap();
+
+        let settings = SearchSettings { seed: 1, restarts: 3, iterations: ITERATIONS };
+
+        let candidates = crack(cipher_text.as_bytes(), 5, 6..=12, &LanguageModel::english(), &settings).unwrap();
+
+        let recovered = common::sanitize_text(candidates[0].plain_text.as_bytes()).unwrap();
+        let expected = common::sanitize_text(adfgvx::decipher_adfgx(b"PEMBERLEY", b"NETHERFIELD", cipher_text.as_bytes()).unwrap().as_bytes()).unwrap();
+
+        let wrong = recovered.iter().zip(&expected).filter(|(r, e)| r != e).count();
+
+        assert!(wrong <= 2, "{} letters wrong in {}", wrong, candidates[0].plain_text);
+    }
+}
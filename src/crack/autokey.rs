@@ -0,0 +1,317 @@
+//! # Breaking the autokey Vigenere ciphers
+//!
+//! The autokey key never repeats, so there is no period to find. Three weaknesses remain.
+//!
+//! **The priming key splits the text into chains.** With a priming key of length _L_, the plain
+//! letter at _i_ keys the letter at _i + L_. Each priming letter deciphers the first letter of a
+//! chain, which deciphers the next one _L_ letters on, and so on: the 26 choices for one priming
+//! letter fix every _L_th letter of the message ([`crack`]). For every length, each priming
+//! letter is first chosen for the letter frequencies of its chain, then the key is climbed a
+//! letter at a time, trying all 26 letters, on the n-gram fitness of the whole message (single
+//! letters to quadgrams).
+//!
+//! **The key is plain text.** A probable word dragged through the cipher text gives, at every
+//! place, the key letters that would put it there ([`drag_crib`]). At the right place, they are a
+//! piece of the priming key or of the message, and read like English. With a word at least as
+//! long as the priming key, the chains can be followed back to the start of the message and the
+//! priming key read off ([`crack_with_crib`]).
+//!
+//! **The ciphertext autokey gives its key away.** Past the priming key, every plain letter is its
+//! cipher letter minus the cipher letter _L_ before, so only the length of the priming key is
+//! unknown ([`crack_ciphertext_autokey`]). The priming letters only key one letter each: they
+//! are read backwards from the known part, each picked for the n-grams it starts, then climbed
+//! as above. They are a guess: any priming key gives some first _L_ letters, and only the
+//! statistics of the language speak for one over another.
+
+use crate::analysis;
+use crate::common::{self, AsciiUppercaseByte};
+use crate::crack::Candidate;
+use crate::errors::Error;
+use crate::language_model::{LanguageModel, MAX_ORDER};
+use crate::vigenere_autokey;
+
+/// A place a probable word might stand in the cipher text, and the key that would put it there
+#[derive(Debug, Clone, PartialEq)]
+pub struct CribPlacement {
+    /// Where the word starts, counting letters from 0
+    pub position: usize,
+    /// The key letters under the word
+    pub key: String,
+    /// How much the key letters look like English. Higher is better.
+    pub score: f64,
+}
+
+/// Attempts to break `cipher_text`, enciphered with the autokey Vigenere cipher, without
+/// knowing the priming key
+///
+/// Priming keys of 1 to `max_length` letters are tried. Candidates are returned best first, and
+/// a key is never reported twice.
+pub fn crack(cipher_text: &[u8], max_length: usize, model: &LanguageModel) -> Result<Vec<Candidate>, Error> {
+    let text = common::sanitize_text(cipher_text)?;
+
+    let candidates = (1..=max_length.min(text.len()))
+        .map(|length| {
+            let key = (0..length)
+                .map(|start| {
+                    (0..26)
+                        .max_by(|&a, &b| chain_score(&text, length, start, a).partial_cmp(&chain_score(&text, length, start, b)).unwrap())
+                        .unwrap()
+                })
+                .collect();
+
+            climb(key, model, |key| vigenere_autokey::decipher_symbols(key.to_vec(), text.clone()))
+        })
+        .collect();
+
+    to_candidates(candidates, cipher_text, vigenere_autokey::decipher)
+}
+
+/// Attempts to break `cipher_text`, enciphered with the ciphertext autokey Vigenere cipher,
+/// without knowing the priming key
+///
+/// Priming keys of 1 to `max_length` letters are tried. Candidates are returned best first, and
+/// a key is never reported twice. Past the priming key, the plain text of the right length is
+/// exact, but its first letters (and so the key) may not be.
+pub fn crack_ciphertext_autokey(cipher_text: &[u8], max_length: usize, model: &LanguageModel) -> Result<Vec<Candidate>, Error> {
+    let text = common::sanitize_text(cipher_text)?;
+
+    let candidates = (1..=max_length.min(text.len()))
+        .map(|length| {
+            // Everything past the priming key is known whatever it is
+            let mut plain = vigenere_autokey::decipher_ciphertext_symbols(&vec![AsciiUppercaseByte::from_index(0); length], &text);
+
+            for i in (0..length).rev() {
+                let end = (i + MAX_ORDER).min(plain.len());
+
+                plain[i] = (0..26)
+                    .map(AsciiUppercaseByte::from_index)
+                    .max_by(|&a, &b| {
+                        plain[i] = a;
+                        let score_a = (1..=end - i).map(|n| model.log_probability(&plain[i..i + n])).sum::<f64>();
+                        plain[i] = b;
+                        let score_b = (1..=end - i).map(|n| model.log_probability(&plain[i..i + n])).sum::<f64>();
+
+                        score_a.partial_cmp(&score_b).unwrap()
+                    })
+                    .unwrap();
+            }
+
+            let key = (0..length).map(|i| (text[i] - plain[i]).index()).collect();
+
+            climb(key, model, |key| vigenere_autokey::decipher_ciphertext_symbols(key, &text))
+        })
+        .collect();
+
+    to_candidates(candidates, cipher_text, vigenere_autokey::decipher_ciphertext_autokey)
+}
+
+/// Drags the probable word `crib` through `cipher_text`, enciphered with the autokey Vigenere
+/// cipher, and works out the key letters under it at every place
+///
+/// At the right place, the key letters are a piece of the priming key or of the message. The
+/// places are returned with the ones whose key looks most like English first.
+///
+/// The same goes for any cipher that adds its key to the plain text, such as the Vigenere
+/// cipher or the running key cipher.
+pub fn drag_crib(cipher_text: &[u8], crib: &[u8], model: &LanguageModel) -> Result<Vec<CribPlacement>, Error> {
+    let text = common::sanitize_text(cipher_text)?;
+    let crib = common::sanitize_text(crib)?;
+
+    if crib.is_empty() || crib.len() > text.len() {
+        return Ok(Vec::new());
+    }
+
+    let mut placements = text.windows(crib.len())
+        .enumerate()
+        .map(|(position, window)| {
+            let key = window.iter().zip(&crib).map(|(&c, &p)| c - p).collect::<Vec<AsciiUppercaseByte>>();
+
+            CribPlacement {
+                position,
+                score: model.ngram_fitness(crib.len().min(MAX_ORDER), &key),
+                key: key.iter().map(|&letter| char::from(letter)).collect(),
+            }
+        })
+        .collect::<Vec<CribPlacement>>();
+
+    placements.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
+
+    Ok(placements)
+}
+
+/// Breaks `cipher_text`, enciphered with the autokey Vigenere cipher, knowing that it holds
+/// the probable word `crib`
+///
+/// The word is tried at every place, with every priming key length from 1 to `max_length` that
+/// is no longer than the word. Candidates are returned best first, and a key is never reported
+/// twice.
+pub fn crack_with_crib(cipher_text: &[u8], crib: &[u8], max_length: usize, model: &LanguageModel) -> Result<Vec<Candidate>, Error> {
+    let text = common::sanitize_text(cipher_text)?;
+    let crib = common::sanitize_text(crib)?;
+
+    let mut keys = Vec::new();
+
+    for position in 0..(text.len() + 1).saturating_sub(crib.len()) {
+        for length in 1..=max_length.min(crib.len()) {
+            let mut plain = vec![None; position + length];
+
+            for (i, &letter) in crib[..length].iter().enumerate() {
+                plain[position + i] = Some(letter);
+            }
+
+            // Follow the chains back to the start: the plain letter at i keys the one at i + length
+            for i in (length..position + length).rev() {
+                plain[i - length] = plain[i].map(|letter| text[i] - letter);
+            }
+
+            let key = (0..length)
+                .map(|i| (text[i] - plain[i].unwrap()).index())
+                .collect::<Vec<usize>>();
+
+            let score = fitness(model, &vigenere_autokey::decipher_symbols(key.iter().map(|&k| AsciiUppercaseByte::from_index(k)).collect(), text.clone()));
+
+            keys.push((key, score));
+        }
+    }
+
+    to_candidates(keys, cipher_text, vigenere_autokey::decipher)
+}
+
+// How much the chain of letters starting at `start` looks like English when its priming letter is `letter`
+fn chain_score(text: &[AsciiUppercaseByte], length: usize, start: usize, letter: usize) -> f64 {
+    let mut key = AsciiUppercaseByte::from_index(letter);
+    let mut score = 0.0;
+
+    for &cipher_letter in text.iter().skip(start).step_by(length) {
+        let plain_letter = cipher_letter - key;
+
+        score += analysis::ENGLISH_FREQUENCIES[plain_letter.index()].ln();
+        key = plain_letter;
+    }
+
+    score
+}
+
+// Changes one letter of the priming key at a time, trying all 26, as long as the text deciphered by `decipher` gets
+// more like English. Returns the key and its fitness.
+fn climb<F>(mut key: Vec<usize>, model: &LanguageModel, decipher: F) -> (Vec<usize>, f64)
+    where F: Fn(&[AsciiUppercaseByte]) -> Vec<AsciiUppercaseByte> {
+    let fitness = |key: &[usize]| fitness(model, &decipher(&key.iter().map(|&k| AsciiUppercaseByte::from_index(k)).collect::<Vec<AsciiUppercaseByte>>()));
+
+    let mut best = fitness(&key);
+    let mut improved = true;
+
+    while improved {
+        improved = false;
+
+        for i in 0..key.len() {
+            let mut best_letter = key[i];
+
+            for letter in 0..26 {
+                key[i] = letter;
+
+                let score = fitness(&key);
+
+                if score > best {
+                    best = score;
+                    best_letter = letter;
+                    improved = true;
+                }
+            }
+
+            key[i] = best_letter;
+        }
+    }
+
+    (key, best)
+}
+
+// How much `text` looks like English, under n-grams of every length. The model may only keep the commonest quadgrams,
+// and the shorter n-grams still tell apart texts made of rarer ones.
+fn fitness(model: &LanguageModel, text: &[AsciiUppercaseByte]) -> f64 {
+    (1..=MAX_ORDER).map(|n| model.ngram_fitness(n, text)).sum()
+}
+
+// Turns keys and their scores into candidates, best first, each key once
+fn to_candidates<F>(mut keys: Vec<(Vec<usize>, f64)>, cipher_text: &[u8], decipher: F) -> Result<Vec<Candidate>, Error>
+    where F: Fn(&[u8], &[u8]) -> Result<String, Error> {
+    keys.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+
+    let mut candidates: Vec<Candidate> = Vec::new();
+
+    for (key, score) in keys {
+        let key = key.iter().map(|&k| char::from(AsciiUppercaseByte::from_index(k))).collect::<String>();
+
+        if candidates.iter().any(|c| c.key == key) {
+            continue;
+        }
+
+        candidates.push(Candidate {
+            plain_text: decipher(key.as_bytes(), cipher_text)?,
+            key,
+            score,
+        });
+    }
+
+    Ok(candidates)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::common;
+    use crate::crack::autokey;
+    use crate::crack::PLAIN_TEXT;
+    use crate::language_model::LanguageModel;
+    use crate::vigenere_autokey;
+
+    #[test]
+    fn test_crack() {
+        let cipher_text = vigenere_autokey::encipher(b"PEMBERLEY", PLAIN_TEXT).unwrap();
+
+        let candidates = autokey::crack(cipher_text.as_bytes(), 15, &LanguageModel::english()).unwrap();
+
+        assert_eq!(candidates[0].key, "PEMBERLEY");
+        assert_eq!(candidates[0].plain_text, vigenere_autokey::decipher(b"PEMBERLEY", cipher_text.as_bytes()).unwrap());
+    }
+
+    #[test]
+    fn test_crack_ciphertext_autokey() {
+        let cipher_text = vigenere_autokey::encipher_ciphertext_autokey(b"NETHERFIELD", PLAIN_TEXT).unwrap();
+
+        let candidates = autokey::crack_ciphertext_autokey(cipher_text.as_bytes(), 15, &LanguageModel::english()).unwrap();
+
+        // The length of the priming key is found, and with it everything after the first 11 letters
+        assert_eq!(candidates[0].key.len(), 11);
+
+        let recovered = common::sanitize_text(candidates[0].plain_text.as_bytes()).unwrap();
+
+        assert_eq!(recovered[11..], common::sanitize_text(PLAIN_TEXT).unwrap()[11..]);
+    }
+
+    #[test]
+    fn test_drag_crib() {
+        let cipher_text = vigenere_autokey::encipher(b"PEMBERLEY", PLAIN_TEXT).unwrap();
+
+        let placements = autokey::drag_crib(cipher_text.as_bytes(), b"NEIGHBOURHOOD", &LanguageModel::english()).unwrap();
+
+        // Under the word is the plain text nine letters before it
+        assert_eq!(placements[0].position, 162);
+        assert_eq!(placements[0].key, "ENTERINGANEIG");
+
+        assert!(autokey::drag_crib(cipher_text.as_bytes(), b"", &LanguageModel::english()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_crack_with_crib() {
+        let cipher_text = vigenere_autokey::encipher(b"PEMBERLEY", PLAIN_TEXT).unwrap();
+
+        let candidates = autokey::crack_with_crib(cipher_text.as_bytes(), b"NEIGHBOURHOOD", 20, &LanguageModel::english()).unwrap();
+
+        assert_eq!(candidates[0].key, "PEMBERLEY");
+
+        // A word shorter than the priming key can't give all of it away
+        let candidates = autokey::crack_with_crib(cipher_text.as_bytes(), b"TRUTH", 20, &LanguageModel::english()).unwrap();
+
+        assert!(candidates.iter().all(|c| c.key.len() <= 5));
+    }
+}
@@ -0,0 +1,624 @@
+//! # Breaking the Enigma with a crib: the Turing–Welchman bombe
+//!
+//! The bombe needs a _crib_, a stretch of plain text guessed to be in the message. Since the
+//! Enigma never enciphers a letter to itself, the crib cannot sit anywhere a crib letter meets the
+//! same cipher letter. Sliding it along the cipher text rules out most places
+//! ([`crib_positions`]).
+//!
+//! At a place that is left, every crib letter and the cipher letter under it are joined by the
+//! machine as set at that point of the message. Drawn as a graph of letters, with each link marked
+//! with its position in the crib, these pairs make the _menu_ ([`Menu`]).
+//!
+//! The bombe then tries every rotor order and every position of the rotors. Each link of the menu
+//! is a _scrambler_: the rotors and reflector, set as they would be at that letter, without the
+//! plugboard. Only the right rotor moves along the crib: the bombe takes the middle and left
+//! rotors to stand still, which holds for short menus in most positions. At each position it
+//! guesses what the most connected letter of the menu (the _test letter_) is plugged to, and
+//! follows what the guess implies through the scramblers:
+//!
+//! - if the test letter is plugged to `w`, and the menu links it to `b` through scrambler `S`,
+//!   then `b` is plugged to `S(w)`;
+//! - if `a` is plugged to `w`, then `w` is plugged to `a` (the _diagonal board_ of Welchman).
+//!
+//! A wrong guess usually implies that the test letter is plugged to every letter at once. When
+//! it does not, the bombe _stops_: the guess, or one of the letters it left out, is consistent
+//! with the whole menu, and the implications are part of the plugboard ([`Stop`]).
+//!
+//! The bombe finds the rotor order, the plugs of the letters of the menu, and the positions of the
+//! rotor cores, but not the rings: [`crack`] tries every ring of the right rotor on every stop,
+//! deciphers the whole message, and ranks the results with a [`LanguageModel`]. The best few are
+//! then completed: every ring of the middle and right rotors is tried, and plugs of letters outside the menu
+//! are added one at a time, as long as they improve the plain text. Rotor orders are searched in
+//! parallel, one thread per processor.
+//!
+//! The scramblers and the stepping along the menu are the bombe's own, but the wirings of the
+//! rotors and reflectors are those of [`enigma`], and the candidates are deciphered on its
+//! [`Machine`]. Only the three-rotor machine ([`Model::M3`]) is modelled. The crib should be
+//! placed when it can: trying every place multiplies the search by the length of the message.
+
+use crate::cipher::{Cipher, KeyedCipher};
+use crate::common::{self, AsciiUppercaseByte};
+use crate::crack::Candidate;
+use crate::enigma::{self, Enigma, Machine, Model, Wiring};
+use crate::errors::Error;
+use crate::language_model::LanguageModel;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+/// Longest stretch of a crib made into a menu. The longer the menu, the fewer false stops, but
+/// the likelier the middle rotor turns over inside it.
+pub const MAX_MENU_LENGTH: usize = 20;
+
+// Best candidates completed with the middle ring and more plugs
+const COMPLETED: usize = 10;
+
+/// What the bombe searches, and how
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BombeSettings {
+    /// The rotors to choose from. Every order of three of them is tried.
+    pub rotors: Vec<String>,
+    /// The reflectors to try
+    pub reflectors: Vec<String>,
+    /// Where the crib starts in the cipher text, or `None` to try every possible place
+    pub crib_position: Option<usize>,
+    /// Number of threads running the search
+    pub threads: usize,
+}
+
+impl Default for BombeSettings {
+    /// Rotors I to V with reflector B, the crib at any place, and one thread per processor
+    fn default() -> Self {
+        BombeSettings {
+            rotors: ["I", "II", "III", "IV", "V"].iter().map(|name| name.to_string()).collect(),
+            reflectors: vec!["B".to_string()],
+            crib_position: None,
+            threads: thread::available_parallelism().map_or(1, |count| count.get()),
+        }
+    }
+}
+
+/// The places where `crib` can start in `cipher_text`: those where no letter of the crib would
+/// be enciphered to itself
+pub fn crib_positions(cipher_text: &[u8], crib: &[u8]) -> Result<Vec<usize>, Error> {
+    let text = common::sanitize_text(cipher_text)?;
+    let crib = common::sanitize_text(crib)?;
+
+    if crib.is_empty() || crib.len() > text.len() {
+        return Ok(Vec::new());
+    }
+
+    Ok((0..=text.len() - crib.len())
+        .filter(|&position| crib.iter().zip(&text[position..]).all(|(a, b)| a != b))
+        .collect())
+}
+
+/// The letters of a crib joined to the cipher letters under them
+///
+/// Each link is a crib letter, the cipher letter it became, and its position in the crib.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Menu {
+    links: Vec<(AsciiUppercaseByte, AsciiUppercaseByte, usize)>,
+}
+
+impl Menu {
+    /// Joins each letter of `crib` to the letter of `cipher_text` in the same place
+    pub fn new(crib: &[AsciiUppercaseByte], cipher_text: &[AsciiUppercaseByte]) -> Menu {
+        Menu {
+            links: crib.iter().zip(cipher_text).enumerate().map(|(i, (&a, &b))| (a, b, i)).collect(),
+        }
+    }
+
+    /// The links of the menu
+    pub fn links(&self) -> &[(AsciiUppercaseByte, AsciiUppercaseByte, usize)] {
+        &self.links
+    }
+
+    /// The letter in the most links, whose plug the bombe guesses
+    pub fn test_letter(&self) -> AsciiUppercaseByte {
+        let degrees = self.degrees();
+
+        let best = (0..26).max_by_key(|&letter| (degrees[letter], std::cmp::Reverse(letter))).unwrap();
+
+        AsciiUppercaseByte::from_index(best)
+    }
+
+    /// The number of closed loops in the menu. Each loop cuts down the false stops about 26 times.
+    pub fn loops(&self) -> usize {
+        let mut parent = (0..26).collect::<Vec<usize>>();
+        let mut loops = 0;
+
+        for &(a, b, _) in &self.links {
+            let (a, b) = (find(&mut parent, a.index()), find(&mut parent, b.index()));
+
+            if a == b {
+                loops += 1;
+            } else {
+                parent[a] = b;
+            }
+        }
+
+        loops
+    }
+
+    fn degrees(&self) -> [usize; 26] {
+        let mut degrees = [0; 26];
+
+        for &(a, b, _) in &self.links {
+            degrees[a.index()] += 1;
+            degrees[b.index()] += 1;
+        }
+
+        degrees
+    }
+}
+
+/// A setting at which the bombe stopped
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stop {
+    /// The rotors, left to right
+    pub rotors: [&'static str; 3],
+    /// The reflector
+    pub reflector: &'static str,
+    /// The positions of the rotor cores just before the first letter of the crib: the letters
+    /// in the windows if every ring were at A
+    pub positions: [AsciiUppercaseByte; 3],
+    /// The plugs implied for the letters of the menu. Letters that turned out unplugged are left
+    /// out.
+    pub plugboard: Vec<(AsciiUppercaseByte, AsciiUppercaseByte)>,
+}
+
+/// Runs the bombe over every position of the `rotors` (left to right) and `reflector`, and
+/// returns the stops
+pub fn run(menu: &Menu, rotors: [&'static Wiring; 3], reflector: &'static Wiring) -> Vec<Stop> {
+    let drums = [Drum::new(rotors[0]), Drum::new(rotors[1]), Drum::new(rotors[2])];
+
+    let test = menu.test_letter().index();
+
+    // For every letter, the letters the menu links it to, and the position of each link
+    let mut links = vec![Vec::new(); 26];
+
+    for &(a, b, i) in menu.links() {
+        links[a.index()].push((b.index(), i));
+        links[b.index()].push((a.index(), i));
+    }
+
+    let mut stops = Vec::new();
+
+    for left in 0..26 {
+        for middle in 0..26 {
+            // The middle and left rotors and the reflector, which stand still along the menu
+            let mut inner = [0; 26];
+
+            for (c, output) in inner.iter_mut().enumerate() {
+                let mut x = drums[1].forward(middle, c as u8);
+                x = drums[0].forward(left, x);
+                x = reflector.wiring[x as usize] - b'A';
+                x = drums[0].backward(left, x);
+                *output = drums[1].backward(middle, x);
+            }
+
+            // The whole scrambler, for each position of the right rotor
+            let mut scramblers = [[0; 26]; 26];
+
+            for (right, scrambler) in scramblers.iter_mut().enumerate() {
+                for (c, output) in scrambler.iter_mut().enumerate() {
+                    *output = drums[2].backward(right as u8, inner[drums[2].forward(right as u8, c as u8) as usize]);
+                }
+            }
+
+            for right in 0..26 {
+                // The right rotor steps before each letter is enciphered
+                let menu_scramblers = (0..menu.links().len())
+                    .map(|i| &scramblers[(right + i + 1) % 26])
+                    .collect::<Vec<&[u8; 26]>>();
+
+                let stop = |plugboard: Vec<(AsciiUppercaseByte, AsciiUppercaseByte)>| Stop {
+                    rotors: [rotors[0].name, rotors[1].name, rotors[2].name],
+                    reflector: reflector.name,
+                    positions: [AsciiUppercaseByte::from_index(left as usize), AsciiUppercaseByte::from_index(middle as usize), AsciiUppercaseByte::from_index(right)],
+                    plugboard,
+                };
+
+                let live = propagate(&links, &menu_scramblers, test, 0);
+
+                if live[test].count_ones() == 26 {
+                    continue;
+                }
+
+                if let Some(plugboard) = plugs(&live) {
+                    stops.push(stop(plugboard));
+                    continue;
+                }
+
+                // The guess was wrong, but a letter it left out may be right
+                for wire in (0..26).filter(|&wire| live[test] & (1 << wire) == 0) {
+                    if let Some(plugboard) = plugs(&propagate(&links, &menu_scramblers, test, wire)) {
+                        stops.push(stop(plugboard));
+                    }
+                }
+            }
+        }
+    }
+
+    stops
+}
+
+/// Attempts to break `cipher_text`, enciphered on an M3 Enigma, given a `crib` found somewhere in
+/// the plain text
+///
+/// The bombe runs at every place the crib can be (or the one in `settings`), with every order of
+/// the rotors and every reflector in `settings`. Every stop yields one candidate, whose key is
+/// written as for [`Enigma`]. Candidates are returned best first.
+pub fn crack(cipher_text: &[u8], crib: &[u8], settings: &BombeSettings, model: &LanguageModel) -> Result<Vec<Candidate>, Error> {
+    let text = common::sanitize_text(cipher_text)?;
+    let crib = common::sanitize_text(crib)?;
+    let crib = &crib[..crib.len().min(MAX_MENU_LENGTH)];
+
+    let rotors = settings.rotors.iter()
+        .map(|name| wiring(enigma::ROTORS, name, "rotor"))
+        .collect::<Result<Vec<&'static Wiring>, Error>>()?;
+
+    let reflectors = settings.reflectors.iter()
+        .map(|name| wiring(enigma::REFLECTORS, name, "reflector").and_then(|reflector| {
+            if reflector.name.ends_with("-thin") {
+                Err(Error::KeyError(format!("The bombe only models the three-rotor machine, not reflector {}", reflector.name)))
+            } else {
+                Ok(reflector)
+            }
+        }))
+        .collect::<Result<Vec<&'static Wiring>, Error>>()?;
+
+    let mut positions = crib_positions(cipher_text, &crib.iter().map(|letter| letter.get_byte()).collect::<Vec<u8>>())?;
+
+    if let Some(position) = settings.crib_position {
+        positions.retain(|&p| p == position);
+    }
+
+    // A job is a place of the crib, a rotor order and a reflector
+    let mut jobs = Vec::new();
+
+    for &position in &positions {
+        for &left in &rotors {
+            for &middle in rotors.iter().filter(|&&r| r != left) {
+                for &right in rotors.iter().filter(|&&r| r != left && r != middle) {
+                    for &reflector in &reflectors {
+                        jobs.push((position, [left, middle, right], reflector));
+                    }
+                }
+            }
+        }
+    }
+
+    let next = AtomicUsize::new(0);
+    let found = Mutex::new(Vec::new());
+
+    thread::scope(|scope| {
+        for _ in 0..settings.threads.max(1) {
+            scope.spawn(|| {
+                while let Some(&(position, order, reflector)) = jobs.get(next.fetch_add(1, Ordering::Relaxed)) {
+                    let menu = Menu::new(crib, &text[position..]);
+
+                    let tried = run(&menu, order, reflector)
+                        .iter()
+                        .map(|stop| best_ring(stop, position, &text, model))
+                        .collect::<Vec<(MachineKey, Candidate)>>();
+
+                    found.lock().unwrap().extend(tried);
+                }
+            });
+        }
+    });
+
+    let mut found = found.into_inner().unwrap();
+
+    found.sort_by(|a, b| b.1.score.partial_cmp(&a.1.score).unwrap().then_with(|| a.1.key.cmp(&b.1.key)));
+    found.dedup_by(|a, b| a.1.key == b.1.key);
+
+    let mut candidates = found.iter()
+        .enumerate()
+        .map(|(i, (key, candidate))| if i < COMPLETED { complete(key, candidate, &text, model) } else { candidate.clone() })
+        .collect::<Vec<Candidate>>();
+
+    candidates.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
+
+    Ok(candidates)
+}
+
+// A stop made into a whole key of the machine
+#[derive(Clone)]
+struct MachineKey {
+    rotors: [&'static str; 3],
+    reflector: &'static str,
+    // The windows just before the crib, and how many letters come before it
+    windows: [AsciiUppercaseByte; 3],
+    position: usize,
+    // The rings of the middle and right rotors. The left ring only turns the left rotor, which
+    // turns nothing, so it is left at A.
+    rings: [AsciiUppercaseByte; 2],
+    plugboard: Vec<(AsciiUppercaseByte, AsciiUppercaseByte)>,
+}
+
+impl MachineKey {
+    // Deciphers `text` under the key
+    fn try_on(&self, text: &[AsciiUppercaseByte], model: &LanguageModel) -> Candidate {
+        let plugboard = self.plugboard.iter()
+            .map(|&(a, b)| format!("{}{}", char::from(a), char::from(b)))
+            .collect::<Vec<String>>()
+            .join(" ");
+
+        let key = format!("{};rotors={};reflector={};rings=A{}{};plugboard={}",
+            self.start().iter().map(|&p| char::from(p)).collect::<String>(),
+            self.rotors.join(" "), self.reflector, char::from(self.rings[0]), char::from(self.rings[1]), plugboard);
+
+        let plain_text = Enigma::from_key_str(&key).unwrap().decipher(&text.iter().map(|l| l.get_byte()).collect::<Vec<u8>>()).unwrap();
+        let score = model.fitness(&common::sanitize_text(plain_text.as_bytes()).unwrap());
+
+        Candidate { key, plain_text, score }
+    }
+
+    // The windows at the start of the message, found by trying the starts that the middle and
+    // left rotors could have stepped from. If none reaches the windows at the crib, only the
+    // right rotor is turned back.
+    fn start(&self) -> [AsciiUppercaseByte; 3] {
+        let back = |window: AsciiUppercaseByte, steps: usize| AsciiUppercaseByte::from_index((window.index() + 26 - steps % 26) % 26);
+
+        let naive = [self.windows[0], self.windows[1], back(self.windows[2], self.position)];
+
+        if self.position == 0 {
+            return naive;
+        }
+
+        let mut machine = Machine::new(Model::M3, &self.rotors, self.reflector).unwrap();
+        machine.set_rings(&[AsciiUppercaseByte::from_index(0), self.rings[0], self.rings[1]]).unwrap();
+
+        for left in 0..2 {
+            for middle in 0..=self.position / 26 + 2 {
+                let start = [back(self.windows[0], left), back(self.windows[1], middle), naive[2]];
+
+                machine.set_positions(&start).unwrap();
+
+                for _ in 0..self.position {
+                    machine.step();
+                }
+
+                if machine.positions() == self.windows {
+                    return start;
+                }
+            }
+        }
+
+        naive
+    }
+}
+
+// Finds the ring of the right rotor that deciphers `text` best under `stop`, whose crib starts at
+// `position`
+fn best_ring(stop: &Stop, position: usize, text: &[AsciiUppercaseByte], model: &LanguageModel) -> (MachineKey, Candidate) {
+    (0..26)
+        .map(|ring| {
+            let key = MachineKey {
+                rotors: stop.rotors,
+                reflector: stop.reflector,
+                windows: [stop.positions[0], stop.positions[1], AsciiUppercaseByte::from_index((stop.positions[2].index() + ring) % 26)],
+                position,
+                rings: [AsciiUppercaseByte::from_index(0), AsciiUppercaseByte::from_index(ring)],
+                plugboard: stop.plugboard.clone(),
+            };
+
+            let candidate = key.try_on(text, model);
+
+            (key, candidate)
+        })
+        .max_by(|a, b| a.1.score.partial_cmp(&b.1.score).unwrap())
+        .unwrap()
+}
+
+// Finds the rings of the middle and right rotors, which decide where the rotors turn over, then adds
+// the plugs of the letters outside the menu one at a time, each time the one that improves the
+// plain text most, until none does
+fn complete(key: &MachineKey, candidate: &Candidate, text: &[AsciiUppercaseByte], model: &LanguageModel) -> Candidate {
+    let (mut key, mut best) = (key.clone(), candidate.clone());
+
+    for middle in 0..26 {
+        for right in 0..26 {
+            let mut trial = key.clone();
+
+            trial.rings = [AsciiUppercaseByte::from_index(middle), AsciiUppercaseByte::from_index(right)];
+            trial.windows[1] = AsciiUppercaseByte::from_index((key.windows[1].index() + 26 - key.rings[0].index() + middle) % 26);
+            trial.windows[2] = AsciiUppercaseByte::from_index((key.windows[2].index() + 26 - key.rings[1].index() + right) % 26);
+
+            let candidate = trial.try_on(text, model);
+
+            if candidate.score > best.score {
+                best = candidate;
+                key = trial;
+            }
+        }
+    }
+
+    loop {
+        let used = key.plugboard.iter().flat_map(|&(a, b)| vec![a, b]).collect::<Vec<AsciiUppercaseByte>>();
+        let free = (0..26).map(AsciiUppercaseByte::from_index).filter(|letter| !used.contains(letter)).collect::<Vec<AsciiUppercaseByte>>();
+
+        let mut improved = None;
+
+        for (i, &a) in free.iter().enumerate() {
+            for &b in &free[i + 1..] {
+                let mut trial = key.clone();
+                trial.plugboard.push((a, b));
+
+                let candidate = trial.try_on(text, model);
+
+                if candidate.score > improved.as_ref().map_or(best.score, |(_, c): &(MachineKey, Candidate)| c.score) {
+                    improved = Some((trial, candidate));
+                }
+            }
+        }
+
+        match improved {
+            Some((trial, candidate)) => {
+                key = trial;
+                best = candidate;
+            },
+            None => return best,
+        }
+    }
+}
+
+// Follows the implications of `test` being plugged to `wire`. Returns, for every letter, the
+// set of letters it would be plugged to, as a bit mask.
+fn propagate(links: &[Vec<(usize, usize)>], scramblers: &[&[u8; 26]], test: usize, wire: usize) -> [u32; 26] {
+    let mut live = [0u32; 26];
+    let mut pending = vec![(test, wire)];
+
+    live[test] |= 1 << wire;
+
+    while let Some((letter, wire)) = pending.pop() {
+        let implied = links[letter].iter()
+            .map(|&(other, i)| (other, scramblers[i][wire] as usize))
+            .chain(std::iter::once((wire, letter)));
+
+        for (letter, wire) in implied {
+            if live[letter] & (1 << wire) == 0 {
+                live[letter] |= 1 << wire;
+                pending.push((letter, wire));
+            }
+        }
+    }
+
+    live
+}
+
+// The plugboard implied by a consistent guess, or nothing if some letter would be plugged twice
+fn plugs(live: &[u32; 26]) -> Option<Vec<(AsciiUppercaseByte, AsciiUppercaseByte)>> {
+    if live.iter().any(|wires| wires.count_ones() > 1) {
+        return None;
+    }
+
+    Some((0..26)
+        .filter(|&letter| live[letter] != 0)
+        .map(|letter| (letter, live[letter].trailing_zeros() as usize))
+        .filter(|&(letter, wire)| letter < wire)
+        .map(|(letter, wire)| (AsciiUppercaseByte::from_index(letter), AsciiUppercaseByte::from_index(wire)))
+        .collect())
+}
+
+// A rotor as the bombe models it: a core turned to a position, with no ring
+struct Drum {
+    forward: [u8; 26],
+    backward: [u8; 26],
+}
+
+impl Drum {
+    fn new(wiring: &Wiring) -> Drum {
+        let mut forward = [0; 26];
+        let mut backward = [0; 26];
+
+        for (i, &letter) in wiring.wiring.iter().enumerate() {
+            forward[i] = letter - b'A';
+            backward[(letter - b'A') as usize] = i as u8;
+        }
+
+        Drum { forward, backward }
+    }
+
+    fn forward(&self, position: u8, contact: u8) -> u8 {
+        (self.forward[((contact + position) % 26) as usize] + 26 - position) % 26
+    }
+
+    fn backward(&self, position: u8, contact: u8) -> u8 {
+        (self.backward[((contact + position) % 26) as usize] + 26 - position) % 26
+    }
+}
+
+fn wiring(wirings: &'static [Wiring], name: &str, what: &str) -> Result<&'static Wiring, Error> {
+    wirings.iter()
+        .find(|wiring| wiring.name.eq_ignore_ascii_case(name))
+        .ok_or_else(|| Error::KeyError(format!("Unknown {} '{}'", what, name)))
+}
+
+fn find(parent: &mut [usize], letter: usize) -> usize {
+    let mut root = letter;
+
+    while parent[root] != root {
+        root = parent[root];
+    }
+
+    parent[letter] = root;
+
+    root
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cipher::{Cipher, KeyedCipher};
+    use crate::common;
+    use crate::crack::bombe::{self, crack, crib_positions, BombeSettings, Menu};
+    use crate::enigma::{self, Enigma};
+    use crate::language_model::LanguageModel;
+
+    const PLAIN_TEXT: &[u8] = b"Weather report for the night. Wind from the west at force five, rain spreading \
+        from the coast after midnight, visibility poor. Convoy expected to pass the northern point at dawn.";
+
+    const KEY: &str = "QWA;rotors=II V III;reflector=B;rings=ACF;plugboard=AM FI NV PS TU WZ";
+
+    #[test]
+    fn test_crib_positions() {
+        // The crib cannot sit where a letter would be enciphered to itself
+        assert_eq!(crib_positions(b"ABCDE", b"BA").unwrap(), vec![0, 2, 3]);
+        assert_eq!(crib_positions(b"ABC", b"ABCD").unwrap(), Vec::<usize>::new());
+
+        let cipher_text = Enigma::from_key_str(KEY).unwrap().encipher(PLAIN_TEXT).unwrap();
+
+        assert!(crib_positions(cipher_text.as_bytes(), b"WEATHERREPORT").unwrap().contains(&0));
+    }
+
+    #[test]
+    fn test_menu() {
+        let crib = common::sanitize_text(b"ABCA").unwrap();
+        let cipher_text = common::sanitize_text(b"BCAD").unwrap();
+
+        let menu = Menu::new(&crib, &cipher_text);
+
+        assert_eq!(menu.links().len(), 4);
+        assert_eq!(menu.loops(), 1);
+        assert_eq!(char::from(menu.test_letter()), 'A');
+    }
+
+    #[test]
+    fn test_run() {
+        let enigma = Enigma::from_key_str(KEY).unwrap();
+        let cipher_text = common::sanitize_text(enigma.encipher(PLAIN_TEXT).unwrap().as_bytes()).unwrap();
+        let crib = common::sanitize_text(b"WEATHERREPORTFORTHE").unwrap();
+
+        let rotors = [&enigma::ROTORS[1], &enigma::ROTORS[4], &enigma::ROTORS[2]];
+
+        let stops = bombe::run(&Menu::new(&crib, &cipher_text), rotors, &enigma::REFLECTORS[0]);
+
+        // With the rings at ACF, the cores of QWA stand at QUV
+        let stop = stops.iter()
+            .find(|stop| stop.positions.iter().map(|&p| char::from(p)).collect::<String>() == "QUV")
+            .unwrap();
+
+        for pair in &stop.plugboard {
+            assert!(KEY.contains(&format!("{}{}", char::from(pair.0), char::from(pair.1))));
+        }
+    }
+
+    #[test]
+    fn test_crack() {
+        let enigma = Enigma::from_key_str(KEY).unwrap();
+        let cipher_text = enigma.encipher(PLAIN_TEXT).unwrap();
+
+        let settings = BombeSettings {
+            rotors: vec!["III".to_string(), "V".to_string(), "II".to_string()],
+            crib_position: Some(0),
+            ..BombeSettings::default()
+        };
+
+        let candidates = crack(cipher_text.as_bytes(), b"weather report for the", &settings, &LanguageModel::english()).unwrap();
+
+        assert_eq!(candidates[0].plain_text, common::format_output(common::sanitize_text(PLAIN_TEXT).unwrap()));
+    }
+}
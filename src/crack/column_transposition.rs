@@ -0,0 +1,188 @@
+//! # Breaking column transposition
+//!
+//! A transposition leaves the letters alone and only scrambles their order, so the attack is
+//! about putting the columns back in the right order. For every number of columns we consider:
+//!
+//! 1. Work out the height of every column, the same way
+//!    [`column_transposition::decipher`](crate::column_transposition::decipher) does.
+//! 2. Search for the order of the columns under which the text reads most like English
+//!    (highest quadgram fitness).
+//!
+//! Narrow tables have few enough orders that we try them all. Wider tables are searched by hill
+//! climbing: starting from a random order, we swap two columns or move a column elsewhere, and
+//! keep the change if the text improves.
+
+use crate::column_transposition;
+use crate::common::{self, AsciiUppercaseByte};
+use crate::crack::{Candidate, SearchSettings};
+use crate::errors::Error;
+use crate::language_model::LanguageModel;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use std::ops::RangeInclusive;
+
+/// Tables up to this many columns are solved by trying every order of the columns
+pub const MAX_EXHAUSTIVE_WIDTH: usize = 7;
+
+/// Attempts to break `cipher_text`, enciphered with column transposition, without knowing the key
+///
+/// Every number of columns in `widths` yields one candidate. Its key is a keyphrase that
+/// deciphers the text (e.g. `CAB` for the key 3 1 2). Candidates are returned best first.
+///
+/// Returns an error for tables wider than 26 columns, whose keys cannot be written as a keyphrase
+/// of distinct letters.
+pub fn crack(cipher_text: &[u8], widths: RangeInclusive<usize>, model: &LanguageModel, settings: &SearchSettings) -> Result<Vec<Candidate>, Error> {
+    let text = common::sanitize_text(cipher_text)?;
+
+    if *widths.end() > 26 {
+        return Err(Error::KeyError(format!("Cannot search tables of more than 26 columns (asked for {})", widths.end())));
+    }
+
+    let mut rng = StdRng::seed_from_u64(settings.seed);
+
+    let mut candidates = Vec::new();
+
+    for width in widths.filter(|&width| width > 0 && width <= text.len().max(1)) {
+        let (key, score) = if width <= MAX_EXHAUSTIVE_WIDTH {
+            search_all(&text, width, model)
+        } else {
+            climb(&text, width, model, &mut rng, settings)
+        };
+
+        let key = key.iter().map(|&k| (b'A' + k as u8) as char).collect::<String>();
+
+        candidates.push(Candidate {
+            plain_text: column_transposition::decipher(key.as_bytes(), cipher_text)?,
+            key,
+            score,
+        });
+    }
+
+    candidates.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
+
+    Ok(candidates)
+}
+
+// Tries every order of `width` columns
+fn search_all(text: &[AsciiUppercaseByte], width: usize, model: &LanguageModel) -> (Vec<usize>, f64) {
+    let mut key = (0..width).collect::<Vec<usize>>();
+
+    let mut best = (key.clone(), score(text, &key, model));
+
+    while next_permutation(&mut key) {
+        let score = score(text, &key, model);
+
+        if score > best.1 {
+            best = (key.clone(), score);
+        }
+    }
+
+    best
+}
+
+// Hill climbs from `settings.restarts` random orders of `width` columns
+fn climb(text: &[AsciiUppercaseByte], width: usize, model: &LanguageModel, rng: &mut StdRng, settings: &SearchSettings) -> (Vec<usize>, f64) {
+    let mut best: Option<(Vec<usize>, f64)> = None;
+
+    for _ in 0..settings.restarts.max(1) {
+        let mut key = (0..width).collect::<Vec<usize>>();
+        key.shuffle(rng);
+
+        let mut current = score(text, &key, model);
+
+        for _ in 0..settings.iterations {
+            let mut candidate = key.clone();
+
+            let (i, j) = (rng.gen_range(0, width), rng.gen_range(0, width));
+
+            if rng.gen::<bool>() {
+                candidate.swap(i, j);
+            } else {
+                let column = candidate.remove(i);
+                candidate.insert(j, column);
+            }
+
+            let score = score(text, &candidate, model);
+
+            if score > current {
+                key = candidate;
+                current = score;
+            }
+        }
+
+        if best.as_ref().is_none_or(|&(_, best)| current > best) {
+            best = Some((key, current));
+        }
+    }
+
+    best.unwrap()
+}
+
+// Fitness of `text` deciphered with the numeric key `key`
+fn score(text: &[AsciiUppercaseByte], key: &[usize], model: &LanguageModel) -> f64 {
+    model.fitness(&column_transposition::untranspose(key, text))
+}
+
+// Rearranges `values` into the next permutation in lexicographic order.
+// Returns false, leaving `values` alone, if it already is the last one.
+fn next_permutation(values: &mut [usize]) -> bool {
+    let pivot = match (1..values.len()).rev().find(|&i| values[i - 1] < values[i]) {
+        Some(i) => i - 1,
+        None => return false,
+    };
+
+    let successor = (pivot + 1..values.len()).rev().find(|&i| values[i] > values[pivot]).unwrap();
+
+    values.swap(pivot, successor);
+    values[pivot + 1..].reverse();
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::column_transposition;
+    use crate::common;
+    use crate::crack::column_transposition::{self as crack, next_permutation};
+    use crate::crack::PLAIN_TEXT;
+    use crate::crack::SearchSettings;
+    use crate::language_model::LanguageModel;
+
+    #[test]
+    fn test_next_permutation() {
+        let mut values = vec![0, 1, 2];
+        let mut count = 1;
+
+        while next_permutation(&mut values) {
+            count += 1;
+        }
+
+        assert_eq!(count, 6);
+        assert_eq!(values, vec![2, 1, 0]);
+    }
+
+    #[test]
+    fn test_crack_exhaustive() {
+        let cipher_text = column_transposition::encipher(b"DARCY", PLAIN_TEXT).unwrap();
+
+        let candidates = crack::crack(cipher_text.as_bytes(), 2..=6, &LanguageModel::english(), &SearchSettings::default()).unwrap();
+
+        assert_eq!(candidates[0].key, "CADBE");
+        assert_eq!(candidates[0].plain_text, column_transposition::decipher(b"DARCY", cipher_text.as_bytes()).unwrap());
+    }
+
+    #[test]
+    fn test_crack_hill_climbing() {
+        let cipher_text = column_transposition::encipher(b"LONGBOURNE", PLAIN_TEXT).unwrap();
+
+        let settings = SearchSettings { seed: 3, restarts: 5, iterations: 1000 };
+
+        let candidates = crack::crack(cipher_text.as_bytes(), 10..=10, &LanguageModel::english(), &settings).unwrap();
+
+        assert_eq!(
+            common::sanitize_text(candidates[0].plain_text.as_bytes()).unwrap(),
+            common::sanitize_text(PLAIN_TEXT).unwrap()
+        );
+    }
+}
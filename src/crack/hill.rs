@@ -0,0 +1,199 @@
+//! # Breaking the Hill cipher with known plain text
+//!
+//! The Hill cipher is linear, and linear equations are easy to solve. Take _n_ blocks of plain
+//! text and the _n_ blocks of cipher text they became, and write them as the columns of two
+//! matrices _P_ and _C_. Every column obeys `C = K · P`, so the whole matrices do too, and if
+//! _P_ has an inverse modulo 26:
+//!
+//! `K = C · P⁻¹ (mod 26)`
+//!
+//! [`solve`] does this, picking blocks whose matrix has an inverse. Any blocks left over check
+//! the key.
+//!
+//! Usually only a probable word or phrase of the plain text is known (a _crib_), not where it
+//! is. [`crack`] drags the crib along the cipher text: wherever it covers enough whole blocks,
+//! they give a key, which deciphers the whole cryptogram. The crib must therefore be at least
+//! n² + n - 1 letters long to be found at any position. Keys that disagree with a block of the
+//! crib are dropped, and the others are ranked with a [`LanguageModel`].
+
+use crate::common::{self, AsciiUppercaseByte};
+use crate::crack::Candidate;
+use crate::errors::Error;
+use crate::hill::Matrix;
+use crate::language_model::LanguageModel;
+use std::ops::RangeInclusive;
+
+// Sets of blocks tried before giving up on finding one whose matrix has an inverse
+const MAX_COMBINATIONS: usize = 10_000;
+
+/// Finds the n×n key matrix that enciphers `plain_text` to `cipher_text`, both starting at the
+/// start of a block
+///
+/// Returns an error if the texts do not share at least n whole blocks whose plain text matrix
+/// has an inverse modulo 26, or if no key enciphers every block of `plain_text` to
+/// `cipher_text`.
+pub fn solve(plain_text: &[u8], cipher_text: &[u8], size: usize) -> Result<Matrix, Error> {
+    let plain_text = common::sanitize_text(plain_text)?;
+    let cipher_text = common::sanitize_text(cipher_text)?;
+
+    if size == 0 {
+        return Err(Error::KeyError("A key matrix needs at least one row".to_string()));
+    }
+
+    let blocks = plain_text.chunks_exact(size).zip(cipher_text.chunks_exact(size)).collect::<Vec<_>>();
+
+    if blocks.len() < size {
+        return Err(Error::KeyError(format!("{} whole blocks of known plain text are needed, not {}", size, blocks.len())));
+    }
+
+    solve_blocks(&blocks, size).ok_or_else(|| {
+        Error::KeyError("No key matrix fits the known plain text: the blocks may be too alike, or not line up".to_string())
+    })
+}
+
+/// Attempts to break `cipher_text`, enciphered with the Hill cipher, given a `crib` found
+/// somewhere in the plain text
+///
+/// Every key size in `sizes` is tried at every position of the crib. Every key found yields one
+/// candidate, whose key is the entries of the matrix as letters, row by row. Candidates are
+/// returned best first, and a key is never reported twice.
+pub fn crack(cipher_text: &[u8], crib: &[u8], sizes: RangeInclusive<usize>, model: &LanguageModel) -> Result<Vec<Candidate>, Error> {
+    let text = common::sanitize_text(cipher_text)?;
+    let crib = common::sanitize_text(crib)?;
+
+    let mut candidates: Vec<Candidate> = Vec::new();
+
+    for size in sizes.filter(|&size| size > 0 && text.len().is_multiple_of(size)) {
+        for position in 0..=text.len().saturating_sub(crib.len()) {
+            // The first block boundary inside the crib
+            let skip = (size - position % size) % size;
+
+            if crib.len() < skip {
+                continue;
+            }
+
+            let blocks = crib[skip..].chunks_exact(size)
+                .zip(text[position + skip..].chunks_exact(size))
+                .collect::<Vec<_>>();
+
+            if blocks.len() < size {
+                continue;
+            }
+
+            let (key, inverse) = match solve_blocks(&blocks, size).and_then(|key| key.inverse().map(|inverse| (key, inverse))) {
+                Some(pair) => pair,
+                None => continue,
+            };
+
+            let letters = key.entries().iter().map(|&entry| char::from(entry)).collect::<String>();
+
+            if candidates.iter().any(|c| c.key == letters) {
+                continue;
+            }
+
+            let plain_text = text.chunks(size).flat_map(|block| inverse.apply(block)).collect::<Vec<AsciiUppercaseByte>>();
+
+            candidates.push(Candidate {
+                key: letters,
+                score: model.fitness(&plain_text),
+                plain_text: common::format_output(plain_text),
+            });
+        }
+    }
+
+    candidates.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
+
+    Ok(candidates)
+}
+
+// Solves `K · plain = cipher` for the (plain, cipher) pairs of blocks, or returns `None` if no set
+// of `size` blocks has an invertible plain text matrix, or if the key does not fit every pair
+fn solve_blocks(blocks: &[(&[AsciiUppercaseByte], &[AsciiUppercaseByte])], size: usize) -> Option<Matrix> {
+    let mut chosen = (0..size).collect::<Vec<usize>>();
+
+    for _ in 0..MAX_COMBINATIONS {
+        let plain = Matrix::from_columns(&chosen.iter().map(|&i| blocks[i].0).collect::<Vec<_>>());
+
+        if let Some(inverse) = plain.inverse() {
+            let cipher = Matrix::from_columns(&chosen.iter().map(|&i| blocks[i].1).collect::<Vec<_>>());
+
+            let key = cipher.multiply(&inverse);
+
+            return if blocks.iter().all(|&(plain, cipher)| key.apply(plain) == cipher) { Some(key) } else { None };
+        }
+
+        if !next_combination(&mut chosen, blocks.len()) {
+            break;
+        }
+    }
+
+    None
+}
+
+// Moves `chosen`, a strictly increasing list of indices below `count`, to the next such list in
+// lexicographic order. Returns false once it was the last one.
+fn next_combination(chosen: &mut [usize], count: usize) -> bool {
+    let k = chosen.len();
+
+    for i in (0..k).rev() {
+        if chosen[i] < count - k + i {
+            chosen[i] += 1;
+
+            for j in i + 1..k {
+                chosen[j] = chosen[j - 1] + 1;
+            }
+
+            return true;
+        }
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::common;
+    use crate::crack::hill::{crack, next_combination, solve};
+    use crate::hill;
+    use crate::language_model::LanguageModel;
+
+    const PLAIN_TEXT: &[u8] = b"The cipher was published by Lester Hill in the American Mathematical Monthly in \
+        nineteen twenty nine, and a machine to apply it with six by six matrices was patented soon after. It \
+        never saw much use, but it was the first cipher that was built on linear algebra from the start.";
+
+    #[test]
+    fn test_next_combination() {
+        let mut chosen = vec![0, 1];
+        let mut all = vec![chosen.clone()];
+
+        while next_combination(&mut chosen, 4) {
+            all.push(chosen.clone());
+        }
+
+        assert_eq!(all, vec![vec![0, 1], vec![0, 2], vec![0, 3], vec![1, 2], vec![1, 3], vec![2, 3]]);
+    }
+
+    #[test]
+    fn test_solve() {
+        let cipher_text = hill::encipher(b"GYBNQKURP", PLAIN_TEXT).unwrap();
+
+        let key = solve(PLAIN_TEXT, cipher_text.as_bytes(), 3).unwrap();
+
+        assert_eq!(key.entries(), &common::sanitize_text(b"GYBNQKURP").unwrap()[..]);
+
+        // The wrong size finds no key that fits every block
+        assert!(solve(PLAIN_TEXT, cipher_text.as_bytes(), 2).is_err());
+        assert!(solve(b"AAAAAAAAA", b"POHPOHPOH", 3).is_err());
+        assert!(solve(b"ACTCAT", b"POHFIN", 3).is_err());
+    }
+
+    #[test]
+    fn test_crack() {
+        let cipher_text = hill::encipher(b"HILL", PLAIN_TEXT).unwrap();
+
+        let candidates = crack(cipher_text.as_bytes(), b"linear algebra", 2..=3, &LanguageModel::english()).unwrap();
+
+        assert_eq!(candidates[0].key, "HILL");
+        assert_eq!(candidates[0].plain_text, hill::decipher(b"HILL", cipher_text.as_bytes()).unwrap());
+    }
+}
@@ -0,0 +1,311 @@
+//! # Breaking the Playfair cipher
+//!
+//! A Playfair square is one of 25! arrangements of the letters, far too many to try. Instead we
+//! _anneal_: starting from a random square, we make a small change to it (swapping two letters,
+//! two rows or two columns, or flipping the square) and decipher the cryptogram again.
+//!
+//! - A change that makes the text look more like English is always kept.
+//! - A change that makes it look less like English is kept with a probability that shrinks as the
+//!   change gets worse, and as the _temperature_ drops.
+//!
+//! Accepting some bad changes lets the search escape squares that no single change improves,
+//! which stop a plain hill climb dead. The temperature only drops a little: the search does best
+//! wandering for a long time, keeping the best square it comes across.
+//!
+//! How much a text looks like English is measured with its digram statistics: every digram of
+//! the text scores its log probability in a [`LanguageModel`], as often as
+//! [`analysis::digram`](crate::analysis::digram) counts it. Counting the digrams of every
+//! deciphered text would be slow, though. A square deciphers a digraph the same way wherever it
+//! appears, so the digrams are counted once, on the digraphs of the cipher text, and every
+//! distinct digraph is only deciphered once.
+//!
+//! A Playfair takes much longer to anneal than other ciphers take to climb. A restart of
+//! [`ITERATIONS`] iterations finds the square of five hundred letters of cipher text about nine
+//! times in ten, and the real square scores well above those that failed restarts end on, so a
+//! few restarts all but always find it.
+
+use crate::common::{self, AsciiUppercaseByte};
+use crate::crack::{Candidate, SearchSettings};
+use crate::errors::Error;
+use crate::language_model::LanguageModel;
+use crate::playfair;
+use crate::polybius::{Grid, Merge};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+/// A good number of iterations for each restart
+pub const ITERATIONS: usize = 150_000;
+
+// Temperature at the start of every restart, per 100 letters of cipher text. It falls linearly to
+// the end temperature.
+const START_TEMPERATURE: f64 = 1.3;
+
+// Temperature at the end of every restart, per 100 letters of cipher text
+const END_TEMPERATURE: f64 = 1.0;
+
+/// Attempts to break `cipher_text`, enciphered with a Playfair square where J is written as I,
+/// without knowing the square
+///
+/// Every restart yields one candidate, whose key is the 25 letters of the square, row by row.
+/// That key can be used as the keyphrase to decipher the text. Candidates are returned best
+/// first, and a key is never reported twice.
+///
+/// Returns an error if the cipher text has an odd number of letters.
+pub fn crack(cipher_text: &[u8], model: &LanguageModel, settings: &SearchSettings) -> Result<Vec<Candidate>, Error> {
+    let merge = Merge::default();
+
+    let text = common::sanitize_text(cipher_text)?
+        .into_iter()
+        .map(|letter| merge.apply(letter))
+        .collect::<Vec<AsciiUppercaseByte>>();
+
+    if !text.len().is_multiple_of(2) {
+        return Err(Error::DecipheringError("Playfair cipher text must have an even number of letters".to_string()));
+    }
+
+    let mut rng = StdRng::seed_from_u64(settings.seed);
+
+    let digraphs = Digraphs::new(&text);
+    let digrams = digram_table(model);
+
+    let mut candidates: Vec<Candidate> = Vec::new();
+
+    for _ in 0..settings.restarts.max(1) {
+        let (square, score) = anneal(&digraphs, &digrams, &mut rng, settings.iterations)?;
+
+        let key = letters(&square).into_iter().map(char::from).collect::<String>();
+
+        if candidates.iter().any(|c| c.key == key) {
+            continue;
+        }
+
+        candidates.push(Candidate {
+            plain_text: common::format_output(decipher(&square, &text)),
+            key,
+            score,
+        });
+    }
+
+    candidates.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
+
+    Ok(candidates)
+}
+
+// Anneals from a random square, and returns the best square seen along the way
+fn anneal(digraphs: &Digraphs, digrams: &[f64], rng: &mut StdRng, iterations: usize) -> Result<(Grid, f64), Error> {
+    // A shuffled alphabet, used as the keyphrase, fills the square in its own order
+    let mut keyphrase = b"ABCDEFGHIKLMNOPQRSTUVWXYZ".to_vec();
+    keyphrase.shuffle(rng);
+
+    let mut square = Grid::square(5, &keyphrase)?;
+    let mut plain = Vec::new();
+    let mut current = digraphs.score(&square, digrams, &mut plain);
+
+    let mut best = (square.clone(), current);
+
+    let (start, end) = (START_TEMPERATURE * digraphs.len as f64 / 100.0, END_TEMPERATURE * digraphs.len as f64 / 100.0);
+
+    for iteration in 0..iterations {
+        let temperature = start + (end - start) * iteration as f64 / iterations as f64;
+
+        // Most changes swap two letters, undone in place if the change is not kept. The rest move,
+        // swap or flip rows or columns.
+        let undo = if rng.gen_range(0, 50) < 44 {
+            let (a, b) = (rng.gen_range(0, 25), rng.gen_range(0, 25));
+            square.swap(a, b);
+
+            Undo::Swap(a, b)
+        } else {
+            let rearranged = Grid::square(5, &rearrange(&letters(&square), rng))?;
+
+            Undo::Replace(Box::new(std::mem::replace(&mut square, rearranged)))
+        };
+
+        let score = digraphs.score(&square, digrams, &mut plain);
+        let delta = score - current;
+
+        if delta > 0.0 || rng.gen::<f64>() < (delta / temperature).exp() {
+            current = score;
+
+            if current > best.1 {
+                best = (square.clone(), current);
+            }
+        } else {
+            match undo {
+                Undo::Swap(a, b) => square.swap(a, b),
+                Undo::Replace(previous) => square = *previous,
+            }
+        }
+    }
+
+    Ok(best)
+}
+
+// How to take back a change to the square
+enum Undo {
+    Swap(usize, usize),
+    Replace(Box<Grid>),
+}
+
+// The 25 letters of `square`, row by row
+fn letters(square: &Grid) -> Vec<u8> {
+    (0..25).map(|cell| square.at(cell / 5, cell % 5).get_byte()).collect()
+}
+
+// A square close to `letters`, with rows or columns moved, swapped or flipped
+fn rearrange(letters: &[u8], rng: &mut StdRng) -> Vec<u8> {
+    let mut rows = letters.chunks(5).map(|row| row.to_vec()).collect::<Vec<Vec<u8>>>();
+
+    let (i, j) = (rng.gen_range(0, 5), rng.gen_range(0, 5));
+
+    match rng.gen_range(0, 6) {
+        0 => rows.swap(i, j),
+        1 => rows.iter_mut().for_each(|row| row.swap(i, j)),
+        2 => {
+            let row = rows.remove(i);
+            rows.insert(j, row);
+        },
+        3 => rows.iter_mut().for_each(|row| {
+            let letter = row.remove(i);
+            row.insert(j, letter);
+        }),
+        4 => rows.reverse(),
+        _ => rows.iter_mut().for_each(|row| row.reverse()),
+    }
+
+    rows.concat()
+}
+
+fn decipher(square: &Grid, text: &[AsciiUppercaseByte]) -> Vec<AsciiUppercaseByte> {
+    let mut plain_text = Vec::with_capacity(text.len());
+
+    for digraph in text.chunks(2) {
+        let (a, b) = playfair::decipher_digraph(square, digraph[0], digraph[1]);
+
+        plain_text.push(a);
+        plain_text.push(b);
+    }
+
+    plain_text
+}
+
+// Log probabilities of the digrams of the language, the digram AB at 26 · A + B
+fn digram_table(model: &LanguageModel) -> Vec<f64> {
+    let letters = common::sanitize_text(b"ABCDEFGHIJKLMNOPQRSTUVWXYZ").unwrap();
+
+    letters.iter()
+        .flat_map(|&a| letters.iter().map(move |&b| [a, b]))
+        .map(|digram| model.log_probability(&digram))
+        .collect()
+}
+
+// The cipher text, as the digraphs it is made of. Every distinct digraph is deciphered once, and
+// its digram counted as often as it appears, as are the digrams across two digraphs.
+struct Digraphs {
+    // Number of letters of the cipher text
+    len: usize,
+    distinct: Vec<(AsciiUppercaseByte, AsciiUppercaseByte, f64)>,
+    // Two distinct digraphs, by index, that follow each other, and how often they do
+    neighbours: Vec<(usize, usize, f64)>,
+}
+
+impl Digraphs {
+    fn new(text: &[AsciiUppercaseByte]) -> Digraphs {
+        let mut distinct: Vec<(AsciiUppercaseByte, AsciiUppercaseByte, f64)> = Vec::new();
+        let mut neighbours: Vec<(usize, usize, f64)> = Vec::new();
+
+        let mut previous = None;
+
+        for digraph in text.chunks(2) {
+            let i = match distinct.iter().position(|&(a, b, _)| (a, b) == (digraph[0], digraph[1])) {
+                Some(i) => i,
+                None => {
+                    distinct.push((digraph[0], digraph[1], 0.0));
+                    distinct.len() - 1
+                },
+            };
+
+            distinct[i].2 += 1.0;
+
+            if let Some(previous) = previous {
+                match neighbours.iter().position(|&(a, b, _)| (a, b) == (previous, i)) {
+                    Some(j) => neighbours[j].2 += 1.0,
+                    None => neighbours.push((previous, i, 1.0)),
+                }
+            }
+
+            previous = Some(i);
+        }
+
+        Digraphs { len: text.len(), distinct, neighbours }
+    }
+
+    // The digrams of the text deciphered with `square`, scored with `digrams`. `plain` is room
+    // for the deciphered digraphs.
+    fn score(&self, square: &Grid, digrams: &[f64], plain: &mut Vec<(usize, usize)>) -> f64 {
+        let mut score = 0.0;
+
+        plain.clear();
+
+        for &(a, b, count) in &self.distinct {
+            let (a, b) = playfair::decipher_digraph(square, a, b);
+            let (a, b) = ((a.get_byte() - b'A') as usize, (b.get_byte() - b'A') as usize);
+
+            score += count * digrams[26 * a + b];
+            plain.push((a, b));
+        }
+
+        for &(first, second, count) in &self.neighbours {
+            score += count * digrams[26 * plain[first].1 + plain[second].0];
+        }
+
+        score
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::analysis;
+    use crate::common;
+    use crate::crack::playfair::{self as crack, crack};
+    use crate::crack::PLAIN_TEXT;
+    use crate::crack::SearchSettings;
+    use crate::language_model::LanguageModel;
+    use crate::playfair;
+    use crate::polybius::Grid;
+
+    #[test]
+    fn test_crack() {
+        let cipher_text = playfair::encipher(b"PEMBERLEY", PLAIN_TEXT).unwrap();
+        let expected = common::sanitize_text(playfair::decipher(b"PEMBERLEY", cipher_text.as_bytes()).unwrap().as_bytes()).unwrap();
+
+        for seed in 0..3 {
+            let settings = SearchSettings { seed, restarts: 2, iterations: crack::ITERATIONS };
+
+            let candidates = crack(cipher_text.as_bytes(), &LanguageModel::english(), &settings).unwrap();
+
+            assert_eq!(common::sanitize_text(candidates[0].plain_text.as_bytes()).unwrap(), expected, "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn test_score() {
+        let model = LanguageModel::english();
+
+        let cipher_text = common::sanitize_text(playfair::encipher(b"PEMBERLEY", b"Meet me at the bridge at noon").unwrap().as_bytes()).unwrap();
+        let square = Grid::square(5, b"PEMBERLEY").unwrap();
+
+        let plain_text = crack::decipher(&square, &cipher_text);
+        let plain_text = plain_text.iter().map(|&letter| letter.get_byte()).collect::<Vec<u8>>();
+
+        let expected = analysis::digram(&plain_text).unwrap()
+            .into_iter()
+            .map(|((a, b), count)| count as f64 * model.log_probability(&[a, b]))
+            .sum::<f64>();
+
+        let score = crack::Digraphs::new(&cipher_text).score(&square, &crack::digram_table(&model), &mut Vec::new());
+
+        assert!((score - expected).abs() < 1e-9);
+    }
+}
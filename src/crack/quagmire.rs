@@ -0,0 +1,506 @@
+//! # Breaking the Quagmire ciphers
+//!
+//! A Quagmire enciphers the ith letter of a period with the cipher alphabet slid by some amount
+//! _sᵢ_ against the plain alphabet. Reading every cipher letter as its place in the cipher
+//! alphabet, and every plain letter as its place in the plain alphabet, the cipher is a
+//! Vigenere:
+//!
+//! `place(C) = place(P) + sᵢ (mod 26)`
+//!
+//! The period is found first, as for the Vigenere cipher
+//! ([`rank_periods`](crate::crack::vigenere::rank_periods)): the index of coincidence of the
+//! cosets does not care how the alphabets are mixed. Then each coset is slid into place and the
+//! missing alphabet is rebuilt. How depends on which alphabet is known.
+//!
+//! - **The cipher alphabet is known** (straight in the Quagmire I). Read as places in the
+//!   cipher alphabet, every coset has the same letter counts, slid along. Sliding each coset to
+//!   best match the others lines them up, and leaves a simple substitution, which
+//!   [`substitution::crack`](crate::crack::substitution::crack) solves. Its key is the plain
+//!   alphabet.
+//! - **The plain alphabet is known** (straight in the Quagmire II). For a guess of the cipher
+//!   alphabet, the best slide of every coset is the one under which its letters look most like
+//!   English, one by one. The cipher alphabet is annealed towards the guess that makes the whole
+//!   text most likely that way: swaps that lower the likelihood are sometimes kept, less and less
+//!   often, as hill climbing gets stuck. It is then climbed with the quadgram fitness of a
+//!   [`LanguageModel`].
+//! - **Both alphabets are known**, and only the indicator is missing. Each coset is slid to
+//!   look most like English, as for the Vigenere cipher.
+//!
+//! With both alphabets mixed (the Quagmire III and IV), the letter counts of a few hundred
+//! letters cannot tell the right alphabets from the many others that count as well, so the
+//! attack needs one of the keywords, or a guess of it, and gives up without either. The Quagmire
+//! I and II are broken by giving the empty keyword of their straight alphabet. Rebuilding an
+//! alphabet from letter counts also needs about a hundred letters per coset: a long indicator
+//! needs a long cryptogram.
+//!
+//! Every Quagmire is a Quagmire IV whose alphabets happen to be keyed alike, so candidate keys
+//! are written for [`Kind::IV`](crate::quagmire::Kind::IV), with both alphabets in full. A
+//! rebuilt alphabet is often a slid copy of the real one, which enciphers the same way.
+
+use crate::analysis;
+use crate::cipher::Cipher;
+use crate::common::{self, AsciiUppercaseByte};
+use crate::crack::{substitution, vigenere, Candidate, SearchSettings};
+use crate::errors::Error;
+use crate::language_model::LanguageModel;
+use crate::quagmire::{self, Kind, Quagmire};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+// Number of the best ranked periods that are tried
+const PERIODS: usize = 3;
+
+// Number of swaps tried while annealing a cipher alphabet, and the temperatures at the start and at the end
+const ANNEALING_STEPS: usize = 200_000;
+const START_TEMPERATURE: f64 = 20.0;
+const END_TEMPERATURE: f64 = 0.2;
+
+// Both alphabets and the slides of a solution: the place of every cipher letter, the slide of every coset and the
+// plain letter at every place
+type Solution = (Vec<usize>, Vec<usize>, Vec<usize>);
+
+/// Attempts to break `cipher_text`, enciphered with any of the Quagmire ciphers, knowing at most
+/// the keywords of its alphabets
+///
+/// `plain_keyword` and `cipher_keyword` are the keywords of the plain and cipher alphabets, if
+/// known; an empty keyword stands for a straight alphabet, such as the cipher alphabet of the
+/// Quagmire I or the plain alphabet of the Quagmire II. Returns an error if neither is known.
+///
+/// Periods from 1 to `max_period` are ranked, and the best few are tried. Searches restart
+/// `settings.restarts` times and change a key `settings.iterations` times each, and decryptions
+/// are scored against `model`. Candidates are returned best first, and a key is never reported
+/// twice.
+pub fn crack(cipher_text: &[u8], max_period: usize, plain_keyword: Option<&[u8]>, cipher_keyword: Option<&[u8]>, model: &LanguageModel,
+        settings: &SearchSettings) -> Result<Vec<Candidate>, Error> {
+    let text = common::sanitize_text(cipher_text)?;
+
+    let mut rng = StdRng::seed_from_u64(settings.seed);
+
+    let mut solutions = Vec::new();
+
+    for estimate in vigenere::rank_periods(&text, max_period).into_iter().take(PERIODS) {
+        let period = estimate.period;
+
+        match (plain_keyword.map(decryption), cipher_keyword.map(places)) {
+            (Some(decryption), Some(places)) => {
+                let slides = best_slides(&coset_counts(&text, period), &places, &decryption);
+
+                solutions.push((places, slides, decryption));
+            },
+            (None, Some(places)) => solutions.extend(find_plain_alphabet(&text, period, places, model, settings)?),
+            (Some(decryption), None) => solutions.extend(find_cipher_alphabet(&text, period, decryption, model, &mut rng, settings)),
+            (None, None) => return Err(Error::KeyError(
+                "Breaking a Quagmire needs the keyword of one of its alphabets, empty for a straight alphabet".to_string()
+            )),
+        }
+    }
+
+    let mut candidates: Vec<Candidate> = Vec::new();
+
+    for (places, slides, decryption) in solutions {
+        let key = quagmire_key(&places, &slides, &decryption);
+
+        if candidates.iter().any(|c| c.key == key) {
+            continue;
+        }
+
+        candidates.push(Candidate {
+            plain_text: Quagmire::parse(Kind::IV, &key)?.decipher(cipher_text)?,
+            score: model.fitness(&decipher(&text, &places, &slides, &decryption)),
+            key,
+        });
+    }
+
+    candidates.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
+
+    Ok(candidates)
+}
+
+// Lines up the cosets under the cipher alphabet given as the place of every letter, and breaks the simple
+// substitution left
+fn find_plain_alphabet(text: &[AsciiUppercaseByte], period: usize, places: Vec<usize>, model: &LanguageModel, settings: &SearchSettings)
+        -> Result<Vec<Solution>, Error> {
+    let slides = align(&coset_counts(text, period), &places);
+
+    // The letters of the cryptogram, as their places in the cipher alphabet lined up with the first coset
+    let aligned = text.iter()
+        .enumerate()
+        .map(|(i, &letter)| b'A' + ((places[letter.index()] + 26 - slides[i % period]) % 26) as u8)
+        .collect::<Vec<u8>>();
+
+    Ok(substitution::crack(&aligned, model, settings)?
+        .into_iter()
+        .map(|solution| {
+            // The key enciphers every plain letter as its place
+            let mut decryption = vec![0; 26];
+
+            for (letter, &place) in solution.key.as_bytes().iter().enumerate() {
+                decryption[(place - b'A') as usize] = letter;
+            }
+
+            (places.clone(), slides.clone(), decryption)
+        })
+        .collect())
+}
+
+// Anneals the cipher alphabet under the plain alphabet given as the plain letter at every place, then climbs it.
+// Every restart yields one solution.
+fn find_cipher_alphabet(text: &[AsciiUppercaseByte], period: usize, decryption: Vec<usize>, model: &LanguageModel, rng: &mut StdRng,
+        settings: &SearchSettings) -> Vec<Solution> {
+    let cosets = coset_counts(text, period);
+
+    // How likely every place is to be seen in English
+    let weights = decryption.iter().map(|&letter| analysis::ENGLISH_FREQUENCIES[letter].ln()).collect::<Vec<f64>>();
+
+    (0..settings.restarts.max(1))
+        .map(|_| {
+            let places = anneal_cipher_alphabet(&cosets, &weights, rng);
+            let slides = SlideScores::new(&cosets, &places, &weights).best_slides();
+            let places = climb_cipher_alphabet(text, places, &slides, &decryption, model, rng, settings.iterations);
+
+            (places, slides, decryption.clone())
+        })
+        .collect()
+}
+
+// The log-likelihood of every coset under every slide, when its letters are read as places in a cipher alphabet and
+// the places as letters of English
+struct SlideScores<'a> {
+    cosets: &'a [[usize; 26]],
+    weights: &'a [f64],
+    scores: Vec<[f64; 26]>,
+}
+
+impl<'a> SlideScores<'a> {
+    fn new(cosets: &'a [[usize; 26]], places: &[usize], weights: &'a [f64]) -> SlideScores<'a> {
+        let scores = cosets.iter()
+            .map(|counts| {
+                let mut scores = [0.0; 26];
+
+                for (slide, score) in scores.iter_mut().enumerate() {
+                    *score = counts.iter()
+                        .zip(places)
+                        .map(|(&count, &place)| count as f64 * weights[(place + 26 - slide) % 26])
+                        .sum();
+                }
+
+                scores
+            })
+            .collect();
+
+        SlideScores { cosets, weights, scores }
+    }
+
+    // Moves `letter` from the place `from` to the place `to`
+    fn move_letter(&mut self, letter: usize, from: usize, to: usize) {
+        for (counts, scores) in self.cosets.iter().zip(self.scores.iter_mut()) {
+            let count = counts[letter] as f64;
+
+            for (slide, score) in scores.iter_mut().enumerate() {
+                *score += count * (self.weights[(to + 26 - slide) % 26] - self.weights[(from + 26 - slide) % 26]);
+            }
+        }
+    }
+
+    // The log-likelihood of the text, every coset under its best slide
+    fn total(&self) -> f64 {
+        self.scores.iter().map(|scores| scores.iter().cloned().fold(f64::MIN, f64::max)).sum()
+    }
+
+    fn best_slides(&self) -> Vec<usize> {
+        self.scores.iter()
+            .map(|scores| (0..26).max_by(|&a, &b| scores[a].partial_cmp(&scores[b]).unwrap()).unwrap())
+            .collect()
+    }
+}
+
+// Anneals a random cipher alphabet, as the place of every letter, towards the one under which the cosets, each under
+// its best slide, look most like English letter by letter. Every step swaps two letters, and keeps the swap if the
+// likelihood goes up, or with a chance that shrinks with the loss and with the temperature.
+fn anneal_cipher_alphabet(cosets: &[[usize; 26]], weights: &[f64], rng: &mut StdRng) -> Vec<usize> {
+    let mut places = (0..26).collect::<Vec<usize>>();
+    places.shuffle(rng);
+
+    let mut scores = SlideScores::new(cosets, &places, weights);
+    let mut current = scores.total();
+
+    let mut best = (places.clone(), current);
+
+    for step in 0..ANNEALING_STEPS {
+        let temperature = START_TEMPERATURE * (END_TEMPERATURE / START_TEMPERATURE).powf(step as f64 / ANNEALING_STEPS as f64);
+
+        let (i, j) = (rng.gen_range(0, 26), rng.gen_range(0, 26));
+
+        if i == j {
+            continue;
+        }
+
+        scores.move_letter(i, places[i], places[j]);
+        scores.move_letter(j, places[j], places[i]);
+
+        let score = scores.total();
+
+        if score >= current || rng.gen::<f64>() < ((score - current) / temperature).exp() {
+            places.swap(i, j);
+            current = score;
+
+            if current > best.1 {
+                best = (places.clone(), current);
+            }
+        } else {
+            scores.move_letter(i, places[j], places[i]);
+            scores.move_letter(j, places[i], places[j]);
+        }
+    }
+
+    best.0
+}
+
+// Swaps two letters of the cipher alphabet at a time, keeping the swaps that make the text look more like English
+fn climb_cipher_alphabet(text: &[AsciiUppercaseByte], mut places: Vec<usize>, slides: &[usize], decryption: &[usize], model: &LanguageModel,
+        rng: &mut StdRng, iterations: usize) -> Vec<usize> {
+    let mut best = model.fitness(&decipher(text, &places, slides, decryption));
+
+    for _ in 0..iterations {
+        let (i, j) = (rng.gen_range(0, 26), rng.gen_range(0, 26));
+
+        if i == j {
+            continue;
+        }
+
+        places.swap(i, j);
+
+        let score = model.fitness(&decipher(text, &places, slides, decryption));
+
+        if score > best {
+            best = score;
+        } else {
+            places.swap(i, j);
+        }
+    }
+
+    places
+}
+
+// Slides every coset, its letters read as their `places` in the cipher alphabet, to best match the others. Returns
+// the slide of every coset; the first one stays where it is.
+fn align(cosets: &[[usize; 26]], places: &[usize]) -> Vec<usize> {
+    let placed = cosets.iter()
+        .map(|counts| {
+            let mut placed = [0usize; 26];
+
+            for (letter, &count) in counts.iter().enumerate() {
+                placed[places[letter]] = count;
+            }
+
+            placed
+        })
+        .collect::<Vec<[usize; 26]>>();
+
+    let mut slides = vec![0; cosets.len()];
+    let mut total = placed[0];
+
+    // Match every coset with the ones before it, then once more with all the others
+    for coset in 1..cosets.len() {
+        slides[coset] = best_match(&total, &placed[coset]);
+
+        for place in 0..26 {
+            total[place] += placed[coset][(place + slides[coset]) % 26];
+        }
+    }
+
+    for coset in 1..cosets.len() {
+        let mut others = total;
+
+        for place in 0..26 {
+            others[place] -= placed[coset][(place + slides[coset]) % 26];
+        }
+
+        slides[coset] = best_match(&others, &placed[coset]);
+
+        for place in 0..26 {
+            total[place] = others[place] + placed[coset][(place + slides[coset]) % 26];
+        }
+    }
+
+    slides
+}
+
+// Finds the slide of `counts` that best matches `total`
+fn best_match(total: &[usize; 26], counts: &[usize; 26]) -> usize {
+    (0..26)
+        .max_by_key(|&slide| (0..26).map(|place| total[place] * counts[(place + slide) % 26]).sum::<usize>())
+        .unwrap()
+}
+
+// Picks the slide of every coset whose letter counts, deciphered, are closest to the letter frequencies of English
+fn best_slides(cosets: &[[usize; 26]], places: &[usize], decryption: &[usize]) -> Vec<usize> {
+    cosets.iter()
+        .map(|counts| {
+            let total = counts.iter().sum::<usize>() as f64;
+
+            (0..26)
+                .map(|slide| {
+                    let mut plain = [0usize; 26];
+
+                    for (letter, &count) in counts.iter().enumerate() {
+                        plain[decryption[(places[letter] + 26 - slide) % 26]] += count;
+                    }
+
+                    let chi_squared = plain.iter()
+                        .zip(analysis::ENGLISH_FREQUENCIES.iter())
+                        .map(|(&observed, &frequency)| (observed as f64 - frequency * total).powi(2) / (frequency * total))
+                        .sum::<f64>();
+
+                    (slide, chi_squared)
+                })
+                .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+                .unwrap()
+                .0
+        })
+        .collect()
+}
+
+// Counts the letters of each coset of `text`
+fn coset_counts(text: &[AsciiUppercaseByte], period: usize) -> Vec<[usize; 26]> {
+    vigenere::split_cosets(text, period)
+        .iter()
+        .map(|coset| {
+            let mut counts = [0; 26];
+
+            for &letter in coset {
+                counts[letter.index()] += 1;
+            }
+
+            counts
+        })
+        .collect()
+}
+
+fn decipher(text: &[AsciiUppercaseByte], places: &[usize], slides: &[usize], decryption: &[usize]) -> Vec<AsciiUppercaseByte> {
+    text.iter()
+        .enumerate()
+        .map(|(i, &letter)| AsciiUppercaseByte::from_index(decryption[(places[letter.index()] + 26 - slides[i % slides.len()]) % 26]))
+        .collect()
+}
+
+// The alphabet keyed with `keyword`, as the place of every letter
+fn places(keyword: &[u8]) -> Vec<usize> {
+    let mut places = vec![0; 26];
+
+    for (place, letter) in quagmire::keyed_alphabet(keyword).into_iter().enumerate() {
+        places[letter.index()] = place;
+    }
+
+    places
+}
+
+// The alphabet keyed with `keyword`, as the letter at every place
+fn decryption(keyword: &[u8]) -> Vec<usize> {
+    quagmire::keyed_alphabet(keyword).into_iter().map(|letter| letter.index()).collect()
+}
+
+// Writes the key of the Quagmire IV found. The indicator is read under the first letter of the plain alphabet.
+fn quagmire_key(places: &[usize], slides: &[usize], decryption: &[usize]) -> String {
+    let mut cipher_alphabet = vec![b'A'; 26];
+
+    for (letter, &place) in places.iter().enumerate() {
+        cipher_alphabet[place] = b'A' + letter as u8;
+    }
+
+    let plain_alphabet = decryption.iter().map(|&letter| b'A' + letter as u8).collect::<Vec<u8>>();
+
+    let indicator = slides.iter().map(|&slide| cipher_alphabet[slide]).collect::<Vec<u8>>();
+
+    format!("{};keyword={};cipher-keyword={}",
+        String::from_utf8(indicator).unwrap(),
+        String::from_utf8(plain_alphabet).unwrap(),
+        String::from_utf8(cipher_alphabet).unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cipher::Cipher;
+    use crate::common;
+    use crate::crack::quagmire;
+    use crate::crack::PLAIN_TEXT;
+    use crate::crack::SearchSettings;
+    use crate::language_model::LanguageModel;
+    use crate::quagmire::{Kind, Quagmire};
+
+    // Counts the letters of `recovered` that differ from those of `expected`, letters missing from
+    // either one counting as wrong
+    fn wrong_letters(recovered: &[u8], expected: &[u8]) -> usize {
+        let recovered = common::sanitize_text(recovered).unwrap();
+        let expected = common::sanitize_text(expected).unwrap();
+
+        let different = recovered.iter().zip(&expected).filter(|(r, e)| r != e).count();
+
+        different + recovered.len().max(expected.len()) - recovered.len().min(expected.len())
+    }
+
+    // Breaks the plain text enciphered with `cipher`, and counts the wrong letters of the best candidate
+    fn wrong_letters_after_crack(cipher: &dyn Cipher, plain_keyword: Option<&[u8]>, cipher_keyword: Option<&[u8]>) -> usize {
+        let cipher_text = cipher.encipher(PLAIN_TEXT).unwrap();
+
+        let settings = SearchSettings { seed: 1, restarts: 3, iterations: 3000 };
+
+        let candidates = quagmire::crack(cipher_text.as_bytes(), 12, plain_keyword, cipher_keyword, &LanguageModel::english(), &settings).unwrap();
+
+        // The key found deciphers the cryptogram
+        assert_eq!(Quagmire::parse(Kind::IV, &candidates[0].key).unwrap().decipher(cipher_text.as_bytes()).unwrap(), candidates[0].plain_text);
+
+        wrong_letters(candidates[0].plain_text.as_bytes(), PLAIN_TEXT)
+    }
+
+    #[test]
+    fn test_crack_quagmire_i() {
+        let cipher = Quagmire::parse(Kind::I, "BENNET;keyword=NETHERFIELD").unwrap();
+
+        // The cipher alphabet is straight
+        assert!(wrong_letters_after_crack(&cipher, None, Some(b"")) <= 2);
+    }
+
+    #[test]
+    fn test_crack_quagmire_ii() {
+        let cipher = Quagmire::parse(Kind::II, "ELIZA;keyword=PEMBERLEY").unwrap();
+
+        // The plain alphabet is straight
+        assert!(wrong_letters_after_crack(&cipher, Some(b""), None) <= 2);
+    }
+
+    #[test]
+    fn test_crack_quagmire_iii() {
+        let cipher = Quagmire::parse(Kind::III, "DARCY;keyword=PEMBERLEY").unwrap();
+
+        assert!(wrong_letters_after_crack(&cipher, None, Some(b"PEMBERLEY")) <= 2);
+    }
+
+    #[test]
+    fn test_crack_without_keyword() {
+        let cipher = Quagmire::parse(Kind::III, "DARCY;keyword=PEMBERLEY").unwrap();
+
+        let cipher_text = cipher.encipher(PLAIN_TEXT).unwrap();
+
+        // Straight alphabets would only give wrong candidates
+        assert!(quagmire::crack(cipher_text.as_bytes(), 12, None, None, &LanguageModel::english(), &SearchSettings::default()).is_err());
+    }
+
+    #[test]
+    fn test_crack_quagmire_iv() {
+        let cipher = Quagmire::parse(Kind::IV, "LUCAS;keyword=PEMBERLEY;cipher-keyword=NETHERFIELD").unwrap();
+
+        assert!(wrong_letters_after_crack(&cipher, None, Some(b"NETHERFIELD")) <= 2);
+
+        // With both keywords, only the indicator is left
+        assert_eq!(wrong_letters_after_crack(&cipher, Some(b"PEMBERLEY"), Some(b"NETHERFIELD")), 0);
+    }
+
+    #[test]
+    fn test_crack_known_plain_keyword() {
+        let cipher = Quagmire::parse(Kind::IV, "LUCAS;keyword=PEMBERLEY;cipher-keyword=NETHERFIELD").unwrap();
+
+        assert!(wrong_letters_after_crack(&cipher, Some(b"PEMBERLEY"), None) <= 2);
+    }
+}
@@ -0,0 +1,56 @@
+//! # Breaking the rail fence cipher
+//!
+//! A rail fence key is only a number of rails and an offset, so there are few enough keys to
+//! try them all. Every key is scored by how much the deciphered text reads like English
+//! (quadgram fitness). Single letter frequencies cannot tell the keys apart: a transposition
+//! leaves them unchanged.
+
+use crate::common;
+use crate::crack::Candidate;
+use crate::errors::Error;
+use crate::language_model::LanguageModel;
+use crate::rail_fence;
+use std::ops::RangeInclusive;
+
+/// Deciphers `cipher_text` with every number of rails in `rails`, and every offset
+///
+/// Candidates are returned best first. Their keys are written `RAILS;offset=OFFSET`.
+pub fn crack(cipher_text: &[u8], rails: RangeInclusive<usize>, model: &LanguageModel) -> Result<Vec<Candidate>, Error> {
+    let text = common::sanitize_text(cipher_text)?;
+
+    let mut candidates = Vec::new();
+
+    for rails in rails.filter(|&rails| rails > 1 && rails <= text.len()) {
+        for offset in 0..rail_fence::cycle_length(rails) {
+            let plain_text = rail_fence::decipher(rails, offset, cipher_text)?;
+
+            candidates.push(Candidate {
+                key: format!("{};offset={}", rails, offset),
+                score: model.fitness(&common::sanitize_text(plain_text.as_bytes())?),
+                plain_text,
+            });
+        }
+    }
+
+    candidates.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
+
+    Ok(candidates)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::crack::rail_fence::crack;
+    use crate::crack::PLAIN_TEXT;
+    use crate::language_model::LanguageModel;
+    use crate::rail_fence;
+
+    #[test]
+    fn test_crack() {
+        let cipher_text = rail_fence::encipher(5, 3, PLAIN_TEXT).unwrap();
+
+        let candidates = crack(cipher_text.as_bytes(), 2..=10, &LanguageModel::english()).unwrap();
+
+        assert_eq!(candidates[0].key, "5;offset=3");
+        assert_eq!(candidates[0].plain_text, rail_fence::decipher(5, 3, cipher_text.as_bytes()).unwrap());
+    }
+}
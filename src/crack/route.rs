@@ -0,0 +1,55 @@
+//! # Breaking route transposition
+//!
+//! The key of a route transposition is the width of the rectangle and the route, so we try
+//! every width in a range along every route. Every key is scored by how much the deciphered
+//! text reads like English (quadgram fitness).
+
+use crate::common;
+use crate::crack::Candidate;
+use crate::errors::Error;
+use crate::language_model::LanguageModel;
+use crate::route::{self, ROUTES};
+use std::ops::RangeInclusive;
+
+/// Deciphers `cipher_text` with every number of columns in `widths`, along every route
+///
+/// Candidates are returned best first. Their keys are written `COLUMNS;path=ROUTE`.
+pub fn crack(cipher_text: &[u8], widths: RangeInclusive<usize>, model: &LanguageModel) -> Result<Vec<Candidate>, Error> {
+    let text = common::sanitize_text(cipher_text)?;
+
+    let mut candidates = Vec::new();
+
+    for width in widths.filter(|&width| width > 0 && width <= text.len()) {
+        for &path in ROUTES.iter() {
+            let plain_text = route::decipher(width, path, cipher_text)?;
+
+            candidates.push(Candidate {
+                key: format!("{};path={}", width, path.name()),
+                score: model.fitness(&common::sanitize_text(plain_text.as_bytes())?),
+                plain_text,
+            });
+        }
+    }
+
+    candidates.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
+
+    Ok(candidates)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::crack::route::crack;
+    use crate::crack::PLAIN_TEXT;
+    use crate::language_model::LanguageModel;
+    use crate::route::{self, Route};
+
+    #[test]
+    fn test_crack() {
+        let cipher_text = route::encipher(7, Route::Diagonal, PLAIN_TEXT).unwrap();
+
+        let candidates = crack(cipher_text.as_bytes(), 2..=12, &LanguageModel::english()).unwrap();
+
+        assert_eq!(candidates[0].key, "7;path=diagonal");
+        assert_eq!(candidates[0].plain_text, route::decipher(7, Route::Diagonal, cipher_text.as_bytes()).unwrap());
+    }
+}
@@ -0,0 +1,364 @@
+//! # Breaking the running key cipher
+//!
+//! Every cipher letter is the sum of a plain letter and a key letter, and both come from English
+//! text. Of the 26 ways to split a cipher letter, few give two likely letters at once, and the
+//! letters around them narrow it down further: each stream has to read as English on its own.
+//!
+//! The attack reads the cipher text from left to right, and keeps a _beam_ of the most likely
+//! pairs of plain text and key so far. Each is extended by the 26 possible plain letters (the key
+//! letter follows from the cipher letter), and both streams are scored as English:
+//!
+//! - As runs of words. Each stream is read as a sequence of words from a list of the commonest
+//!   words of English, a word being taken to be as much less likely than the first as its rank
+//!   is far down the list. A word that is not on the list (a name, or a rarer word) can still be
+//!   read, but each of its letters costs far more than those of a listed word.
+//! - As letters, by how likely each letter is to follow the three before it, under the n-gram
+//!   statistics of a [`LanguageModel`]. This is what holds the words of a stream together.
+//!
+//! Letter n-grams alone are not enough: pairs of fragments built from common quadgrams score
+//! better than the real streams, and the search finds those. Words are a much stronger
+//! constraint, as both streams have to split into words at once.
+//!
+//! Two pairs of streams that are in the same place of the same word, and end in the same three
+//! letters, will score the same from then on: only the better one is kept, as in the Viterbi
+//! algorithm. The beam keeps the best `beam_width` of what is left.
+//!
+//! Both streams are English, so the attack cannot tell which one is the key: a pair and its swap
+//! score the same, and only one of them is reported. For the same reason, the streams often
+//! cross over where both are between words, and the recovered plain text goes on in the key.
+//!
+//! The attack does not break a message on its own. On a few hundred letters of ordinary prose it
+//! gets the letters at around half the places right, most of them in runs of whole words, with
+//! the rest common words that fit both ways. That is a start for the work of old, done by hand:
+//! reading words off either stream, and dragging them through the cryptogram to see what they
+//! give in the other (with [`crate::running_key::decipher`]). The word list is English, whatever
+//! the language of the model.
+
+use crate::common::{self, AsciiUppercaseByte};
+use crate::crack::Candidate;
+use crate::errors::Error;
+use crate::language_model::{LanguageModel, MAX_ORDER};
+use std::collections::{HashMap, HashSet};
+
+/// A beam width that keeps the best streams in reach on a few hundred letters, in a second or two
+pub const BEAM_WIDTH: usize = 500;
+
+// The share of words that are not on the list
+const UNKNOWN_WORDS: f64 = 0.1;
+
+// The log10 probability every letter of a word that is not on the list costs, on top of its
+// n-gram probability
+const UNKNOWN_LETTER: f64 = -2.0;
+
+// How much the n-gram probabilities of the letters count, against those of the words
+const NGRAM_WEIGHT: f64 = 0.5;
+
+// The log10 probability a letter loses for every letter of context dropped, when the model has
+// never seen the n-gram it ends
+const BACKOFF: f64 = -0.4;
+
+// Where one stream is in its current word
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+enum Word {
+    // Before the first letter
+    Start,
+    // Inside a word of the list, at a node of the trie
+    Listed(u32),
+    // Inside a word that is not on the list
+    Unlisted,
+}
+
+// Where one stream is: in its current word, and in its last letters
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+struct Stream {
+    word: Word,
+    // The last letters, at most MAX_ORDER - 1, as a base 26 number
+    context: u16,
+    length: u8,
+}
+
+impl Stream {
+    const START: Stream = Stream { word: Word::Start, context: 0, length: 0 };
+}
+
+// A pair of partial streams in the beam
+#[derive(Copy, Clone)]
+struct Hypothesis {
+    plain: Stream,
+    key: Stream,
+    score: f64,
+    // Where the hypothesis it extends is in the beam of the step before
+    parent: usize,
+    letter: u8,
+}
+
+// A node of the trie of listed words: the words starting with the letters that lead to it
+struct Node {
+    children: [u32; 26],
+    // The log10 probability of the word that ends here, if any
+    word: Option<f64>,
+    // The log10 probability of the likeliest word below
+    best: f64,
+}
+
+impl Node {
+    fn new() -> Node {
+        Node { children: [0; 26], word: None, best: f64::NEG_INFINITY }
+    }
+}
+
+// Scores streams letter by letter
+//
+// A listed word is paid for as it is read: each letter costs what it takes off the probability
+// of the likeliest word the stream could still be reading, and the end of the word the rest.
+// Otherwise the beam would favour streams halfway through long words, that have paid nothing yet.
+struct Scorer {
+    // The log10 probability of a letter after the ones before it, for n-grams of length n at
+    // index n - 1
+    conditional: Vec<Vec<f64>>,
+    trie: Vec<Node>,
+}
+
+impl Scorer {
+    fn new(model: &LanguageModel, words: &[&str]) -> Scorer {
+        let mut conditional: Vec<Vec<f64>> = Vec::new();
+
+        for n in 1..=MAX_ORDER {
+            let table = (0..26usize.pow(n as u32)).map(|i| {
+                let ngram = (0..n).rev().map(|j| AsciiUppercaseByte::from_index(i / 26usize.pow(j as u32) % 26)).collect::<Vec<AsciiUppercaseByte>>();
+                let log_probability = model.log_probability(&ngram);
+
+                if n == 1 {
+                    log_probability
+                }
+                else if log_probability > model.floor(n) {
+                    log_probability - model.log_probability(&ngram[..n - 1])
+                }
+                else {
+                    BACKOFF + conditional[n - 2][i % 26usize.pow(n as u32 - 1)]
+                }
+            }).collect();
+
+            conditional.push(table);
+        }
+
+        // Zipf's law: the word of rank r is r times less likely than the first
+        let harmonic = (1..=words.len()).map(|rank| 1.0 / rank as f64).sum::<f64>();
+
+        let mut trie = vec![Node::new()];
+
+        for (rank, word) in (1..).zip(words) {
+            let log_probability = ((1.0 - UNKNOWN_WORDS) / (rank as f64 * harmonic)).log10();
+            let mut node = 0;
+
+            for letter in word.bytes().map(|letter| (letter.to_ascii_uppercase() - b'A') as usize) {
+                if trie[node].children[letter] == 0 {
+                    trie[node].children[letter] = trie.len() as u32;
+                    trie.push(Node::new());
+                }
+
+                node = trie[node].children[letter] as usize;
+                trie[node].best = trie[node].best.max(log_probability);
+            }
+
+            trie[node].word = Some(trie[node].word.map_or(log_probability, |other| other.max(log_probability)));
+        }
+
+        Scorer { conditional, trie }
+    }
+
+    // Every way `stream` can go on with `letter`, and what it costs
+    fn next(&self, stream: Stream, letter: usize, words: &mut Vec<(Word, f64)>, next: &mut Vec<(Stream, f64)>) {
+        words.clear();
+        next.clear();
+
+        match stream.word {
+            Word::Start => self.start_word(letter, 0.0, words),
+            Word::Listed(node) => {
+                let node = &self.trie[node as usize];
+                let child = node.children[letter];
+
+                if child != 0 {
+                    words.push((Word::Listed(child), self.trie[child as usize].best - node.best));
+                }
+
+                if let Some(word) = node.word {
+                    self.start_word(letter, word - node.best, words);
+                }
+            }
+            Word::Unlisted => {
+                words.push((Word::Unlisted, UNKNOWN_LETTER));
+
+                self.start_word(letter, 0.0, words);
+            }
+        }
+
+        let order = stream.length as usize + 1;
+        let ngram = stream.context as usize * 26 + letter;
+        let letter_score = NGRAM_WEIGHT * self.conditional[order - 1][ngram];
+
+        let length = order.min(MAX_ORDER - 1);
+        let context = (ngram % 26usize.pow(length as u32)) as u16;
+
+        next.extend(words.iter().map(|&(word, cost)| (Stream { word, context, length: length as u8 }, cost + letter_score)));
+    }
+
+    // The ways a new word can start with `letter`, after a word that cost `end`
+    fn start_word(&self, letter: usize, end: f64, words: &mut Vec<(Word, f64)>) {
+        let child = self.trie[0].children[letter];
+
+        if child != 0 {
+            words.push((Word::Listed(child), end + self.trie[child as usize].best));
+        }
+
+        words.push((Word::Unlisted, end + UNKNOWN_WORDS.log10() + UNKNOWN_LETTER));
+    }
+}
+
+/// Attempts to break `cipher_text`, enciphered with a running key, when both the plain text and
+/// the key text are in the language of `model`
+///
+/// At most `beam_width` partial solutions are kept at each letter: a wider beam is slower, but
+/// less likely to lose the right one. Candidates are returned best first, each with the key
+/// stream as its key.
+pub fn crack(cipher_text: &[u8], model: &LanguageModel, beam_width: usize) -> Result<Vec<Candidate>, Error> {
+    let text = common::sanitize_text(cipher_text)?.into_iter().map(|letter| letter.index()).collect::<Vec<usize>>();
+
+    if text.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let words = include_str!("../data/english.words").lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                    .collect::<Vec<&str>>();
+
+    let scorer = Scorer::new(model, &words);
+
+    let mut beam = vec![Hypothesis { plain: Stream::START, key: Stream::START, score: 0.0, parent: 0, letter: 0 }];
+    let mut history = Vec::new();
+
+    let (mut words, mut plain_streams, mut key_streams) = (Vec::new(), Vec::new(), Vec::new());
+
+    for &cipher_letter in &text {
+        let mut next: Vec<Hypothesis> = Vec::new();
+        let mut found: HashMap<(Stream, Stream), usize> = HashMap::new();
+
+        for (parent, hypothesis) in beam.iter().enumerate() {
+            for letter in 0..26 {
+                scorer.next(hypothesis.plain, letter, &mut words, &mut plain_streams);
+                scorer.next(hypothesis.key, (cipher_letter + 26 - letter) % 26, &mut words, &mut key_streams);
+
+                for &(plain, plain_cost) in &plain_streams {
+                    for &(key, key_cost) in &key_streams {
+                        let extended = Hypothesis { plain, key, score: hypothesis.score + plain_cost + key_cost, parent, letter: letter as u8 };
+
+                        match found.get(&(plain, key)) {
+                            Some(&i) if extended.score > next[i].score => next[i] = extended,
+                            Some(_) => (),
+                            None => {
+                                found.insert((plain, key), next.len());
+                                next.push(extended);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        beam = next;
+        beam.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
+        beam.truncate(beam_width.max(1));
+
+        history.push(beam.iter().map(|h| (h.parent, h.letter)).collect::<Vec<(usize, u8)>>());
+    }
+
+    let mut candidates: Vec<Candidate> = Vec::new();
+    let mut seen = HashSet::new();
+
+    for (i, hypothesis) in beam.iter().enumerate() {
+        let plain = trace_back(&history, i);
+
+        // The same letters read as other words, or the pair with the streams swapped, is the same
+        // solution
+        if seen.contains(&plain) {
+            continue;
+        }
+
+        let key = plain.iter().zip(&text).map(|(&p, &c)| (c + 26 - p) % 26).collect::<Vec<usize>>();
+
+        candidates.push(Candidate {
+            key: key.iter().map(|&k| char::from(AsciiUppercaseByte::from_index(k))).collect(),
+            plain_text: common::format_output(plain.iter().map(|&p| AsciiUppercaseByte::from_index(p)).collect()),
+            score: hypothesis.score,
+        });
+
+        seen.insert(plain);
+        seen.insert(key);
+    }
+
+    Ok(candidates)
+}
+
+// Follows the parents of the `i`th hypothesis of the last beam back to the start, and returns its plain letters
+fn trace_back(history: &[Vec<(usize, u8)>], mut i: usize) -> Vec<usize> {
+    let mut letters = Vec::with_capacity(history.len());
+
+    for step in history.iter().rev() {
+        let (parent, letter) = step[i];
+
+        letters.push(letter as usize);
+        i = parent;
+    }
+
+    letters.reverse();
+
+    letters
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::common;
+    use crate::crack::running_key;
+    use crate::crack::PLAIN_TEXT;
+    use crate::language_model::LanguageModel;
+    use crate::running_key as cipher;
+
+    const KEY_TEXT: &[u8] = b"Call me Ishmael. Some years ago, never mind how long precisely, having little \
+        or no money in my purse, and nothing particular to interest me on shore.";
+
+    #[test]
+    fn test_crack() {
+        // The key text runs out after the first sentence
+        let plain_text = &PLAIN_TEXT[..=PLAIN_TEXT.iter().position(|&c| c == b'.').unwrap()];
+
+        let cipher_text = cipher::encipher(KEY_TEXT, 0, plain_text).unwrap();
+
+        let candidates = running_key::crack(cipher_text.as_bytes(), &LanguageModel::english(), running_key::BEAM_WIDTH).unwrap();
+
+        let plain = common::sanitize_text(plain_text).unwrap();
+        let key = common::sanitize_text(KEY_TEXT).unwrap();
+
+        // The recovered key deciphers the cryptogram
+        assert_eq!(cipher::decipher(candidates[0].key.as_bytes(), 0, cipher_text.as_bytes()).unwrap(), candidates[0].plain_text);
+
+        // The streams cross over, so a letter is right when it is that of either stream. At least
+        // two in five are, where n-grams alone got under a third.
+        let recovered = common::sanitize_text(candidates[0].plain_text.as_bytes()).unwrap();
+        let right = recovered.iter().zip(plain.iter().zip(&key)).filter(|&(r, (p, k))| r == p || r == k).count();
+
+        assert!(right * 5 >= plain.len() * 2);
+
+        // And no pair is reported twice, the streams swapped
+        for (i, a) in candidates.iter().take(20).enumerate() {
+            for b in candidates.iter().take(20).skip(i + 1) {
+                assert!(a.key != b.key && common::sanitize_text(a.plain_text.as_bytes()).unwrap() != common::sanitize_text(b.key.as_bytes()).unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn test_empty() {
+        assert!(running_key::crack(b"", &LanguageModel::english(), 10).unwrap().is_empty());
+    }
+}
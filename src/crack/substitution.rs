@@ -0,0 +1,174 @@
+//! # Breaking simple substitution
+//!
+//! With 26! possible keys, trying them all is out of the question. Instead we _climb_:
+//!
+//! 1. Start from a key that matches the most frequent cipher letters with the most frequent
+//!    letters of English, with a few random swaps, and decipher the cryptogram with it.
+//! 2. Swap two letters of the key. If the text now looks more like English, keep the swap,
+//!    otherwise undo it.
+//! 3. Repeat until the iteration budget runs out.
+//!
+//! How much a text looks like English is measured with the quadgram fitness of a
+//! [`LanguageModel`]. A climb can get stuck on a key that no single swap improves, so the
+//! search restarts several times, each time from a slightly different key, and keeps the best
+//! results.
+
+use crate::analysis;
+use crate::common::{self, AsciiUppercaseByte};
+use crate::crack::{Candidate, SearchSettings};
+use crate::errors::Error;
+use crate::language_model::LanguageModel;
+use crate::substitution;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::convert::TryFrom;
+
+// Number of random swaps applied to the starting key of every climb
+const PERTURBATIONS: usize = 8;
+
+/// Attempts to break `cipher_text`, enciphered with a simple substitution, without knowing the key
+///
+/// Decryptions are scored against `model`, the language of the plain text.
+/// Every climb yields one candidate, whose key is the cipher alphabet (the letters A-Z are
+/// enciphered as the 1st, 2nd, ... letters of the key). Candidates are returned best first,
+/// and a key is never reported twice.
+pub fn crack(cipher_text: &[u8], model: &LanguageModel, settings: &SearchSettings) -> Result<Vec<Candidate>, Error> {
+    let text = common::sanitize_text(cipher_text)?;
+
+    let mut rng = StdRng::seed_from_u64(settings.seed);
+
+    let mut candidates: Vec<Candidate> = Vec::new();
+
+    for _ in 0..settings.restarts.max(1) {
+        let (decryption, score) = climb(&text, model, &mut rng, settings.iterations);
+
+        let key = cipher_alphabet(&decryption);
+
+        if candidates.iter().any(|c| c.key == key) {
+            continue;
+        }
+
+        candidates.push(Candidate {
+            plain_text: substitution::decipher(key.as_bytes(), cipher_text)?,
+            key,
+            score,
+        });
+    }
+
+    candidates.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
+
+    Ok(candidates)
+}
+
+// Climbs from a perturbed frequency key. Returns the best key found, as the plain letter of every cipher letter,
+// and its fitness.
+fn climb(text: &[AsciiUppercaseByte], model: &LanguageModel, rng: &mut StdRng, iterations: usize) -> (Vec<AsciiUppercaseByte>, f64) {
+    let mut decryption = frequency_key(text);
+
+    for _ in 0..PERTURBATIONS {
+        decryption.swap(rng.gen_range(0, 26), rng.gen_range(0, 26));
+    }
+
+    let mut best = model.fitness(&apply(&decryption, text));
+
+    for _ in 0..iterations {
+        let (i, j) = (rng.gen_range(0, 26), rng.gen_range(0, 26));
+
+        if i == j {
+            continue;
+        }
+
+        decryption.swap(i, j);
+
+        let score = model.fitness(&apply(&decryption, text));
+
+        if score > best {
+            best = score;
+        } else {
+            decryption.swap(i, j);
+        }
+    }
+
+    (decryption, best)
+}
+
+// Matches the letters of `text`, from most to least frequent, with the letters of English
+fn frequency_key(text: &[AsciiUppercaseByte]) -> Vec<AsciiUppercaseByte> {
+    let mut counts = [0usize; 26];
+
+    for letter in text {
+        counts[(letter.get_byte() - b'A') as usize] += 1;
+    }
+
+    let mut cipher_letters = (0..26).collect::<Vec<usize>>();
+    cipher_letters.sort_by(|&a, &b| counts[b].cmp(&counts[a]));
+
+    let mut plain_letters = (0..26).collect::<Vec<usize>>();
+    plain_letters.sort_by(|&a, &b| analysis::ENGLISH_FREQUENCIES[b].partial_cmp(&analysis::ENGLISH_FREQUENCIES[a]).unwrap());
+
+    let mut decryption = vec![AsciiUppercaseByte::try_from(b'A').unwrap(); 26];
+
+    for (&cipher_letter, &plain_letter) in cipher_letters.iter().zip(&plain_letters) {
+        decryption[cipher_letter] = AsciiUppercaseByte::try_from(b'A' + plain_letter as u8).unwrap();
+    }
+
+    decryption
+}
+
+// Deciphers `text` with a key giving the plain letter of every cipher letter
+fn apply(decryption: &[AsciiUppercaseByte], text: &[AsciiUppercaseByte]) -> Vec<AsciiUppercaseByte> {
+    text.iter().map(|&letter| decryption[(letter.get_byte() - b'A') as usize]).collect()
+}
+
+// Turns a key giving the plain letter of every cipher letter into a cipher alphabet
+fn cipher_alphabet(decryption: &[AsciiUppercaseByte]) -> String {
+    let mut alphabet = vec![b'A'; 26];
+
+    for (cipher_letter, plain_letter) in (b'A'..=b'Z').zip(decryption) {
+        alphabet[(plain_letter.get_byte() - b'A') as usize] = cipher_letter;
+    }
+
+    String::from_utf8(alphabet).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::common;
+    use crate::crack::PLAIN_TEXT;
+    use crate::crack::SearchSettings;
+    use crate::crack::substitution;
+    use crate::language_model::LanguageModel;
+    use crate::substitution::{self as cipher, Arrangement, Mixing};
+
+    #[test]
+    fn test_crack() {
+        let key = cipher::keyword_cipher_alphabet(b"PEMBERLEY", Mixing::Columnar, Arrangement::K2).unwrap();
+        let key = key.iter().map(|k| k.get_byte()).collect::<Vec<u8>>();
+
+        let cipher_text = cipher::encipher(&key, PLAIN_TEXT).unwrap();
+
+        let settings = SearchSettings { seed: 7, restarts: 3, iterations: 3000 };
+
+        let candidates = substitution::crack(cipher_text.as_bytes(), &LanguageModel::english(), &settings).unwrap();
+
+        let recovered = common::sanitize_text(candidates[0].plain_text.as_bytes()).unwrap();
+        let expected = common::sanitize_text(PLAIN_TEXT).unwrap();
+
+        // Letters that appear once or twice (here the X of "fixed") can be mistaken for one another
+        let wrong = recovered.iter().zip(&expected).filter(|(r, e)| r != e).count();
+
+        assert!(wrong <= 2, "{} letters wrong in {}", wrong, candidates[0].plain_text);
+    }
+
+    #[test]
+    fn test_crack_is_deterministic() {
+        let cipher_text = cipher::encipher(b"QWERTYUIOPASDFGHJKLZXCVBNM", PLAIN_TEXT).unwrap();
+
+        let settings = SearchSettings { seed: 42, restarts: 2, iterations: 500 };
+
+        assert_eq!(
+            substitution::crack(cipher_text.as_bytes(), &LanguageModel::english(), &settings).unwrap(),
+            substitution::crack(cipher_text.as_bytes(), &LanguageModel::english(), &settings).unwrap()
+        );
+    }
+}
@@ -0,0 +1,201 @@
+//! # Breaking the standard Vigenere cipher
+//!
+//! The attack works in two stages.
+//!
+//! First we find the length of the key (the _period_):
+//!
+//! - The Kasiski examination looks for sequences of three or more letters that are repeated in
+//!   the cipher text. These are usually the same plain text enciphered by the same part of the key,
+//!   so the distance between them tends to be a multiple of the period.
+//! - The index of coincidence test splits the cipher text into `period` columns (_cosets_). Under the
+//!   right period, each coset was enciphered with a single letter, so it keeps the "roughness" of English.
+//!
+//! Once we have a period, each coset is a simple Caesar cipher. We try all 26 shifts and keep the one
+//! whose letter frequencies are closest to English according to the chi-squared statistic.
+
+use crate::analysis;
+use crate::common;
+use crate::common::AsciiUppercaseByte;
+use crate::crack::Candidate;
+use crate::errors::Error;
+use crate::vigenere_standard;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+/// Statistics gathered about a possible key length
+#[derive(Debug, Clone, PartialEq)]
+pub struct PeriodEstimate {
+    /// The key length being considered
+    pub period: usize,
+    /// Number of repeated-sequence spacings that are a multiple of `period`
+    pub kasiski_count: usize,
+    /// Average index of coincidence of the `period` cosets
+    pub index_of_coincidence: f64,
+    /// Combined score used for ranking. Higher is better.
+    pub score: f64,
+}
+
+/// Runs the Kasiski examination on `text`
+///
+/// Returns the spacings between consecutive occurrences of every repeated trigram.
+/// Longer repeated sequences contain several repeated trigrams, so they weigh more.
+pub fn kasiski_spacings(text: &[AsciiUppercaseByte]) -> Vec<usize> {
+    let mut last_seen = HashMap::new();
+    let mut spacings = Vec::new();
+
+    for (i, trigram) in text.windows(3).enumerate() {
+        if let Some(previous) = last_seen.insert(trigram, i) {
+            spacings.push(i - previous);
+        }
+    }
+
+    spacings
+}
+
+/// Computes the average index of coincidence of the cosets of `text` under `period`
+pub fn coset_index_of_coincidence(text: &[AsciiUppercaseByte], period: usize) -> f64 {
+    let cosets = split_cosets(text, period);
+
+    cosets.iter()
+            .map(|coset| analysis::index_of_coincidence(&analysis::count_letters(coset)))
+            .sum::<f64>() / period as f64
+}
+
+/// Ranks every key length from 1 to `max_period`, most likely first
+///
+/// A period scores well when its cosets look like English (high index of coincidence) and
+/// when it divides many of the Kasiski spacings. The Kasiski share is what lets the true
+/// period beat its own multiples, which have an equally good index of coincidence.
+pub fn rank_periods(text: &[AsciiUppercaseByte], max_period: usize) -> Vec<PeriodEstimate> {
+    let spacings = kasiski_spacings(text);
+
+    let mut estimates = Vec::new();
+
+    for period in 1..=max_period.min(text.len()) {
+        let kasiski_count = spacings.iter().filter(|&&spacing| spacing % period == 0).count();
+
+        let index_of_coincidence = coset_index_of_coincidence(text, period);
+
+        // Every spacing is a multiple of 1, so the Kasiski share tells us nothing about it
+        let kasiski_share = if period == 1 || spacings.is_empty() {
+            0.0
+        } else {
+            kasiski_count as f64 / spacings.len() as f64
+        };
+
+        estimates.push(PeriodEstimate {
+            period,
+            kasiski_count,
+            index_of_coincidence,
+            score: index_of_coincidence * (1.0 + kasiski_share),
+        });
+    }
+
+    estimates.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
+
+    estimates
+}
+
+/// Recovers the most likely key of length `period` by solving each coset as a Caesar cipher
+pub fn recover_key(text: &[AsciiUppercaseByte], period: usize) -> Vec<AsciiUppercaseByte> {
+    split_cosets(text, period).iter().map(|coset| {
+        (b'A'..=b'Z')
+            .map(|shift| AsciiUppercaseByte::try_from(shift).unwrap())
+            .map(|shift| {
+                let shifted = coset.iter().map(|&c| c - shift).collect::<Vec<AsciiUppercaseByte>>();
+                (shift, analysis::chi_squared(&shifted))
+            })
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+            .unwrap()
+            .0
+    })
+    .collect()
+}
+
+/// Attempts to break `cipher_text`, enciphered with the standard Vigenere cipher, without knowing the key
+///
+/// Key lengths from 1 to `max_period` are considered. Candidates are returned best first
+/// and a key is never reported twice.
+pub fn crack(cipher_text: &[u8], max_period: usize) -> Result<Vec<Candidate>, Error> {
+    let text = common::sanitize_text(cipher_text)?;
+
+    let mut candidates: Vec<Candidate> = Vec::new();
+
+    for estimate in rank_periods(&text, max_period) {
+        let key = shortest_repeating_unit(recover_key(&text, estimate.period));
+
+        let key = String::from_utf8(key.iter().map(|k| k.get_byte()).collect()).unwrap();
+
+        if candidates.iter().any(|c| c.key == key) {
+            continue;
+        }
+
+        candidates.push(Candidate {
+            plain_text: vigenere_standard::decipher(key.as_bytes(), cipher_text)?,
+            key,
+            score: estimate.score,
+        });
+    }
+
+    Ok(candidates)
+}
+
+// Splits `text` into `period` columns, the ith column holding every letter enciphered by the ith key letter
+pub(crate) fn split_cosets(text: &[AsciiUppercaseByte], period: usize) -> Vec<Vec<AsciiUppercaseByte>> {
+    let mut cosets = vec![Vec::new(); period];
+
+    for (i, &letter) in text.iter().enumerate() {
+        cosets[i % period].push(letter);
+    }
+
+    cosets
+}
+
+// Reduces keys like "LEMONLEMON" to "LEMON"
+fn shortest_repeating_unit(key: Vec<AsciiUppercaseByte>) -> Vec<AsciiUppercaseByte> {
+    for length in 1..key.len() {
+        if key.len().is_multiple_of(length) && key.chunks(length).all(|chunk| chunk == &key[..length]) {
+            return key[..length].to_vec();
+        }
+    }
+
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::common;
+    use crate::crack::vigenere;
+    use crate::crack::PLAIN_TEXT;
+    use crate::vigenere_standard;
+
+    #[test]
+    fn test_kasiski_spacings() {
+        let text = common::sanitize_text(b"THEABCDTHE").unwrap();
+
+        assert_eq!(vigenere::kasiski_spacings(&text), vec![7]);
+    }
+
+    #[test]
+    fn test_rank_periods() {
+        let cipher_text = vigenere_standard::encipher(b"LEMON", PLAIN_TEXT).unwrap();
+        let cipher_text = common::sanitize_text(cipher_text.as_bytes()).unwrap();
+
+        let estimates = vigenere::rank_periods(&cipher_text, 20);
+
+        assert_eq!(estimates[0].period, 5);
+    }
+
+    #[test]
+    fn test_crack() {
+        let cipher_text = vigenere_standard::encipher(b"CIPHERS", PLAIN_TEXT).unwrap();
+
+        let candidates = vigenere::crack(cipher_text.as_bytes(), 20).unwrap();
+
+        assert_eq!(candidates[0].key, "CIPHERS");
+        assert_eq!(
+            candidates[0].plain_text,
+            common::format_output(common::sanitize_text(PLAIN_TEXT).unwrap())
+        );
+    }
+}
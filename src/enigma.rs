@@ -0,0 +1,615 @@
+//! # Simulation of the Enigma cipher machine
+//!
+//! The Enigma is an electric rotor machine. Pressing a key sends a current through:
+//!
+//! 1. the _plugboard_, which swaps pairs of letters;
+//! 2. three (on the naval M4, four) _rotors_, right to left, each wired as a scrambled alphabet;
+//! 3. the _reflector_, which swaps letters in pairs and sends the current back;
+//! 4. the rotors again, left to right, and the plugboard again, to light a lamp.
+//!
+//! Because the reflector sends the current back the way it came, the machine is its own inverse:
+//! with the same settings, typing the cipher text gives back the plain text. It also means no
+//! letter ever enciphers to itself.
+//!
+//! Before the current flows, the rotors move on like an odometer: the right rotor steps at every
+//! key press, and each _notch_ on a rotor lets it carry its left neighbour along. The ratchets
+//! that read the notches make the middle rotor step again on the very next press, so it moves
+//! twice in a row (_double stepping_). The fourth rotor of the M4 (Beta or Gamma) never steps.
+//!
+//! The _ring setting_ (Ringstellung) turns a rotor's wiring against the letters on its rim and its
+//! notches, and the letters showing in the windows when enciphering starts are the _start
+//! positions_ (Grundstellung).
+//!
+//! # Example:
+//!
+//! Model: M3, reflector B, rotors I II III, rings AAA, no plugs
+//!
+//! Start positions: AAA
+//!
+//! Plain text: AAAAA
+//!
+//! Cipher text: BDZGO
+
+use crate::alphabet::{Alphabet, Symbol};
+use crate::cipher::{Cipher, KeyString, KeyedCipher};
+use crate::common::{self, AsciiUppercaseByte};
+use crate::errors::Error;
+use crate::passthrough::{KeyAdvance, MaskedText};
+
+/// The wiring of a rotor or reflector, as the letters its contacts A to Z lead to
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Wiring {
+    /// The name stamped on the rotor
+    pub name: &'static str,
+    /// Where each contact leads, with the ring at A
+    pub wiring: &'static [u8; 26],
+    /// The letters showing in the window when the rotor carries its neighbour along
+    pub notches: &'static [u8],
+}
+
+/// The eight rotors of the Wehrmacht and Kriegsmarine machines. VI to VIII were naval only.
+pub const ROTORS: &[Wiring] = &[
+    Wiring { name: "I", wiring: b"EKMFLGDQVZNTOWYHXUSPAIBRCJ", notches: b"Q" },
+    Wiring { name: "II", wiring: b"AJDKSIRUXBLHWTMCQGZNPYFVOE", notches: b"E" },
+    Wiring { name: "III", wiring: b"BDFHJLCPRTXVZNYEIWGAKMUSQO", notches: b"V" },
+    Wiring { name: "IV", wiring: b"ESOVPZJAYQUIRHXLNFTGKDCMWB", notches: b"J" },
+    Wiring { name: "V", wiring: b"VZBRGITYUPSDNHLXAWMJQOFECK", notches: b"Z" },
+    Wiring { name: "VI", wiring: b"JPGVOUMFYQBENHZRDKASXLICTW", notches: b"ZM" },
+    Wiring { name: "VII", wiring: b"NZJHGRCXMYSWBOUFAIVLPEKQDT", notches: b"ZM" },
+    Wiring { name: "VIII", wiring: b"FKQHTLXOCBJSPDZRAMEWNIUYGV", notches: b"ZM" },
+];
+
+/// The thin fourth rotors of the M4, which sit beside the reflector and never step
+pub const GREEK_ROTORS: &[Wiring] = &[
+    Wiring { name: "Beta", wiring: b"LEYJVCNIXWPBQMDRTAKZGFUHOS", notches: b"" },
+    Wiring { name: "Gamma", wiring: b"FSOKANUERHMBTIYCWLQPZXVGJD", notches: b"" },
+];
+
+/// The reflectors: B and C for the M3, and their thin versions for the M4
+pub const REFLECTORS: &[Wiring] = &[
+    Wiring { name: "B", wiring: b"YRUHQSLDPXNGOKMIEBFZCWVJAT", notches: b"" },
+    Wiring { name: "C", wiring: b"FVPJIAOYEDRZXWGCTKUQSBNMHL", notches: b"" },
+    Wiring { name: "B-thin", wiring: b"ENKQAUYWJICOPBLMDXZVFTHRGS", notches: b"" },
+    Wiring { name: "C-thin", wiring: b"RDOBJNTKVEHMLFCWZAXGYIPSUQ", notches: b"" },
+];
+
+/// The model of the machine, which decides which rotors and reflectors fit
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Model {
+    /// The three-rotor machine of the army, air force and navy, with reflector B or C
+    M3,
+    /// The four-rotor naval machine: Beta or Gamma, three rotors, and a thin reflector
+    M4,
+}
+
+impl Model {
+    /// The number of rotors the machine holds
+    pub fn rotor_count(self) -> usize {
+        match self {
+            Model::M3 => 3,
+            Model::M4 => 4,
+        }
+    }
+
+    fn default_rotors(self) -> &'static str {
+        match self {
+            Model::M3 => "I II III",
+            Model::M4 => "Beta I II III",
+        }
+    }
+
+    fn default_reflector(self) -> &'static str {
+        match self {
+            Model::M3 => "B",
+            Model::M4 => "B-thin",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Rotor {
+    wiring: &'static Wiring,
+    forward: [u8; 26],
+    backward: [u8; 26],
+    ring: u8,
+    position: u8,
+}
+
+impl Rotor {
+    fn new(wiring: &'static Wiring) -> Rotor {
+        let mut forward = [0; 26];
+        let mut backward = [0; 26];
+
+        for (i, &letter) in wiring.wiring.iter().enumerate() {
+            forward[i] = letter - b'A';
+            backward[(letter - b'A') as usize] = i as u8;
+        }
+
+        Rotor { wiring, forward, backward, ring: 0, position: 0 }
+    }
+
+    fn at_notch(&self) -> bool {
+        self.wiring.notches.contains(&(self.position + b'A'))
+    }
+
+    fn advance(&mut self) {
+        self.position = (self.position + 1) % 26;
+    }
+
+    fn pass(&self, table: &[u8; 26], contact: u8) -> u8 {
+        let shift = (26 + self.position - self.ring) % 26;
+
+        (table[((contact + shift) % 26) as usize] + 26 - shift) % 26
+    }
+}
+
+/// An Enigma machine, with its rotors in place and set
+///
+/// A machine is also the key of [`Enigma`]. As a string, the key is the start positions (e.g.
+/// `ABC`), with the settings `rotors` (left to right, e.g. `II IV V`, or `Beta II IV I` on the
+/// M4), `reflector` (`B`, `C`, `B-thin` or `C-thin`), `rings` (letters, `BUL`, or numbers,
+/// `02 21 12`) and `plugboard` (pairs of letters, `AV BS CG`). Left out, the rotors are I II III
+/// (Beta I II III on the M4), the reflector is B (B-thin), the rings and start positions are all A,
+/// and the plugboard is empty.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Machine {
+    model: Model,
+    reflector: &'static Wiring,
+    // Left to right: on the M4, the Greek rotor comes first
+    rotors: Vec<Rotor>,
+    plugboard: [u8; 26],
+}
+
+impl Machine {
+    /// Builds a machine of `model` with the `rotors` (by name, left to right) and `reflector`
+    ///
+    /// The rings and start positions are all at A, and the plugboard is empty. Returns an error if
+    /// a name is unknown, if a rotor is used twice, or if the parts do not fit the model.
+    pub fn new(model: Model, rotors: &[&str], reflector: &str) -> Result<Machine, Error> {
+        if rotors.len() != model.rotor_count() {
+            return Err(Error::KeyError(format!("The {:?} holds {} rotors, not {}", model, model.rotor_count(), rotors.len())));
+        }
+
+        let reflector = find(REFLECTORS, reflector)
+            .ok_or_else(|| Error::KeyError(format!("Unknown reflector '{}': expected B, C, B-thin or C-thin", reflector)))?;
+
+        let thin = reflector.name.ends_with("-thin");
+
+        if thin != (model == Model::M4) {
+            return Err(Error::KeyError(format!("Reflector {} does not fit the {:?}", reflector.name, model)));
+        }
+
+        let mut wheels = Vec::new();
+
+        for (i, &name) in rotors.iter().enumerate() {
+            let greek_slot = model == Model::M4 && i == 0;
+
+            let wiring = match (find(ROTORS, name), find(GREEK_ROTORS, name)) {
+                (Some(wiring), None) if !greek_slot => wiring,
+                (None, Some(wiring)) if greek_slot => wiring,
+                (None, None) => return Err(Error::KeyError(format!("Unknown rotor '{}'", name))),
+                _ if greek_slot => return Err(Error::KeyError("The leftmost rotor of the M4 must be Beta or Gamma".to_string())),
+                _ => return Err(Error::KeyError(format!("Rotor {} only fits the leftmost slot of the M4", name))),
+            };
+
+            if wheels.iter().any(|rotor: &Rotor| rotor.wiring == wiring) {
+                return Err(Error::KeyError(format!("Rotor {} is used twice", wiring.name)));
+            }
+
+            wheels.push(Rotor::new(wiring));
+        }
+
+        let mut plugboard = [0; 26];
+
+        for (i, plug) in plugboard.iter_mut().enumerate() {
+            *plug = i as u8;
+        }
+
+        Ok(Machine { model, reflector, rotors: wheels, plugboard })
+    }
+
+    /// The model of the machine
+    pub fn model(&self) -> Model {
+        self.model
+    }
+
+    /// The names of the rotors, left to right
+    pub fn rotor_names(&self) -> Vec<&'static str> {
+        self.rotors.iter().map(|rotor| rotor.wiring.name).collect()
+    }
+
+    /// The name of the reflector
+    pub fn reflector_name(&self) -> &'static str {
+        self.reflector.name
+    }
+
+    /// Sets the rings of the rotors, left to right, A (or 01) being the unturned ring
+    pub fn set_rings(&mut self, rings: &[AsciiUppercaseByte]) -> Result<(), Error> {
+        self.check_count(rings.len(), "ring settings")?;
+
+        for (rotor, ring) in self.rotors.iter_mut().zip(rings) {
+            rotor.ring = ring.get_byte() - b'A';
+        }
+
+        Ok(())
+    }
+
+    /// Turns the rotors so that `positions` show in the windows, left to right
+    pub fn set_positions(&mut self, positions: &[AsciiUppercaseByte]) -> Result<(), Error> {
+        self.check_count(positions.len(), "start positions")?;
+
+        for (rotor, position) in self.rotors.iter_mut().zip(positions) {
+            rotor.position = position.get_byte() - b'A';
+        }
+
+        Ok(())
+    }
+
+    /// Plugs in the cables swapping each pair of letters
+    ///
+    /// Returns an error if a letter is plugged twice or to itself.
+    pub fn set_plugboard(&mut self, pairs: &[(AsciiUppercaseByte, AsciiUppercaseByte)]) -> Result<(), Error> {
+        let mut plugboard = [0; 26];
+
+        for (i, plug) in plugboard.iter_mut().enumerate() {
+            *plug = i as u8;
+        }
+
+        for &(a, b) in pairs {
+            let (a, b) = (a.get_byte() - b'A', b.get_byte() - b'A');
+
+            if a == b || plugboard[a as usize] != a || plugboard[b as usize] != b {
+                return Err(Error::KeyError(format!("{} and {} cannot be plugged together", char::from(a + b'A'), char::from(b + b'A'))));
+            }
+
+            plugboard.swap(a as usize, b as usize);
+        }
+
+        self.plugboard = plugboard;
+
+        Ok(())
+    }
+
+    /// The letters showing in the windows, left to right
+    pub fn positions(&self) -> Vec<AsciiUppercaseByte> {
+        self.rotors.iter().map(|rotor| AsciiUppercaseByte::from_index(rotor.position as usize)).collect()
+    }
+
+    /// Moves the rotors on, as pressing a key does before the current flows
+    pub fn step(&mut self) {
+        let count = self.rotors.len();
+        let (left, middle, right) = (count - 3, count - 2, count - 1);
+
+        // The middle rotor's own notch makes it step along with the left rotor: the double step
+        if self.rotors[middle].at_notch() {
+            self.rotors[middle].advance();
+            self.rotors[left].advance();
+        } else if self.rotors[right].at_notch() {
+            self.rotors[middle].advance();
+        }
+
+        self.rotors[right].advance();
+    }
+
+    /// Presses `key`: steps the rotors, then returns the letter that lights up
+    pub fn press(&mut self, key: AsciiUppercaseByte) -> AsciiUppercaseByte {
+        self.step();
+
+        let mut contact = self.plugboard[(key.get_byte() - b'A') as usize];
+
+        for rotor in self.rotors.iter().rev() {
+            contact = rotor.pass(&rotor.forward, contact);
+        }
+
+        contact = self.reflector.wiring[contact as usize] - b'A';
+
+        for rotor in &self.rotors {
+            contact = rotor.pass(&rotor.backward, contact);
+        }
+
+        AsciiUppercaseByte::from_index(self.plugboard[contact as usize] as usize)
+    }
+
+    fn check_count(&self, count: usize, what: &str) -> Result<(), Error> {
+        if count == self.rotors.len() {
+            Ok(())
+        } else {
+            Err(Error::KeyError(format!("The {:?} needs {} {}, not {}", self.model, self.rotors.len(), what, count)))
+        }
+    }
+}
+
+/// Enciphers (or, the machine being its own inverse, deciphers) `text` on an M3 with the
+/// `rotors` (left to right), reflector, rings and start positions, and no plugboard
+pub fn encipher(rotors: &[&str], reflector: &str, rings: &[u8], positions: &[u8], text: &[u8]) -> Result<String, Error> {
+    let mut machine = Machine::new(Model::M3, rotors, reflector)?;
+
+    machine.set_rings(&common::sanitize_text(rings)?)?;
+    machine.set_positions(&common::sanitize_text(positions)?)?;
+
+    Enigma::with_key(machine)?.encipher(text)
+}
+
+/// The Enigma machine, for use through the [`Cipher`] trait
+///
+/// The key is a [`Machine`], whose settings are written as described there. Every message starts
+/// from the machine as set, so one cipher enciphers any number of messages.
+pub struct Enigma {
+    machine: Machine,
+}
+
+impl KeyedCipher for Enigma {
+    type Key = Machine;
+
+    fn parse_key(key: &str) -> Result<Self::Key, Error> {
+        parse_key(key, Model::M3)
+    }
+
+    fn with_key(machine: Self::Key) -> Result<Self, Error> {
+        Ok(Enigma { machine })
+    }
+}
+
+impl Enigma {
+    /// The machine as set at the start of every message
+    pub fn machine(&self) -> &Machine {
+        &self.machine
+    }
+
+    fn run(&self, text: &[u8]) -> Result<String, Error> {
+        let mut machine = self.machine.clone();
+
+        let text = common::sanitize_text(text)?;
+
+        Ok(common::format_output(text.into_iter().map(|letter| machine.press(letter)).collect()))
+    }
+
+    fn run_passthrough(&self, text: &[u8], advance: KeyAdvance) -> String {
+        let alphabet = Alphabet::standard();
+        let masked = MaskedText::new(&alphabet, text);
+
+        let mut machine = self.machine.clone();
+        let mut steps = 0;
+
+        let letters = masked.letters()
+            .into_iter()
+            .zip(masked.key_positions(advance))
+            .map(|(symbol, position)| {
+                // Characters that are not enciphered still move the rotors on when the key advances on every character
+                for _ in steps..position {
+                    machine.step();
+                }
+
+                steps = position + 1;
+
+                let output = machine.press(AsciiUppercaseByte::from_index(symbol.index()));
+
+                alphabet.symbol((output.get_byte() - b'A') as usize)
+            })
+            .collect::<Vec<Symbol>>();
+
+        masked.unmask(&alphabet, &letters)
+    }
+}
+
+impl Cipher for Enigma {
+    fn encipher(&self, plain_text: &[u8]) -> Result<String, Error> {
+        self.run(plain_text)
+    }
+
+    fn decipher(&self, cipher_text: &[u8]) -> Result<String, Error> {
+        self.run(cipher_text)
+    }
+
+    fn encipher_passthrough(&self, plain_text: &[u8], advance: KeyAdvance) -> Result<String, Error> {
+        Ok(self.run_passthrough(plain_text, advance))
+    }
+
+    fn decipher_passthrough(&self, cipher_text: &[u8], advance: KeyAdvance) -> Result<String, Error> {
+        Ok(self.run_passthrough(cipher_text, advance))
+    }
+}
+
+/// The four-rotor naval Enigma, for use through the [`Cipher`] trait
+///
+/// The key is written as for [`Enigma`], with four rotors and four rings and start positions.
+pub struct EnigmaM4(Enigma);
+
+impl KeyedCipher for EnigmaM4 {
+    type Key = Machine;
+
+    fn parse_key(key: &str) -> Result<Self::Key, Error> {
+        parse_key(key, Model::M4)
+    }
+
+    fn with_key(machine: Self::Key) -> Result<Self, Error> {
+        Ok(EnigmaM4(Enigma::with_key(machine)?))
+    }
+}
+
+impl EnigmaM4 {
+    /// The machine as set at the start of every message
+    pub fn machine(&self) -> &Machine {
+        self.0.machine()
+    }
+}
+
+impl Cipher for EnigmaM4 {
+    fn encipher(&self, plain_text: &[u8]) -> Result<String, Error> {
+        self.0.encipher(plain_text)
+    }
+
+    fn decipher(&self, cipher_text: &[u8]) -> Result<String, Error> {
+        self.0.decipher(cipher_text)
+    }
+
+    fn encipher_passthrough(&self, plain_text: &[u8], advance: KeyAdvance) -> Result<String, Error> {
+        self.0.encipher_passthrough(plain_text, advance)
+    }
+
+    fn decipher_passthrough(&self, cipher_text: &[u8], advance: KeyAdvance) -> Result<String, Error> {
+        self.0.decipher_passthrough(cipher_text, advance)
+    }
+}
+
+fn parse_key(key: &str, model: Model) -> Result<Machine, Error> {
+    let key = KeyString::parse(key)?;
+
+    let rotors = key.setting("rotors").unwrap_or_else(|| model.default_rotors());
+    let rotors = rotors.split(|c: char| c.is_whitespace() || c == ',' || c == '-').filter(|name| !name.is_empty()).collect::<Vec<&str>>();
+
+    let mut machine = Machine::new(model, &rotors, key.setting("reflector").unwrap_or_else(|| model.default_reflector()))?;
+
+    if let Some(rings) = key.setting("rings") {
+        machine.set_rings(&parse_rings(rings)?)?;
+    }
+
+    if !key.main.is_empty() {
+        machine.set_positions(&common::sanitize_text(key.main.as_bytes())?)?;
+    }
+
+    if let Some(plugboard) = key.setting("plugboard") {
+        machine.set_plugboard(&parse_plugboard(plugboard)?)?;
+    }
+
+    Ok(machine)
+}
+
+// Ring settings are written as letters (BUL) or as the numbers on the rim (02 21 12)
+fn parse_rings(rings: &str) -> Result<Vec<AsciiUppercaseByte>, Error> {
+    if !rings.chars().any(|c| c.is_ascii_digit()) {
+        return common::sanitize_text(rings.as_bytes());
+    }
+
+    rings.split(|c: char| !c.is_ascii_digit())
+        .filter(|number| !number.is_empty())
+        .map(|number| match number.parse::<u8>() {
+            Ok(n @ 1..=26) => Ok(AsciiUppercaseByte::from_index((n - 1) as usize)),
+            _ => Err(Error::KeyError(format!("Invalid ring setting '{}': expected 1 to 26", number))),
+        })
+        .collect()
+}
+
+fn parse_plugboard(plugboard: &str) -> Result<Vec<(AsciiUppercaseByte, AsciiUppercaseByte)>, Error> {
+    plugboard.split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| match common::sanitize_text(pair.as_bytes())?[..] {
+            [a, b] => Ok((a, b)),
+            _ => Err(Error::KeyError(format!("Invalid plugboard pair '{}': expected two letters", pair))),
+        })
+        .collect()
+}
+
+fn find(wirings: &'static [Wiring], name: &str) -> Option<&'static Wiring> {
+    wirings.iter().find(|wiring| wiring.name.eq_ignore_ascii_case(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cipher::{Cipher, KeyedCipher};
+    use crate::common;
+    use crate::enigma::{self, Enigma, EnigmaM4, Machine, Model};
+    use crate::passthrough::KeyAdvance;
+    use quickcheck::quickcheck;
+
+    #[test]
+    fn test_enigma() {
+        assert_eq!(enigma::encipher(&["I", "II", "III"], "B", b"AAA", b"AAA", b"AAAAA").unwrap(), "BDZGO");
+        assert_eq!(enigma::encipher(&["I", "II", "III"], "B", b"AAA", b"AAA", b"BDZGO").unwrap(), "AAAAA");
+        assert_eq!(enigma::encipher(&["I", "II", "III"], "B", b"BBB", b"AAA", b"AAAAA").unwrap(), "EWTYX");
+    }
+
+    #[test]
+    fn test_double_stepping() {
+        let mut machine = Enigma::parse_key("ADU").unwrap();
+
+        let windows = (0..3)
+            .map(|_| {
+                machine.step();
+                machine.positions().into_iter().map(char::from).collect::<String>()
+            })
+            .collect::<Vec<String>>();
+
+        assert_eq!(windows, vec!["ADV", "AEW", "BFX"]);
+    }
+
+    #[test]
+    fn test_barbarossa() {
+        // The first part of a message of 7 July 1941, on an army M3
+        let enigma = Enigma::from_key_str("BLA;rotors=II IV V;reflector=B;rings=02 21 12;plugboard=AV BS CG DL FU HZ IN KM OW RX").unwrap();
+
+        let cipher_text = b"EDPUD NRGYS ZRCXN UYTPO MRMBO FKTBZ REZKM LXLVE FGUEY SIOZV EQMIK UBPMM YLKLT TDEIS \
+            MDICA GYKUA CTCDO MOHWX MUUIA UBSTS LRNBZ SZWNR FXWFY SSXJZ VIJHI DISHP RKLKA YUPAD TXQSP INQMA \
+            TLPIF SVKDA SCTAC DPBOP VHJK";
+
+        let plain_text = enigma.decipher(cipher_text).unwrap().split_whitespace().collect::<String>();
+
+        assert_eq!(plain_text, "AUFKLXABTEILUNGXVONXKURTINOWAXKURTINOWAXNORDWESTLXSEBEZXSEBEZXUAFFLIEGERSTRASZER\
+            IQTUNGXDUBROWKIXDUBROWKIXOPOTSCHKAXOPOTSCHKAXUMXEINSAQTDREINULLXUHRANGETRETENXAN\
+            GRIFFXINFXRGTX");
+    }
+
+    #[test]
+    fn test_m4() {
+        // A message to U-264, November 1942, on the naval M4
+        let enigma = EnigmaM4::from_key_str("VJNA;rotors=Beta II IV I;reflector=B-thin;rings=AAAV;plugboard=AT BL DF GJ HM NW OP QY RZ VX").unwrap();
+
+        let cipher_text = b"NCZW VUSX PNYM INHZ XMQX SFWX WLKJ AHSH NMCO CCAK UQPM KCSM HKSE INJU SBLK IOSX CKUB HMLL \
+            XCSJ USRR DVKO HULX WCCB GVLI YXEO AHXR HKKF VDRE WEZL XOBA FGYU JQUK GRTV UKAM EURB VEKS UHHV OYHA \
+            BCJW MAKL FKLM YFVN RIZR VVRT KOFD ANJM OLBG FFLE OPRG TFLV RHOW OPBE KVWM UQFM PWPA RMFH AGKX IIBG";
+
+        let plain_text = enigma.decipher(cipher_text).unwrap().split_whitespace().collect::<String>();
+
+        assert_eq!(plain_text, "VONVONJLOOKSJHFFTTTEINSEINSDREIZWOYYQNNSNEUNINHALTXXBEIANGRIFFUNTERWASSERGEDRUEC\
+            KTYWABOSXLETZTERGEGNERSTANDNULACHTDREINULUHRMARQUANTONJOTANEUNACHTSEYHSDREIYZWOZ\
+            WONULGRADYACHTSMYSTOSSENACHXEKNSVIERMBFAELLTYNNNNNNOOOVIERYSICHTEINSNULL");
+    }
+
+    #[test]
+    fn test_m4_matches_m3() {
+        // With Beta at A and the thin reflector B, the M4 behaves as an M3 with reflector B
+        let m3 = Enigma::from_key_str("QEV;rotors=I II III;reflector=B;rings=CDE").unwrap();
+        let m4 = EnigmaM4::from_key_str("AQEV;rotors=Beta I II III;reflector=B-thin;rings=ACDE").unwrap();
+
+        let plain_text = b"The middle rotor steps twice in a row when it reaches its own notch";
+
+        assert_eq!(m3.encipher(plain_text).unwrap(), m4.encipher(plain_text).unwrap());
+    }
+
+    #[test]
+    fn test_passthrough() {
+        let enigma = Enigma::from_key_str("AAA").unwrap();
+
+        assert_eq!(enigma.encipher_passthrough(b"Aa, a!", KeyAdvance::LettersOnly).unwrap(), "Bd, z!");
+        assert_eq!(enigma.encipher_passthrough(b"Aa, a!", KeyAdvance::EveryCharacter).unwrap(), "Bd, o!");
+    }
+
+    #[test]
+    fn test_invalid_keys() {
+        assert!(Enigma::from_key_str("AAA;rotors=I II").is_err());
+        assert!(Enigma::from_key_str("AAA;rotors=I I III").is_err());
+        assert!(Enigma::from_key_str("AAA;rotors=I II IX").is_err());
+        assert!(Enigma::from_key_str("AAA;rotors=Beta II III").is_err());
+        assert!(Enigma::from_key_str("AAA;reflector=B-thin").is_err());
+        assert!(Enigma::from_key_str("AAAA").is_err());
+        assert!(Enigma::from_key_str("AAA;rings=1 2 27").is_err());
+        assert!(Enigma::from_key_str("AAA;plugboard=AB BC").is_err());
+        assert!(Enigma::from_key_str("AAA;plugboard=AA").is_err());
+        assert!(Enigma::from_key_str("AAA;plugboard=ABC").is_err());
+        assert!(EnigmaM4::from_key_str("AAAA;rotors=I II III IV").is_err());
+        assert!(EnigmaM4::from_key_str("AAAA;reflector=B").is_err());
+        assert!(Machine::new(Model::M4, &["gamma", "vi", "vii", "viii"], "c-thin").is_ok());
+    }
+
+    quickcheck! {
+        fn enigma_round_trip(positions: Vec<u8>, plain_text: Vec<u8>) -> bool {
+            let positions = common::sanitize_text(&positions).unwrap().into_iter().map(char::from).chain("AAA".chars()).take(3).collect::<String>();
+
+            let enigma = Enigma::from_key_str(&format!("{};rotors=VI II VIII;rings=XYZ;plugboard=AZ QP", positions)).unwrap();
+
+            let enciphered = enigma.encipher(&plain_text).unwrap();
+
+            enigma.decipher(enciphered.as_bytes()).unwrap() == common::format_output(common::sanitize_text(&plain_text).unwrap())
+        }
+    }
+}
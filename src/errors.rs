@@ -1,10 +1,14 @@
 use std::fmt;
 
 #[derive(Debug)]
+#[allow(clippy::enum_variant_names)]
 pub enum Error {
     AsciiUppercaseError(String),
     EncipheringError(String),
-    DecipheringError(String)
+    DecipheringError(String),
+    KeyError(String),
+    AlphabetError(String),
+    ModelError(String),
 }
 
 impl fmt::Display for Error {
@@ -13,6 +17,9 @@ impl fmt::Display for Error {
             Error::EncipheringError(s) => write!(f, "Error: {}", s),
             Error::DecipheringError(s) => write!(f, "Error: {}", s),
             Error::AsciiUppercaseError(s) => write!(f, "Error: {}", s),
+            Error::KeyError(s) => write!(f, "Error: {}", s),
+            Error::AlphabetError(s) => write!(f, "Error: {}", s),
+            Error::ModelError(s) => write!(f, "Error: {}", s),
         }
     }
 }
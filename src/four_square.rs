@@ -0,0 +1,192 @@
+//! # Implementation of Delastelle's four-square cipher
+//!
+//! The four-square enciphers digraphs with four squares (see [`polybius`]) laid out in a larger
+//! square. The top left and bottom right squares hold the plain alphabet; the top right and bottom
+//! left squares are built from two keyphrases.
+//!
+//! ```text
+//! A B C D E   E X A M P
+//! F G H I J   L B C D F
+//! K L M N O   G H I J K
+//! P R S T U   N O R S T
+//! V W X Y Z   U V W Y Z
+//!
+//! K E Y W O   A B C D E
+//! R D A B C   F G H I J
+//! F G H I J   K L M N O
+//! L M N P S   P R S T U
+//! T U V X Z   V W X Y Z
+//! ```
+//!
+//! The first letter of a digraph is found in the top left square and the second in the bottom
+//! right square. They are two corners of a rectangle, and the digraph is replaced by the letters at
+//! the other two corners: first the one in the top right square, then the one in the bottom left
+//! square.
+//!
+//! Deciphering finds the cipher letters in the keyed squares, and reads the plain letters from the
+//! plain squares.
+//!
+//! # Example:
+//!
+//! Keyphrases: EXAMPLE and KEYWORD, with Q written as K (the squares above)
+//!
+//! Message to encipher: "HELP ME OBI WAN KENOBI"
+//!
+//! Digraphs: HE LP ME OB IW AN KE NO BI
+//!
+//! Cipher text: FYGMK YHOBX MFKKK IMD
+
+use crate::cipher::{Cipher, KeyString, KeyedCipher};
+use crate::common::{self, AsciiUppercaseByte};
+use crate::errors::Error;
+use crate::polybius::{self, Grid, Merge};
+
+/// Enciphers `plain_text` with the keyed squares built from `first_keyphrase` (top right) and
+/// `second_keyphrase` (bottom left), writing J as I and padding with X
+pub fn encipher(first_keyphrase: &[u8], second_keyphrase: &[u8], plain_text: &[u8]) -> Result<String, Error> {
+    FourSquare::with_key(default_key(first_keyphrase, second_keyphrase)?)?.encipher(plain_text)
+}
+
+/// Deciphers `cipher_text` with the keyed squares built from `first_keyphrase` (top right) and
+/// `second_keyphrase` (bottom left), writing J as I
+pub fn decipher(first_keyphrase: &[u8], second_keyphrase: &[u8], cipher_text: &[u8]) -> Result<String, Error> {
+    FourSquare::with_key(default_key(first_keyphrase, second_keyphrase)?)?.decipher(cipher_text)
+}
+
+/// The four-square cipher, for use through the [`Cipher`] trait
+///
+/// The key is the two keyed squares and the padding letter. As a string, it is the keyphrase of
+/// the top right square, with the settings `second` (the keyphrase of the bottom left square),
+/// `merge` (the letter left out of the squares, then the letter it is written as, default `JI`)
+/// and `pad` (default `X`).
+pub struct FourSquare {
+    plain: Grid,
+    first: Grid,
+    second: Grid,
+    merge: Merge,
+    pad: AsciiUppercaseByte,
+}
+
+impl KeyedCipher for FourSquare {
+    type Key = (Grid, Grid, AsciiUppercaseByte);
+
+    fn parse_key(key: &str) -> Result<Self::Key, Error> {
+        let key = KeyString::parse(key)?;
+
+        let merge = polybius::parse_merge(&key)?;
+
+        Ok((
+            Grid::letter_square(key.main.as_bytes(), merge)?,
+            Grid::letter_square(key.setting("second").unwrap_or("").as_bytes(), merge)?,
+            polybius::parse_pad(&key, merge)?,
+        ))
+    }
+
+    fn with_key((first, second, pad): Self::Key) -> Result<Self, Error> {
+        let merge = polybius::check_squares(&first, &second, pad)?;
+
+        let plain = Grid::letter_square(b"", merge)?;
+
+        Ok(FourSquare { plain, first, second, merge, pad })
+    }
+}
+
+impl FourSquare {
+    /// The keyed square at the top right
+    pub fn first(&self) -> &Grid {
+        &self.first
+    }
+
+    /// The keyed square at the bottom left
+    pub fn second(&self) -> &Grid {
+        &self.second
+    }
+}
+
+impl Cipher for FourSquare {
+    fn encipher(&self, plain_text: &[u8]) -> Result<String, Error> {
+        let plain_text = common::sanitize_text(plain_text)?;
+
+        let cipher_text = polybius::digraphs(&plain_text, self.merge, self.pad)
+            .into_iter()
+            .flat_map(|(a, b)| {
+                let (a, b) = polybius::opposite_corners((&self.plain, a), (&self.plain, b), (&self.first, &self.second));
+                vec![a, b]
+            })
+            .collect();
+
+        Ok(common::format_output(cipher_text))
+    }
+
+    fn decipher(&self, cipher_text: &[u8]) -> Result<String, Error> {
+        let cipher_text = common::sanitize_text(cipher_text)?;
+
+        polybius::check_even(&cipher_text, "Four-square")?;
+
+        let plain_text = polybius::digraphs(&cipher_text, self.merge, self.pad)
+            .into_iter()
+            .flat_map(|(a, b)| {
+                let (a, b) = polybius::opposite_corners((&self.first, a), (&self.second, b), (&self.plain, &self.plain));
+                vec![a, b]
+            })
+            .collect();
+
+        Ok(common::format_output(plain_text))
+    }
+}
+
+fn default_key(first_keyphrase: &[u8], second_keyphrase: &[u8]) -> Result<(Grid, Grid, AsciiUppercaseByte), Error> {
+    Ok((Grid::square(5, first_keyphrase)?, Grid::square(5, second_keyphrase)?, polybius::letter(b'X')))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cipher::{Cipher, KeyedCipher};
+    use crate::common;
+    use crate::four_square::{self, FourSquare};
+    use quickcheck::quickcheck;
+
+    #[test]
+    fn test_four_square() {
+        let four_square = FourSquare::from_key_str("EXAMPLE;second=KEYWORD;merge=QK").unwrap();
+
+        let enciphered = four_square.encipher(b"HELP ME OBI WAN KENOBI").unwrap();
+
+        assert_eq!(enciphered, "FYGMK YHOBX MFKKK IMD");
+
+        assert_eq!(four_square.decipher(enciphered.as_bytes()).unwrap(), "HELPM EOBIW ANKEN OBI");
+    }
+
+    #[test]
+    fn test_padding() {
+        let enciphered = four_square::encipher(b"EXAMPLE", b"KEYWORD", b"JOE").unwrap();
+
+        assert_eq!(four_square::decipher(b"EXAMPLE", b"KEYWORD", enciphered.as_bytes()).unwrap(), "IOEX");
+    }
+
+    #[test]
+    fn test_invalid_keys() {
+        assert!(FourSquare::from_key_str("KEY;second=WORD;pad=J").is_err());
+        assert!(FourSquare::from_key_str("KEY;merge=JIK").is_err());
+        assert!(four_square::decipher(b"KEY", b"WORD", b"ABC").is_err());
+    }
+
+    quickcheck! {
+        fn four_square_round_trip(first: Vec<u8>, second: Vec<u8>, plain_text: Vec<u8>) -> bool {
+            let enciphered = four_square::encipher(&first, &second, &plain_text).unwrap();
+            let deciphered = four_square::decipher(&first, &second, enciphered.as_bytes()).unwrap();
+
+            // Deciphering gives back the digraphs, J written as I and padded with X
+            let mut digraphs = common::sanitize_text(&plain_text).unwrap()
+                .into_iter()
+                .map(|letter| if char::from(letter) == 'J' { 'I' } else { char::from(letter) })
+                .collect::<Vec<char>>();
+
+            if !digraphs.len().is_multiple_of(2) {
+                digraphs.push('X');
+            }
+
+            deciphered == common::format_output(digraphs)
+        }
+    }
+}
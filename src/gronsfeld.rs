@@ -0,0 +1,127 @@
+//! # Implementation of the Gronsfeld cipher
+//!
+//! The Gronsfeld cipher is the Vigenere cipher with a key of digits: each digit says how far to
+//! shift the letter under it, so only the first ten rows of the tableau (A to J) are used.
+//!
+//! A numeric key was easier to remember and to hide, but it leaves every letter only ten
+//! possible substitutes, which makes the cipher weaker than the Vigenere.
+//!
+//! # Example:
+//!
+//! Key: 31415
+//!
+//! Plain text: ATTACK AT DAWN
+//!
+//! Cipher text: DUXBH NBXEF ZO
+
+use crate::cipher::{Cipher, KeyedCipher};
+use crate::common::{self, AsciiUppercaseByte};
+use crate::errors::Error;
+use crate::passthrough::KeyAdvance;
+use crate::vigenere_standard::{self, add_bytes, subtract_bytes};
+use std::convert::TryFrom;
+
+/// Encipher `plain_text` with the Gronsfeld cipher under the numeric key `key`
+pub fn encipher(key: &[u8], plain_text: &[u8]) -> Result<String, Error> {
+    Gronsfeld::with_key(parse_digits(key)?)?.encipher(plain_text)
+}
+
+/// Decipher `cipher_text` with the Gronsfeld cipher under the numeric key `key`
+pub fn decipher(key: &[u8], cipher_text: &[u8]) -> Result<String, Error> {
+    Gronsfeld::with_key(parse_digits(key)?)?.decipher(cipher_text)
+}
+
+/// The Gronsfeld cipher, for use through the [`Cipher`] trait
+///
+/// The key is a string of digits. Spaces between them are ignored.
+pub struct Gronsfeld {
+    // The digits as the letters A to J, which shift by the same amount
+    key: Vec<AsciiUppercaseByte>,
+}
+
+impl KeyedCipher for Gronsfeld {
+    type Key = Vec<AsciiUppercaseByte>;
+
+    fn parse_key(key: &str) -> Result<Self::Key, Error> {
+        parse_digits(key.as_bytes())
+    }
+
+    fn with_key(key: Self::Key) -> Result<Self, Error> {
+        Ok(Gronsfeld { key })
+    }
+}
+
+impl Gronsfeld {
+    /// The key, as the shift of each position
+    pub fn shifts(&self) -> Vec<u8> {
+        self.key.iter().map(|letter| letter.get_byte() - b'A').collect()
+    }
+}
+
+impl Cipher for Gronsfeld {
+    fn encipher(&self, plain_text: &[u8]) -> Result<String, Error> {
+        vigenere_standard::combine(&self.key, plain_text, add_bytes)
+    }
+
+    fn decipher(&self, cipher_text: &[u8]) -> Result<String, Error> {
+        vigenere_standard::combine(&self.key, cipher_text, subtract_bytes)
+    }
+
+    fn encipher_passthrough(&self, plain_text: &[u8], advance: KeyAdvance) -> Result<String, Error> {
+        Ok(vigenere_standard::passthrough(&common::to_symbols(&self.key), plain_text, advance, add_bytes))
+    }
+
+    fn decipher_passthrough(&self, cipher_text: &[u8], advance: KeyAdvance) -> Result<String, Error> {
+        Ok(vigenere_standard::passthrough(&common::to_symbols(&self.key), cipher_text, advance, subtract_bytes))
+    }
+}
+
+// Turns the digits of a key into the letters A to J
+fn parse_digits(key: &[u8]) -> Result<Vec<AsciiUppercaseByte>, Error> {
+    key.iter()
+        .filter(|c| !c.is_ascii_whitespace())
+        .map(|&c| match c {
+            b'0'..=b'9' => AsciiUppercaseByte::try_from(c - b'0' + b'A'),
+            _ => Err(Error::KeyError(format!("A Gronsfeld key is made of digits, not '{}'", char::from(c)))),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cipher::KeyedCipher;
+    use crate::common;
+    use crate::gronsfeld::{self, Gronsfeld};
+    use crate::vigenere_standard;
+    use quickcheck::quickcheck;
+
+    #[test]
+    fn test_gronsfeld() {
+        let enciphered = gronsfeld::encipher(b"31415", b"ATTACK AT DAWN").unwrap();
+
+        assert_eq!(enciphered, "DUXBH NBXEF ZO");
+
+        // The same as the Vigenere cipher under the letters D B E B F
+        assert_eq!(enciphered, vigenere_standard::encipher(b"DBEBF", b"ATTACK AT DAWN").unwrap());
+
+        assert_eq!(gronsfeld::decipher(b"3 1 4 1 5", enciphered.as_bytes()).unwrap(), "ATTAC KATDA WN");
+    }
+
+    #[test]
+    fn test_invalid_key() {
+        assert!(Gronsfeld::from_key_str("31415").is_ok());
+        assert!(Gronsfeld::from_key_str("PI").is_err());
+        assert_eq!(Gronsfeld::from_key_str("0925").unwrap().shifts(), vec![0, 9, 2, 5]);
+    }
+
+    quickcheck! {
+        fn gronsfeld_round_trip(key: Vec<u8>, plain_text: Vec<u8>) -> bool {
+            let key = key.into_iter().map(|digit| b'0' + digit % 10).collect::<Vec<u8>>();
+
+            let enciphered = gronsfeld::encipher(&key, &plain_text).unwrap();
+            let deciphered = gronsfeld::decipher(&key, enciphered.as_bytes()).unwrap();
+
+            deciphered == common::format_output(common::sanitize_text(&plain_text).unwrap())
+        }
+    }
+}
@@ -0,0 +1,451 @@
+//! # Implementation of the Hill cipher
+//!
+//! Lester Hill's cipher (1929) reads letters as numbers modulo 26 (A = 0, B = 1, ... Z = 25) and
+//! enciphers them _n_ at a time. Each block of plain text _P_, written as a column, is multiplied
+//! by an n×n key matrix _K_:
+//!
+//! `C = K · P (mod 26)`
+//!
+//! And deciphering multiplies by the inverse matrix:
+//!
+//! `P = K⁻¹ · C (mod 26)`
+//!
+//! Not every matrix has an inverse modulo 26: its determinant must have one, so it must be odd
+//! and not 13. As 26 = 2 · 13, both are worked out by Gauss-Jordan elimination modulo 2 and
+//! modulo 13, where every number but 0 has an inverse, and the results put back together with
+//! the Chinese remainder theorem.
+//!
+//! Where the Vigenère cipher (see [`vigenere_standard`](crate::vigenere_standard)) shifts every
+//! letter on its own, the Hill cipher mixes the letters of a block: changing one letter of the
+//! plain text changes the whole block of cipher text. It is still linear though, which is its
+//! undoing: a few blocks of known plain text give away the key (see
+//! [`crack::hill`](crate::crack::hill)).
+//!
+//! # Example:
+//!
+//! Key: GYBNQKURP, written row by row
+//!
+//! ```text
+//!  6 24  1
+//! 13 16 10
+//! 20 17 15
+//! ```
+//!
+//! Message to encipher: "ACT", the column 0 2 19
+//!
+//! ```text
+//!  6·0 + 24·2 +  1·19 =  67 = 15 (mod 26)
+//! 13·0 + 16·2 + 10·19 = 222 = 14 (mod 26)
+//! 20·0 + 17·2 + 15·19 = 319 =  7 (mod 26)
+//! ```
+//!
+//! Cipher text: POH
+//!
+//! The last block is filled up with a padding letter, X unless another one is chosen.
+
+use crate::cipher::{Cipher, KeyString, KeyedCipher};
+use crate::common::{self, AsciiUppercaseByte};
+use crate::errors::Error;
+use std::convert::TryFrom;
+use std::fmt;
+
+/// Enciphers `plain_text` with the key matrix whose entries, row by row, are the letters of `key`,
+/// padding the last block with X
+///
+/// Returns an error if the number of letters of the key is not a square, or if the key matrix
+/// has no inverse modulo 26.
+pub fn encipher(key: &[u8], plain_text: &[u8]) -> Result<String, Error> {
+    Hill::with_key((Matrix::new(common::sanitize_text(key)?)?, letter(b'X')))?.encipher(plain_text)
+}
+
+/// Reverses [`encipher`]
+pub fn decipher(key: &[u8], cipher_text: &[u8]) -> Result<String, Error> {
+    Hill::with_key((Matrix::new(common::sanitize_text(key)?)?, letter(b'X')))?.decipher(cipher_text)
+}
+
+/// The multiplicative inverse of `value` modulo 26, if it has one
+pub fn reciprocal(value: AsciiUppercaseByte) -> Option<AsciiUppercaseByte> {
+    (b'A'..=b'Z')
+        .map(letter)
+        .find(|&candidate| value * candidate == letter(b'B'))
+}
+
+/// A square matrix of numbers modulo 26, written as letters
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Matrix {
+    size: usize,
+    entries: Vec<AsciiUppercaseByte>,
+}
+
+impl Matrix {
+    /// Builds a matrix from its entries, row by row
+    ///
+    /// Returns an error if there are no entries, or if their number is not a square.
+    pub fn new(entries: Vec<AsciiUppercaseByte>) -> Result<Matrix, Error> {
+        let size = (1..=entries.len()).find(|size| size * size >= entries.len()).unwrap_or(0);
+
+        if size == 0 || size * size != entries.len() {
+            return Err(Error::KeyError(format!("A key matrix needs a square number of entries, not {}", entries.len())));
+        }
+
+        Ok(Matrix { size, entries })
+    }
+
+    /// Builds a matrix from its columns, which must all be as long as there are columns
+    pub fn from_columns(columns: &[&[AsciiUppercaseByte]]) -> Matrix {
+        let size = columns.len();
+
+        let entries = (0..size)
+            .flat_map(|row| columns.iter().map(move |column| column[row]))
+            .collect();
+
+        Matrix { size, entries }
+    }
+
+    /// The number of rows (and columns)
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// The entries, row by row
+    pub fn entries(&self) -> &[AsciiUppercaseByte] {
+        &self.entries
+    }
+
+    /// The entry at `row` and `column`, counted from 0
+    pub fn at(&self, row: usize, column: usize) -> AsciiUppercaseByte {
+        self.entries[row * self.size + column]
+    }
+
+    /// The determinant
+    pub fn determinant(&self) -> AsciiUppercaseByte {
+        let (modulo_2, _) = self.eliminate(2);
+        let (modulo_13, _) = self.eliminate(13);
+
+        letter(b'A' + combine(modulo_2, modulo_13) as u8)
+    }
+
+    /// The inverse modulo 26, if the determinant has an inverse
+    pub fn inverse(&self) -> Option<Matrix> {
+        let (_, modulo_2) = self.eliminate(2);
+        let (_, modulo_13) = self.eliminate(13);
+
+        let entries = modulo_2?.into_iter()
+            .zip(modulo_13?)
+            .map(|(a, b)| letter(b'A' + combine(a, b) as u8))
+            .collect();
+
+        Some(Matrix { size: self.size, entries })
+    }
+
+    /// The product of this matrix and `other`, of the same size
+    pub fn multiply(&self, other: &Matrix) -> Matrix {
+        let entries = (0..self.size)
+            .flat_map(|row| (0..self.size).map(move |column| (row, column)))
+            .map(|(row, column)| {
+                (0..self.size).fold(letter(b'A'), |sum, i| sum + self.at(row, i) * other.at(i, column))
+            })
+            .collect();
+
+        Matrix { size: self.size, entries }
+    }
+
+    /// Multiplies the column `block`, as long as the matrix is wide, by the matrix
+    pub fn apply(&self, block: &[AsciiUppercaseByte]) -> Vec<AsciiUppercaseByte> {
+        (0..self.size)
+            .map(|row| block.iter().enumerate().fold(letter(b'A'), |sum, (i, &value)| sum + self.at(row, i) * value))
+            .collect()
+    }
+
+    // Gauss-Jordan elimination modulo the prime `modulus`, next to the identity matrix, which
+    // becomes the inverse. Returns the determinant, and the inverse if there is one.
+    fn eliminate(&self, modulus: usize) -> (usize, Option<Vec<usize>>) {
+        let size = self.size;
+
+        let mut rows = (0..size)
+            .map(|row| {
+                (0..size).map(|column| (self.at(row, column).get_byte() - b'A') as usize % modulus)
+                    .chain((0..size).map(|column| if row == column { 1 } else { 0 }))
+                    .collect::<Vec<usize>>()
+            })
+            .collect::<Vec<Vec<usize>>>();
+
+        let mut determinant = 1;
+
+        for column in 0..size {
+            let pivot = match (column..size).find(|&row| rows[row][column] != 0) {
+                Some(pivot) => pivot,
+                None => return (0, None),
+            };
+
+            // Swapping two rows changes the sign of the determinant
+            if pivot != column {
+                rows.swap(pivot, column);
+                determinant = (modulus - determinant) % modulus;
+            }
+
+            let value = rows[column][column];
+            let reciprocal = (1..modulus).find(|x| value * x % modulus == 1).unwrap();
+
+            determinant = determinant * value % modulus;
+
+            for entry in rows[column].iter_mut() {
+                *entry = *entry * reciprocal % modulus;
+            }
+
+            let pivot_row = rows[column].clone();
+
+            for (row, entries) in rows.iter_mut().enumerate() {
+                let factor = entries[column];
+
+                if row != column && factor != 0 {
+                    for (entry, &pivot_entry) in entries.iter_mut().zip(&pivot_row) {
+                        *entry = (*entry + (modulus - factor) * pivot_entry) % modulus;
+                    }
+                }
+            }
+        }
+
+        (determinant, Some(rows.into_iter().flat_map(|row| row.into_iter().skip(size)).collect()))
+    }
+}
+
+// The number modulo 26 that is `modulo_2` modulo 2 and `modulo_13` modulo 13
+fn combine(modulo_2: usize, modulo_13: usize) -> usize {
+    // 13 is 1 modulo 2 and 0 modulo 13, 14 is 0 modulo 2 and 1 modulo 13
+    (13 * modulo_2 + 14 * modulo_13) % 26
+}
+
+impl fmt::Display for Matrix {
+    /// Writes the entries as numbers, a row on each line
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rows = self.entries
+            .chunks(self.size)
+            .map(|row| row.iter().map(|entry| format!("{:>2}", entry.get_byte() - b'A')).collect::<Vec<String>>().join(" "))
+            .collect::<Vec<String>>();
+
+        write!(f, "{}", rows.join("\n"))
+    }
+}
+
+/// The Hill cipher, for use through the [`Cipher`] trait
+///
+/// The key is the key matrix and the padding letter. As a string, it is the entries of the
+/// matrix row by row, either as letters (`GYBNQKURP`) or as numbers (`6 24 1 13 16 10 20 17 15`),
+/// with the setting `pad` (default `X`). Numbers may be negative, and are taken modulo 26.
+pub struct Hill {
+    key: Matrix,
+    inverse: Matrix,
+    pad: AsciiUppercaseByte,
+}
+
+impl KeyedCipher for Hill {
+    type Key = (Matrix, AsciiUppercaseByte);
+
+    fn parse_key(key: &str) -> Result<Self::Key, Error> {
+        let key = KeyString::parse(key)?;
+
+        let entries = if key.main.bytes().any(|c| c.is_ascii_digit()) {
+            key.main
+                .split(|c: char| c.is_whitespace() || c == ',')
+                .filter(|number| !number.is_empty())
+                .map(|number| {
+                    number.parse::<i64>()
+                        .map(|number| AsciiUppercaseByte::from_index(number.rem_euclid(26) as usize))
+                        .map_err(|_| Error::KeyError(format!("{} is not a valid entry of a key matrix", number)))
+                })
+                .collect::<Result<Vec<AsciiUppercaseByte>, Error>>()?
+        } else {
+            common::sanitize_text(key.main.as_bytes())?
+        };
+
+        let pad = match key.setting("pad").map(|pad| pad.as_bytes()) {
+            None => letter(b'X'),
+            Some(&[pad]) => AsciiUppercaseByte::try_from(pad.to_ascii_uppercase())?,
+            Some(_) => return Err(Error::KeyError("The pad setting should be one letter, e.g. X".to_string())),
+        };
+
+        Ok((Matrix::new(entries)?, pad))
+    }
+
+    fn with_key((key, pad): Self::Key) -> Result<Self, Error> {
+        let inverse = inverse(&key)?;
+
+        Ok(Hill { key, inverse, pad })
+    }
+}
+
+impl Hill {
+    /// The key matrix
+    pub fn key(&self) -> &Matrix {
+        &self.key
+    }
+}
+
+impl Cipher for Hill {
+    fn encipher(&self, plain_text: &[u8]) -> Result<String, Error> {
+        let mut plain_text = common::sanitize_text(plain_text)?;
+
+        while !plain_text.len().is_multiple_of(self.key.size()) {
+            plain_text.push(self.pad);
+        }
+
+        let cipher_text = plain_text.chunks(self.key.size())
+            .flat_map(|block| self.key.apply(block))
+            .collect();
+
+        Ok(common::format_output(cipher_text))
+    }
+
+    fn decipher(&self, cipher_text: &[u8]) -> Result<String, Error> {
+        let cipher_text = common::sanitize_text(cipher_text)?;
+
+        if !cipher_text.len().is_multiple_of(self.key.size()) {
+            return Err(Error::DecipheringError(format!(
+                "Hill cipher text must be made of whole blocks of {} letters",
+                self.key.size()
+            )));
+        }
+
+        let plain_text = cipher_text.chunks(self.key.size())
+            .flat_map(|block| self.inverse.apply(block))
+            .collect();
+
+        Ok(common::format_output(plain_text))
+    }
+}
+
+// The inverse of the key `matrix` modulo 26, or an error if it has none and cannot be used as a key
+fn inverse(matrix: &Matrix) -> Result<Matrix, Error> {
+    matrix.inverse().ok_or_else(|| Error::KeyError(format!(
+        "The determinant of the key matrix, {}, has no inverse modulo 26",
+        matrix.determinant().index()
+    )))
+}
+
+fn letter(byte: u8) -> AsciiUppercaseByte {
+    AsciiUppercaseByte::try_from(byte).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cipher::{Cipher, KeyedCipher};
+    use crate::common;
+    use crate::hill::{self, Hill, Matrix};
+    use quickcheck::quickcheck;
+
+    #[test]
+    fn test_hill() {
+        assert_eq!(hill::encipher(b"GYBNQKURP", b"ACT").unwrap(), "POH");
+        assert_eq!(hill::encipher(b"GYBNQKURP", b"CAT").unwrap(), "FIN");
+
+        assert_eq!(hill::decipher(b"GYBNQKURP", b"POH FIN").unwrap(), "ACTCA T");
+
+        assert_eq!(hill::encipher(b"HILL", b"short example").unwrap(), "APADJ TFTWL FJ");
+    }
+
+    #[test]
+    fn test_inverse() {
+        let key = Matrix::new(common::sanitize_text(b"GYBNQKURP").unwrap()).unwrap();
+
+        assert_eq!(key.determinant(), common::sanitize_text(b"Z").unwrap()[0]);
+
+        let inverse = key.inverse().unwrap();
+
+        assert_eq!(inverse.to_string(), " 8  5 10\n21  8 21\n21 12  8");
+        assert_eq!(key.multiply(&inverse).to_string(), " 1  0  0\n 0  1  0\n 0  0  1");
+    }
+
+    #[test]
+    fn test_large_key() {
+        // Rows that step by 10 letters are dependent
+        let key = Matrix::new(common::sanitize_text(b"
+            BCDEFGHIJK LMNOPQRSTU VWXYZABCDE FGHIJKLMNO PQRSTUVWXY
+            ZABCDEFGHI JKLMNOPQRS TUVWXYZABC DEFGHIJKLM NOPQRSTUVW").unwrap()).unwrap();
+
+        assert_eq!(key.determinant(), common::sanitize_text(b"A").unwrap()[0]);
+        assert!(key.inverse().is_none());
+
+        let key = Matrix::new(common::sanitize_text(b"
+            EBXLOVSQNQ EREQQAOYFT AYZEFEPTXD RBKVQQRPZY DRBHGIBYDQ
+            ORAYCOKTQT QGWIOQRZPQ HWQIRGOEND MOKCVHNCGV JZDYEWUVLE").unwrap()).unwrap();
+
+        assert_eq!(key.size(), 10);
+        assert_eq!(key.determinant(), common::sanitize_text(b"T").unwrap()[0]);
+
+        let inverse = key.inverse().unwrap();
+
+        assert_eq!(inverse.to_string().lines().next().unwrap(), "14 21 19 21 17  7 13  3 22 18");
+
+        let product = key.multiply(&inverse);
+
+        for row in 0..10 {
+            for column in 0..10 {
+                assert_eq!(product.at(row, column).get_byte() - b'A', if row == column { 1 } else { 0 });
+            }
+        }
+    }
+
+    #[test]
+    fn test_numeric_key() {
+        let hill = Hill::from_key_str("6 24 1, 13 16 10, 20 17 15").unwrap();
+
+        assert_eq!(hill.key(), Hill::from_key_str("GYBNQKURP").unwrap().key());
+        assert_eq!(hill.encipher(b"ACT").unwrap(), "POH");
+
+        // -20 is 6 modulo 26, and 50 is 24
+        assert_eq!(Hill::from_key_str("-20 50 1 13 16 10 20 17 15").unwrap().key(), hill.key());
+    }
+
+    #[test]
+    fn test_padding() {
+        assert_eq!(hill::decipher(b"HILL", hill::encipher(b"HILL", b"ACE").unwrap().as_bytes()).unwrap(), "ACEX");
+
+        let hill = Hill::from_key_str("GYBNQKURP;pad=Q").unwrap();
+
+        assert_eq!(hill.decipher(hill.encipher(b"ACTS").unwrap().as_bytes()).unwrap(), "ACTSQ Q");
+        assert!(hill.decipher(b"POHF").is_err());
+    }
+
+    #[test]
+    fn test_invalid_keys() {
+        // The determinant of 0 1 2 3 is 24, which is even
+        assert!(Hill::from_key_str("ABCD").is_err());
+        // The determinant of 13 0 0 1 is 13
+        assert!(Hill::from_key_str("13 0 0 1").is_err());
+        assert!(Hill::from_key_str("ABC").is_err());
+        assert!(Hill::from_key_str("").is_err());
+        assert!(Hill::from_key_str("HILL;pad=XY").is_err());
+        // Letters among the numbers, or a stray minus sign, are not entries
+        assert!(Hill::from_key_str("1 2 B 3 4").is_err());
+        assert!(Hill::from_key_str("1 2 3 - 4").is_err());
+        assert!(Hill::from_key_str("1 2 3x 4").is_err());
+    }
+
+    quickcheck! {
+        fn hill_round_trip(key: Vec<u8>, plain_text: Vec<u8>) -> bool {
+            let mut key = common::sanitize_text(&key).unwrap();
+            key.truncate(4);
+
+            let key = key.iter().map(|&letter| char::from(letter)).collect::<String>();
+
+            let hill = match Hill::from_key_str(&key) {
+                Ok(hill) => hill,
+                Err(_) => return true,
+            };
+
+            let deciphered = hill.decipher(hill.encipher(&plain_text).unwrap().as_bytes()).unwrap();
+
+            let mut expected = common::sanitize_text(&plain_text).unwrap()
+                .into_iter()
+                .map(char::from)
+                .collect::<Vec<char>>();
+
+            while !expected.len().is_multiple_of(hill.key().size()) {
+                expected.push('X');
+            }
+
+            deciphered == common::format_output(expected)
+        }
+    }
+}
@@ -0,0 +1,266 @@
+//! # What a language looks like, in numbers
+//!
+//! A [`LanguageModel`] knows how often every _n-gram_ (sequence of n letters, from single
+//! letters up to quadgrams) appears in a language. It is built by counting the n-grams of a
+//! large sample of text (a _corpus_), and is used to score how much a candidate decryption
+//! looks like that language.
+//!
+//! Models are stored as text, one n-gram per line followed by the number of times it was seen.
+//! Blank lines and lines starting with `#` are ignored:
+//!
+//! ```text
+//! # codebreakers language model
+//! E 519706
+//! TH 122233
+//! THE 79405
+//! TION 20148
+//! ```
+//!
+//! An English model trained from about four million letters is embedded in the crate
+//! (see [`LanguageModel::english`]). Models for other languages can be trained with
+//! `codebreakers train-model corpus.txt -o en.model`.
+
+use crate::common::{self, AsciiUppercaseByte};
+use crate::errors::Error;
+
+/// The longest n-grams a model knows about
+pub const MAX_ORDER: usize = 4;
+
+// The n-grams of one length, indexed as base 26 numbers
+#[derive(Debug, Clone)]
+struct NgramTable {
+    counts: Vec<u64>,
+    log_probabilities: Vec<f64>,
+    floor: f64,
+}
+
+impl NgramTable {
+    fn new(order: usize) -> NgramTable {
+        NgramTable {
+            counts: vec![0; 26usize.pow(order as u32)],
+            log_probabilities: Vec::new(),
+            floor: 0.0,
+        }
+    }
+
+    // Turns counts into log10 probabilities. N-grams that were never seen get a probability a
+    // hundred times smaller than a single occurrence, so one unusual n-gram does not sink a text.
+    fn compute_log_probabilities(&mut self) {
+        let total = self.counts.iter().sum::<u64>().max(1) as f64;
+        let floor = (0.01 / total).log10();
+
+        self.log_probabilities = self.counts.iter()
+            .map(|&count| if count == 0 { floor } else { (count as f64 / total).log10() })
+            .collect();
+        self.floor = floor;
+    }
+}
+
+/// Monogram through quadgram statistics of a language
+#[derive(Debug, Clone)]
+pub struct LanguageModel {
+    // The table of n-grams of length n is at index n - 1
+    tables: Vec<NgramTable>,
+}
+
+impl LanguageModel {
+    /// The embedded English model
+    pub fn english() -> LanguageModel {
+        LanguageModel::parse(include_str!("data/english.model"))
+            .expect("the embedded English model is valid")
+    }
+
+    /// Trains a model by counting every n-gram of `corpus`
+    ///
+    /// Anything that is not a letter A-Z is ignored, so n-grams run across word boundaries.
+    pub fn train(corpus: &[u8]) -> Result<LanguageModel, Error> {
+        let text = common::sanitize_text(corpus)?;
+
+        let mut tables = (1..=MAX_ORDER).map(NgramTable::new).collect::<Vec<NgramTable>>();
+
+        for (n, table) in (1..=MAX_ORDER).zip(tables.iter_mut()) {
+            for ngram in text.windows(n) {
+                table.counts[ngram_index(ngram)] += 1;
+            }
+        }
+
+        Ok(LanguageModel::from_tables(tables))
+    }
+
+    /// Reads a model written in the text format described in the [module documentation](self)
+    pub fn parse(model: &str) -> Result<LanguageModel, Error> {
+        let mut tables = (1..=MAX_ORDER).map(NgramTable::new).collect::<Vec<NgramTable>>();
+
+        for line in model.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut fields = line.split_whitespace();
+
+            let (ngram, count) = match (fields.next(), fields.next(), fields.next()) {
+                (Some(ngram), Some(count), None) => (ngram, count),
+                _ => return Err(Error::ModelError(format!("Line '{}' should look like 'NGRAM COUNT'", line))),
+            };
+
+            let letters = common::sanitize_text(ngram.as_bytes())?;
+
+            if letters.len() != ngram.len() || letters.is_empty() || letters.len() > MAX_ORDER {
+                return Err(Error::ModelError(format!("'{}' is not an n-gram of 1 to {} letters A-Z", ngram, MAX_ORDER)));
+            }
+
+            let count = count.parse::<u64>()
+                .map_err(|_| Error::ModelError(format!("Invalid count '{}' for n-gram {}", count, ngram)))?;
+
+            tables[letters.len() - 1].counts[ngram_index(&letters)] += count;
+        }
+
+        Ok(LanguageModel::from_tables(tables))
+    }
+
+    /// Writes the model in the text format read by [`LanguageModel::parse`]
+    ///
+    /// N-grams are written from shortest to longest, most common first. Only the `max_ngrams`
+    /// most common n-grams of each length are kept, if given.
+    pub fn to_text(&self, max_ngrams: Option<usize>) -> String {
+        let mut text = String::from("# codebreakers language model\n");
+
+        for (n, table) in (1..=MAX_ORDER).zip(&self.tables) {
+            let mut ngrams = table.counts.iter()
+                                .enumerate()
+                                .filter(|&(_, &count)| count > 0)
+                                .collect::<Vec<(usize, &u64)>>();
+
+            ngrams.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(&b.0)));
+
+            for (index, count) in ngrams.into_iter().take(max_ngrams.unwrap_or(usize::MAX)) {
+                text.push_str(&format!("{} {}\n", ngram_letters(index, n), count));
+            }
+        }
+
+        text
+    }
+
+    /// The log10 probability of `ngram`, an n-gram of 1 to [`MAX_ORDER`] letters
+    ///
+    /// # Panics
+    ///
+    /// Panics if `ngram` is empty or longer than [`MAX_ORDER`]
+    pub fn log_probability(&self, ngram: &[AsciiUppercaseByte]) -> f64 {
+        self.tables[ngram.len() - 1].log_probabilities[ngram_index(ngram)]
+    }
+
+    /// The log10 probability of an n-gram of length `n` that was never seen, lower than that of
+    /// any n-gram that was
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is 0 or larger than [`MAX_ORDER`]
+    pub fn floor(&self, n: usize) -> f64 {
+        self.tables[n - 1].floor
+    }
+
+    /// Scores `text` with n-grams of length `n`: the sum of the log probabilities of all its n-grams
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is 0 or larger than [`MAX_ORDER`]
+    pub fn ngram_fitness(&self, n: usize, text: &[AsciiUppercaseByte]) -> f64 {
+        let table = &self.tables[n - 1];
+
+        text.windows(n).map(|ngram| table.log_probabilities[ngram_index(ngram)]).sum()
+    }
+
+    /// Scores how much `text` looks like the language of the model. Higher is better.
+    ///
+    /// This is the quadgram fitness, which is the most discriminating. Scores only compare
+    /// texts of the same length.
+    pub fn fitness(&self, text: &[AsciiUppercaseByte]) -> f64 {
+        self.ngram_fitness(MAX_ORDER, text)
+    }
+
+    fn from_tables(mut tables: Vec<NgramTable>) -> LanguageModel {
+        for table in tables.iter_mut() {
+            table.compute_log_probabilities();
+        }
+
+        LanguageModel { tables }
+    }
+}
+
+// Position of an n-gram in its table: its letters read as a base 26 number
+fn ngram_index(ngram: &[AsciiUppercaseByte]) -> usize {
+    ngram.iter().fold(0, |index, letter| index * 26 + (letter.get_byte() - b'A') as usize)
+}
+
+// The n-gram of length `n` at position `index` of its table
+fn ngram_letters(mut index: usize, n: usize) -> String {
+    let mut letters = vec![b'A'; n];
+
+    for letter in letters.iter_mut().rev() {
+        *letter = b'A' + (index % 26) as u8;
+        index /= 26;
+    }
+
+    String::from_utf8(letters).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::common;
+    use crate::language_model::LanguageModel;
+
+    #[test]
+    fn test_fitness() {
+        let english = LanguageModel::english();
+
+        let text = common::sanitize_text(b"It was the best of times, it was the worst of times").unwrap();
+        let gibberish = common::sanitize_text(b"Qzxj vkqw zzxq jjkv qxzw vvqj xkzq jvkq zxwq vjk").unwrap();
+
+        assert_eq!(text.len(), gibberish.len());
+
+        for n in 1..=4 {
+            assert!(english.ngram_fitness(n, &text) > english.ngram_fitness(n, &gibberish));
+        }
+    }
+
+    #[test]
+    fn test_train() {
+        let model = LanguageModel::train(b"Abab, abb!").unwrap();
+
+        let ab = common::sanitize_text(b"AB").unwrap();
+        let ba = common::sanitize_text(b"BA").unwrap();
+
+        // AB appears 3 times out of 6 digrams, BA twice
+        assert!((model.log_probability(&ab) - (3.0f64 / 6.0).log10()).abs() < 1e-9);
+        assert!((model.log_probability(&ba) - (2.0f64 / 6.0).log10()).abs() < 1e-9);
+
+        // AA was never seen, and gets a hundredth of a single occurrence
+        let aa = common::sanitize_text(b"AA").unwrap();
+
+        assert!((model.floor(2) - (0.01f64 / 6.0).log10()).abs() < 1e-9);
+        assert_eq!(model.log_probability(&aa), model.floor(2));
+
+        assert_eq!(
+            model.to_text(Some(1)),
+            "# codebreakers language model\nB 4\nAB 3\nABA 2\nABAB 2\n"
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let model = LanguageModel::train(b"The quick brown fox jumps over the lazy dog").unwrap();
+
+        let text = model.to_text(None);
+
+        assert_eq!(LanguageModel::parse(&text).unwrap().to_text(None), text);
+    }
+
+    #[test]
+    fn test_invalid_models() {
+        assert!(LanguageModel::parse("TION").is_err());
+        assert!(LanguageModel::parse("TION many").is_err());
+        assert!(LanguageModel::parse("QUICK 1").is_err());
+        assert!(LanguageModel::parse("T-ON 1").is_err());
+    }
+}
@@ -1,7 +1,33 @@
-mod common;
 mod errors;
 
+pub use errors::Error;
+
+pub mod alphabet;
+pub mod common;
 pub mod vigenere_standard;
 pub mod vigenere_autokey;
+pub mod running_key;
+pub mod beaufort;
+pub mod gronsfeld;
+pub mod porta;
+pub mod tableau;
+pub mod quagmire;
+pub mod hill;
+pub mod enigma;
 pub mod column_transposition;
-pub mod analysis;
\ No newline at end of file
+pub mod rail_fence;
+pub mod route;
+pub mod polybius;
+pub mod playfair;
+pub mod two_square;
+pub mod four_square;
+pub mod bifid;
+pub mod trifid;
+pub mod adfgvx;
+pub mod substitution;
+pub mod analysis;
+pub mod language_model;
+pub mod passthrough;
+pub mod cipher;
+pub mod chain;
+pub mod crack;
\ No newline at end of file
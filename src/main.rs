@@ -1,8 +1,12 @@
-use clap::{App, SubCommand, Arg, ArgMatches};
-use codebreakers::vigenere_standard;
-use codebreakers::vigenere_autokey;
-use codebreakers::column_transposition;
+use clap::{App, SubCommand, Arg, ArgMatches, ErrorKind};
 use codebreakers::analysis;
+use codebreakers::chain::Chain;
+use codebreakers::cipher::{self, Cipher};
+use codebreakers::common::{self, LetterCase, OutputFormat};
+use codebreakers::crack;
+use codebreakers::language_model::LanguageModel;
+use codebreakers::passthrough::KeyAdvance;
+use std::fs;
 use std::io;
 use std::io::Read;
 
@@ -11,27 +15,37 @@ fn main() {
     let app = App::new("codebreakers")
                     .about("Implementation of historical ciphers from David Khan's Codebreakers book")
                     .version("0.0.1")
-                    .subcommand(create_vigenere_command())
-                    .subcommand(create_column_transposition_command())
-                    .subcommand(create_analyze_command());
+                    .subcommands(cipher::REGISTRY.iter().map(create_cipher_command))
+                    .subcommand(create_chain_command())
+                    .subcommand(create_analyze_command())
+                    .subcommand(create_crack_command())
+                    .subcommand(create_train_model_command());
 
     let matches = app.get_matches();
 
     match matches.subcommand() {
-        ("vigenere", Some(vigenere_cmd)) => handle_vigenere_command(vigenere_cmd),
-        ("column-transposition", Some(col_transpose_cmd)) => handle_column_transposition_command(col_transpose_cmd),
+        ("chain", Some(chain_cmd)) => handle_chain_command(chain_cmd),
         ("analyze", Some(analyze_cmd)) => handle_analyze_command(analyze_cmd),
+        ("crack", Some(crack_cmd)) => handle_crack_command(crack_cmd),
+        ("train-model", Some(train_cmd)) => handle_train_model_command(train_cmd),
+        (name, Some(cipher_cmd)) => {
+            if let Some(entry) = cipher::lookup(name) {
+                handle_cipher_command(entry, cipher_cmd)
+            }
+        },
         _ => {}
     }
 }
 
 
-fn create_vigenere_command<'a, 'b>() -> App<'a, 'b> {
+fn create_cipher_command<'a, 'b>(entry: &'static cipher::Entry) -> App<'a, 'b> {
+    let variants = entry.variants.iter().map(|v| v.name).collect::<Vec<&str>>();
+
     let variant_arg = Arg::with_name("variant")
                             .long("variant")
                             .takes_value(true)
-                            .required(true)
-                            .possible_values(&["standard", "autokey"]);
+                            .possible_values(&variants)
+                            .default_value(variants[0]);
 
     let decipher_flag = Arg::with_name("decipher")
                             .long("decipher")
@@ -40,79 +54,199 @@ fn create_vigenere_command<'a, 'b>() -> App<'a, 'b> {
     let key = Arg::with_name("key")
                     .long("key")
                     .takes_value(true)
-                    .required(true);
+                    .required(entry.settings.is_empty());
+
+    let settings = entry.settings.iter().map(|setting| {
+        Arg::with_name(setting.name)
+            .long(setting.name)
+            .takes_value(true)
+            .help(setting.help)
+    })
+    .collect::<Vec<Arg>>();
+
+    let key_table = entry.key_table.iter().map(|table| {
+        Arg::with_name(table.flag)
+            .long(table.flag)
+            .takes_value(false)
+            .help(table.help)
+    })
+    .collect::<Vec<Arg>>();
 
-    SubCommand::with_name("vigenere")
-                .about("Standard & Autokey vigenere cipher")
-                .args(&[variant_arg, decipher_flag, key])
+    let passthrough_flag = Arg::with_name("passthrough")
+                            .long("passthrough")
+                            .takes_value(false)
+                            .conflicts_with_all(&["group", "line-width", "raw", "case-convention"])
+                            .help("Only encipher letters, keeping spaces, punctuation and case in place");
 
+    let key_advance_arg = Arg::with_name("key-advance")
+                            .long("key-advance")
+                            .takes_value(true)
+                            .requires("passthrough")
+                            .possible_values(&["letters", "all"])
+                            .help("Whether the key moves on after non-letters in passthrough mode (default: letters). The autokey variants only move on after letters");
+
+    SubCommand::with_name(entry.name)
+                .about(entry.about)
+                .args(&[variant_arg, decipher_flag, key, passthrough_flag, key_advance_arg])
+                .args(&settings)
+                .args(&key_table)
+                .args(&create_format_args())
 }
 
-fn handle_vigenere_command(arg: &ArgMatches) {
+fn handle_cipher_command(entry: &cipher::Entry, arg: &ArgMatches) {
     // Unless the user passes in the `--decipher` flag, we'll be enciphering
     let encipher = !arg.is_present("decipher");
 
     let mut input = Vec::new();
     io::stdin().read_to_end(&mut input).unwrap();
 
-    let key = arg.value_of("key").unwrap();
-    
-    let output = match arg.value_of("variant") {
-        Some("standard") => { 
-            if encipher {
-                vigenere_standard::encipher(key.as_bytes(), &input).unwrap()
-            } else {
-                vigenere_standard::decipher(key.as_bytes(), &input).unwrap()
-            }
-        },
-        Some("autokey") => {
-            if encipher {
-                vigenere_autokey::encipher(key.as_bytes(), &input).unwrap()
-            } else {
-                vigenere_autokey::decipher(key.as_bytes(), &input).unwrap()
-            }
+    // Settings given as flags are added to the key, as `KEY;name=value`
+    let mut key = arg.value_of("key").unwrap_or("").to_string();
 
-        },
-        _ => String::new()
+    for setting in entry.settings {
+        if let Some(value) = arg.value_of(setting.name) {
+            key.push_str(&format!(";{}={}", setting.name, value));
+        }
+    }
+
+    let cipher = (entry.variant(arg.value_of("variant")).unwrap().build)(&key).unwrap();
+
+    if let Some(table) = entry.key_table.as_ref().filter(|table| arg.is_present(table.flag)) {
+        println!("{}", (table.show)(&key).unwrap());
+    }
+
+    if arg.is_present("passthrough") {
+        let advance = match arg.value_of("key-advance") {
+            Some("all") => KeyAdvance::EveryCharacter,
+            _ => KeyAdvance::LettersOnly,
+        };
+
+        let output = if encipher {
+            cipher.encipher_passthrough(&input, advance).unwrap()
+        } else {
+            cipher.decipher_passthrough(&input, advance).unwrap()
+        };
+
+        // The input's own line breaks were kept
+        if arg.is_present("number-lines") {
+            print!("{}", common::number_lines(&output));
+        } else {
+            print!("{}", output);
+        }
+
+        return;
+    }
+
+    let output = if encipher {
+        cipher.encipher(&input).unwrap()
+    } else {
+        cipher.decipher(&input).unwrap()
     };
 
-    println!("{}", output);
+    println!("{}", common::reformat(&output, &output_format(arg, !encipher)));
 }
 
-fn create_column_transposition_command<'a, 'b>() -> App<'a, 'b> {
+fn create_chain_command<'a, 'b>() -> App<'a, 'b> {
+    let stages = Arg::with_name("stages")
+                        .help("The ciphers to apply, in enciphering order, e.g. \"vigenere:KEY | column-transposition:ZEBRAS\"")
+                        .required(true);
+
     let decipher_flag = Arg::with_name("decipher")
                             .long("decipher")
-                            .takes_value(false);
-
-    let key = Arg::with_name("key")
-                    .long("key")
-                    .takes_value(true)
-                    .required(true);
-
-    SubCommand::with_name("column-transposition")
-                .about("Column transposition cipher")
-                .args(&[decipher_flag, key])
+                            .takes_value(false)
+                            .help("Decipher, running the stages in reverse order");
 
+    SubCommand::with_name("chain")
+                .about("Apply several ciphers one after the other")
+                .args(&[stages, decipher_flag])
+                .args(&create_format_args())
 }
 
-fn handle_column_transposition_command(arg: &ArgMatches) {
+fn handle_chain_command(arg: &ArgMatches) {
     let encipher = !arg.is_present("decipher");
 
     let mut input = Vec::new();
     io::stdin().read_to_end(&mut input).unwrap();
 
-    let key = arg.value_of("key").unwrap();
-    
-    let output = match encipher {
-        true => { 
-            column_transposition::encipher(key.as_bytes(), &input).unwrap()
-        },
-        false => {
-            column_transposition::decipher(key.as_bytes(), &input).unwrap()
-        },
+    let chain = Chain::parse(arg.value_of("stages").unwrap()).unwrap();
+
+    let output = if encipher {
+        chain.encipher(&input).unwrap()
+    } else {
+        chain.decipher(&input).unwrap()
     };
 
-    println!("{}", output);
+    println!("{}", common::reformat(&output, &output_format(arg, !encipher)));
+}
+
+fn create_format_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    let group = Arg::with_name("group")
+                    .long("group")
+                    .takes_value(true)
+                    .help("Number of letters in a group, 0 for no groups (default: 5)");
+
+    let line_width = Arg::with_name("line-width")
+                        .long("line-width")
+                        .takes_value(true)
+                        .help("Number of groups (letters, with --group 0) on a line, 0 for a single line (default: 5, or a single line with --group 0)");
+
+    let raw = Arg::with_name("raw")
+                .long("raw")
+                .takes_value(false)
+                .conflicts_with_all(&["group", "line-width"])
+                .help("Write the letters without groups or line breaks");
+
+    let number_lines = Arg::with_name("number-lines")
+                        .long("number-lines")
+                        .takes_value(false)
+                        .help("Start every line with its line number");
+
+    let case_convention = Arg::with_name("case-convention")
+                            .long("case-convention")
+                            .takes_value(false)
+                            .help("Write plain text in lowercase and cipher text in uppercase");
+
+    vec![group, line_width, raw, number_lines, case_convention]
+}
+
+fn output_format(arg: &ArgMatches, is_plain_text: bool) -> OutputFormat {
+    let mut format = if arg.is_present("raw") { OutputFormat::raw() } else { OutputFormat::default() };
+
+    if let Some(group) = arg.value_of("group") {
+        format.group_size = match group.parse::<usize>().unwrap() {
+            0 => None,
+            size => Some(size),
+        };
+    }
+
+    if let Some(line_width) = arg.value_of("line-width") {
+        format.groups_per_line = match line_width.parse::<usize>().unwrap() {
+            0 => None,
+            groups => Some(groups),
+        };
+    } else if format.group_size.is_none() {
+        // Lines of 5 letters would look like groups
+        format.groups_per_line = None;
+    }
+
+    format.number_lines = arg.is_present("number-lines");
+
+    if arg.is_present("case-convention") && is_plain_text {
+        format.case = LetterCase::Lower;
+    }
+
+    format
+}
+
+// Every subcommand takes the output format arguments, but some never write a run of letters to lay
+// out. Those stop with an error rather than ignore them.
+fn refuse_output_format(arg: &ArgMatches, output: &str) {
+    let given = ["group", "line-width", "raw", "number-lines", "case-convention"].iter()
+                    .find(|&&name| arg.is_present(name));
+
+    if let Some(name) = given {
+        clap::Error::with_description(&format!("--{} has nothing to lay out: {}", name, output), ErrorKind::ArgumentConflict).exit();
+    }
 }
 
 fn create_analyze_command<'a, 'b>() -> App<'a, 'b> {
@@ -120,14 +254,37 @@ fn create_analyze_command<'a, 'b>() -> App<'a, 'b> {
                             .long("variant")
                             .takes_value(true)
                             .required(true)
-                            .possible_values(&["single-letter-frequency", "digram-frequency"]);
+                            .possible_values(&["single-letter-frequency", "digram-frequency", "stats", "bifid-period", "drag-crib"]);
+
+    let max_period_arg = Arg::with_name("max-period")
+                            .long("max-period")
+                            .takes_value(true)
+                            .default_value("20")
+                            .help("Largest period tried by bifid-period");
+
+    let crib_arg = Arg::with_name("crib")
+                            .long("crib")
+                            .takes_value(true)
+                            .required_if("variant", "drag-crib")
+                            .help("Probable word dragged through the cipher text by drag-crib");
+
+    let model_arg = Arg::with_name("model")
+                            .long("model")
+                            .takes_value(true)
+                            .help("Language model that drag-crib scores the key fragments with, made with train-model (default: English)");
 
     SubCommand::with_name("analyze")
                 .about("Poor man's cryptanalysis")
-                .args(&[variant_arg])
+                .args(&[variant_arg, max_period_arg, crib_arg, model_arg])
+                .args(&create_format_args())
 }
 
 fn handle_analyze_command(arg: &ArgMatches) {
+    // Only the key fragments of drag-crib are runs of letters
+    if arg.value_of("variant") != Some("drag-crib") {
+        refuse_output_format(arg, "this variant prints a table");
+    }
+
     let mut input = Vec::new();
     io::stdin().read_to_end(&mut input).unwrap();
     
@@ -138,7 +295,289 @@ fn handle_analyze_command(arg: &ArgMatches) {
         Some("digram-frequency") => {
             analysis::print_digram_frequencies(&analysis::digram(&input).unwrap())
         },
+        Some("stats") => {
+            analysis::print_statistics(&analysis::statistics(&input).unwrap())
+        },
+        Some("bifid-period") => {
+            let max_period = arg.value_of("max-period").unwrap().parse::<usize>().unwrap();
+
+            analysis::print_bifid_periods(&analysis::bifid_periods(&input, 2..=max_period).unwrap())
+        },
+        Some("drag-crib") => {
+            let placements = crack::autokey::drag_crib(&input, arg.value_of("crib").unwrap().as_bytes(), &language_model(arg)).unwrap();
+
+            let format = output_format(arg, true);
+
+            for placement in placements.iter().take(10) {
+                println!("{:>6}  {}  ({:.4})", placement.position, common::reformat(&placement.key, &format), placement.score);
+            }
+        },
         Some(_) => unimplemented!(),
         None => unreachable!()
     };
-}
\ No newline at end of file
+}
+
+fn create_crack_command<'a, 'b>() -> App<'a, 'b> {
+    let cipher_arg = Arg::with_name("cipher")
+                            .long("cipher")
+                            .takes_value(true)
+                            .required(true)
+                            .possible_values(&["vigenere", "substitution", "column-transposition", "rail-fence", "route", "playfair", "adfgvx", "hill", "enigma", "quagmire", "running-key", "autokey", "ciphertext-autokey"]);
+
+    let max_period = Arg::with_name("max-period")
+                            .long("max-period")
+                            .help("Largest period, or length of the priming key (autokey, ciphertext-autokey), to try")
+                            .takes_value(true)
+                            .default_value("20");
+
+    let min_width = Arg::with_name("min-width")
+                            .long("min-width")
+                            .help("Smallest number of columns to try (column-transposition, route, adfgvx)")
+                            .takes_value(true)
+                            .default_value("2");
+
+    let max_width = Arg::with_name("max-width")
+                            .long("max-width")
+                            .help("Largest number of columns to try, at most 26 (column-transposition, route, adfgvx)")
+                            .takes_value(true)
+                            .default_value("12");
+
+    let max_rails = Arg::with_name("max-rails")
+                            .long("max-rails")
+                            .help("Largest number of rails to try (rail-fence)")
+                            .takes_value(true)
+                            .default_value("10");
+
+    let crib = Arg::with_name("crib")
+                            .long("crib")
+                            .help("A word or phrase of the plain text, at least n² + n - 1 letters long for an n×n key (hill), at least as long as the priming key (autokey, optional), or the longer the better (enigma)")
+                            .takes_value(true)
+                            .required_ifs(&[("cipher", "hill"), ("cipher", "enigma")]);
+
+    let crib_position = Arg::with_name("crib-position")
+                            .long("crib-position")
+                            .help("Where the crib starts in the cipher text, counting letters from 0 (enigma, default: try every place)")
+                            .takes_value(true);
+
+    let rotors = Arg::with_name("rotors")
+                            .long("rotors")
+                            .help("Rotors to choose from: every order of three of them is tried (enigma)")
+                            .takes_value(true)
+                            .default_value("I II III IV V");
+
+    let reflectors = Arg::with_name("reflectors")
+                            .long("reflectors")
+                            .help("Reflectors to try (enigma)")
+                            .takes_value(true)
+                            .default_value("B");
+
+    let side = Arg::with_name("side")
+                            .long("side")
+                            .help("Side of the square: 6 for ADFGVX, 5 for ADFGX (adfgvx)")
+                            .takes_value(true)
+                            .possible_values(&["5", "6"])
+                            .default_value("6");
+
+    let max_size = Arg::with_name("max-size")
+                            .long("max-size")
+                            .help("Largest key matrix to try (hill)")
+                            .takes_value(true)
+                            .default_value("4");
+
+    let keyword = Arg::with_name("keyword")
+                            .long("keyword")
+                            .help("Keyword of the plain alphabet, if known, or \"\" if straight (quagmire; this or --cipher-keyword is needed)")
+                            .takes_value(true);
+
+    let cipher_keyword = Arg::with_name("cipher-keyword")
+                            .long("cipher-keyword")
+                            .help("Keyword of the cipher alphabet, if known, or \"\" if straight (quagmire)")
+                            .takes_value(true);
+
+    let beam_width = Arg::with_name("beam-width")
+                            .long("beam-width")
+                            .help("Number of partial solutions kept at each letter (running-key)")
+                            .takes_value(true)
+                            .default_value("500");
+
+    let candidates = Arg::with_name("candidates")
+                            .long("candidates")
+                            .takes_value(true)
+                            .default_value("3");
+
+    let seed = Arg::with_name("seed")
+                            .long("seed")
+                            .help("Seed of the random search. The same seed gives the same result.")
+                            .takes_value(true)
+                            .default_value("0");
+
+    let restarts = Arg::with_name("restarts")
+                            .long("restarts")
+                            .help("Number of times a random search starts over")
+                            .takes_value(true)
+                            .default_value("20");
+
+    let iterations = Arg::with_name("iterations")
+                            .long("iterations")
+                            .help("Number of key changes tried after each restart of a random search (default: 5000, 20000 for adfgvx, 150000 for playfair)")
+                            .takes_value(true);
+
+    let model = Arg::with_name("model")
+                            .long("model")
+                            .help("Language model of the plain text, made with train-model (default: English)")
+                            .takes_value(true);
+
+    SubCommand::with_name("crack")
+                .about("Recover the key and plain text of a cryptogram")
+                .args(&[cipher_arg, max_period, min_width, max_width, max_rails, crib, crib_position, side, max_size, rotors, reflectors, keyword, cipher_keyword, beam_width, candidates, seed, restarts, iterations, model])
+                .args(&create_format_args())
+}
+
+fn handle_crack_command(arg: &ArgMatches) {
+    let mut input = Vec::new();
+    io::stdin().read_to_end(&mut input).unwrap();
+
+    let count = arg.value_of("candidates").unwrap().parse::<usize>().unwrap();
+
+    let candidates = match arg.value_of("cipher") {
+        Some("vigenere") => {
+            let max_period = arg.value_of("max-period").unwrap().parse::<usize>().unwrap();
+            crack::vigenere::crack(&input, max_period).unwrap()
+        },
+        Some("substitution") => {
+            crack::substitution::crack(&input, &language_model(arg), &search_settings(arg)).unwrap()
+        },
+        Some("column-transposition") => {
+            let min_width = arg.value_of("min-width").unwrap().parse::<usize>().unwrap();
+            let max_width = arg.value_of("max-width").unwrap().parse::<usize>().unwrap();
+            crack::column_transposition::crack(&input, min_width..=max_width, &language_model(arg), &search_settings(arg)).unwrap()
+        },
+        Some("rail-fence") => {
+            let max_rails = arg.value_of("max-rails").unwrap().parse::<usize>().unwrap();
+            crack::rail_fence::crack(&input, 2..=max_rails, &language_model(arg)).unwrap()
+        },
+        Some("route") => {
+            let min_width = arg.value_of("min-width").unwrap().parse::<usize>().unwrap();
+            let max_width = arg.value_of("max-width").unwrap().parse::<usize>().unwrap();
+            crack::route::crack(&input, min_width..=max_width, &language_model(arg)).unwrap()
+        },
+        Some("playfair") => {
+            let mut settings = search_settings(arg);
+
+            if !arg.is_present("iterations") {
+                settings.iterations = crack::playfair::ITERATIONS;
+            }
+
+            crack::playfair::crack(&input, &language_model(arg), &settings).unwrap()
+        },
+        Some("adfgvx") => {
+            let min_width = arg.value_of("min-width").unwrap().parse::<usize>().unwrap();
+            let max_width = arg.value_of("max-width").unwrap().parse::<usize>().unwrap();
+            let side = arg.value_of("side").unwrap().parse::<usize>().unwrap();
+            let mut settings = search_settings(arg);
+
+            if !arg.is_present("iterations") {
+                settings.iterations = crack::adfgvx::ITERATIONS;
+            }
+
+            crack::adfgvx::crack(&input, side, min_width..=max_width, &language_model(arg), &settings).unwrap()
+        },
+        Some("hill") => {
+            let max_size = arg.value_of("max-size").unwrap().parse::<usize>().unwrap();
+            crack::hill::crack(&input, arg.value_of("crib").unwrap().as_bytes(), 1..=max_size, &language_model(arg)).unwrap()
+        },
+        Some("enigma") => {
+            let settings = crack::bombe::BombeSettings {
+                rotors: arg.value_of("rotors").unwrap().split_whitespace().map(String::from).collect(),
+                reflectors: arg.value_of("reflectors").unwrap().split_whitespace().map(String::from).collect(),
+                crib_position: arg.value_of("crib-position").map(|position| position.parse::<usize>().unwrap()),
+                ..crack::bombe::BombeSettings::default()
+            };
+
+            crack::bombe::crack(&input, arg.value_of("crib").unwrap().as_bytes(), &settings, &language_model(arg)).unwrap()
+        },
+        Some("quagmire") => {
+            let max_period = arg.value_of("max-period").unwrap().parse::<usize>().unwrap();
+            let keyword = arg.value_of("keyword").map(str::as_bytes);
+            let cipher_keyword = arg.value_of("cipher-keyword").map(str::as_bytes);
+
+            crack::quagmire::crack(&input, max_period, keyword, cipher_keyword, &language_model(arg), &search_settings(arg)).unwrap()
+        },
+        Some("running-key") => {
+            let beam_width = arg.value_of("beam-width").unwrap().parse::<usize>().unwrap();
+
+            crack::running_key::crack(&input, &language_model(arg), beam_width).unwrap()
+        },
+        Some("autokey") => {
+            let max_length = arg.value_of("max-period").unwrap().parse::<usize>().unwrap();
+
+            match arg.value_of("crib") {
+                Some(crib) => crack::autokey::crack_with_crib(&input, crib.as_bytes(), max_length, &language_model(arg)).unwrap(),
+                None => crack::autokey::crack(&input, max_length, &language_model(arg)).unwrap(),
+            }
+        },
+        Some("ciphertext-autokey") => {
+            let max_length = arg.value_of("max-period").unwrap().parse::<usize>().unwrap();
+
+            crack::autokey::crack_ciphertext_autokey(&input, max_length, &language_model(arg)).unwrap()
+        },
+        Some(_) => unreachable!(),
+        None => unreachable!()
+    };
+
+    for candidate in candidates.iter().take(count) {
+        println!("Key: {} (score: {:.4})", candidate.key, candidate.score);
+        println!("{}", common::reformat(&candidate.plain_text, &output_format(arg, true)));
+        println!();
+    }
+}
+
+fn search_settings(arg: &ArgMatches) -> crack::SearchSettings {
+    crack::SearchSettings {
+        seed: arg.value_of("seed").unwrap().parse::<u64>().unwrap(),
+        restarts: arg.value_of("restarts").unwrap().parse::<usize>().unwrap(),
+        iterations: arg.value_of("iterations").map_or(crack::SearchSettings::default().iterations, |iterations| iterations.parse::<usize>().unwrap()),
+    }
+}
+
+fn language_model(arg: &ArgMatches) -> LanguageModel {
+    match arg.value_of("model") {
+        Some(path) => LanguageModel::parse(&fs::read_to_string(path).unwrap()).unwrap(),
+        None => LanguageModel::english(),
+    }
+}
+
+fn create_train_model_command<'a, 'b>() -> App<'a, 'b> {
+    let corpus = Arg::with_name("corpus")
+                        .help("Plain text file to count n-grams in")
+                        .required(true);
+
+    let output = Arg::with_name("output")
+                        .short("o")
+                        .long("output")
+                        .help("File to write the model to")
+                        .takes_value(true)
+                        .required(true);
+
+    let max_ngrams = Arg::with_name("max-ngrams")
+                        .long("max-ngrams")
+                        .help("Only keep this many of the most common n-grams of each length (default: all)")
+                        .takes_value(true);
+
+    SubCommand::with_name("train-model")
+                .about("Build a language model from a corpus of plain text")
+                .args(&[corpus, output, max_ngrams])
+                .args(&create_format_args())
+}
+
+fn handle_train_model_command(arg: &ArgMatches) {
+    refuse_output_format(arg, "train-model writes a model file");
+
+    let corpus = fs::read(arg.value_of("corpus").unwrap()).unwrap();
+
+    let max_ngrams = arg.value_of("max-ngrams").map(|max| max.parse::<usize>().unwrap());
+
+    let model = LanguageModel::train(&corpus).unwrap();
+
+    fs::write(arg.value_of("output").unwrap(), model.to_text(max_ngrams)).unwrap();
+}
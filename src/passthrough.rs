@@ -0,0 +1,82 @@
+//! # Enciphering the letters of a text and nothing else
+//!
+//! Normally the text is stripped down to its letters and regrouped into blocks of five.
+//! In _passthrough_ mode, only the letters are enciphered: spaces, punctuation and digits stay
+//! where they were, and every letter keeps its original case.
+//!
+//! ```text
+//! Plain text:  Meet me at 10, by the gate!
+//! Key:         LEMON (letters only)
+//! Cipher text: Xiqh zp ef 10, pl elq unei!
+//! ```
+//!
+//! The [`KeyAdvance`] policy decides whether the key moves on when it meets a non-letter.
+
+use crate::alphabet::{Alphabet, Symbol};
+
+/// What the key does when it meets a character that is not enciphered
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum KeyAdvance {
+    /// The key only moves on after enciphering a letter
+    LettersOnly,
+    /// The key moves on after every character, whether it was enciphered or not
+    EveryCharacter,
+}
+
+// A text split into the letters that get enciphered, and everything needed to put it back together
+pub(crate) struct MaskedText {
+    characters: Vec<char>,
+    // For every letter: its position in `characters` and its symbol
+    letters: Vec<(usize, Symbol)>,
+}
+
+impl MaskedText {
+    pub(crate) fn new(alphabet: &Alphabet, text: &[u8]) -> MaskedText {
+        let characters = String::from_utf8_lossy(text).chars().collect::<Vec<char>>();
+
+        let letters = characters.iter()
+                        .enumerate()
+                        .filter_map(|(i, &c)| alphabet.symbol_of(c).map(|symbol| (i, symbol)))
+                        .collect();
+
+        MaskedText { characters, letters }
+    }
+
+    // The letters of the text, in order
+    pub(crate) fn letters(&self) -> Vec<Symbol> {
+        self.letters.iter().map(|&(_, symbol)| symbol).collect()
+    }
+
+    // For every letter, how far the key has advanced when it is reached
+    pub(crate) fn key_positions(&self, advance: KeyAdvance) -> Vec<usize> {
+        match advance {
+            KeyAdvance::LettersOnly => (0..self.letters.len()).collect(),
+            KeyAdvance::EveryCharacter => self.letters.iter().map(|&(i, _)| i).collect(),
+        }
+    }
+
+    // The position a key stream would reach after running over the whole text
+    pub(crate) fn key_length(&self, advance: KeyAdvance) -> usize {
+        match advance {
+            KeyAdvance::LettersOnly => self.letters.len(),
+            KeyAdvance::EveryCharacter => self.characters.len(),
+        }
+    }
+
+    // Puts the text back together, with `letters` in place of the original letters
+    pub(crate) fn unmask(&self, alphabet: &Alphabet, letters: &[Symbol]) -> String {
+        let mut characters = self.characters.clone();
+
+        for (&(i, _), &letter) in self.letters.iter().zip(letters) {
+            let letter = alphabet.character(letter);
+
+            characters[i] = if characters[i].is_lowercase() {
+                letter.to_lowercase().next().unwrap_or(letter)
+            } else {
+                letter
+            };
+        }
+
+        characters.into_iter().collect()
+    }
+}
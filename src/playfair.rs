@@ -0,0 +1,287 @@
+//! # Implementation of the Playfair cipher
+//!
+//! The Playfair enciphers pairs of letters (_digraphs_) with a 5×5 square of letters. The square
+//! is filled with a keyphrase, repeated letters dropped, followed by the rest of the alphabet. There
+//! are only 25 cells, so two letters share one: usually J is written as I.
+//!
+//! ```text
+//! P L A Y F
+//! I R E X M
+//! B C D G H
+//! K N O Q S
+//! T U V W Z
+//! ```
+//!
+//! The plain text is split into digraphs. A doubled letter (e.g. the LL of BALLOON) would be
+//! split by a padding letter, usually X, and a padding letter is added at the end to complete the
+//! last digraph. When the doubled letter is X itself, a second padding letter, usually Q, is used.
+//!
+//! Each digraph is then enciphered with one of three rules:
+//!
+//! 1. Letters in the same row are replaced by the letters to their right (wrapping around)
+//! 2. Letters in the same column are replaced by the letters below them (wrapping around)
+//! 3. Otherwise, the letters are opposite corners of a rectangle, and each is replaced by the
+//!    letter in its own row, in the column of the other letter
+//!
+//! # Example:
+//!
+//! Keyphrase: PLAYFAIR EXAMPLE (the square above)
+//!
+//! Message to encipher: "HIDE THE GOLD IN THE TREE STUMP"
+//!
+//! Digraphs: HI DE TH EG OL DI NT HE TR EX ES TU MP
+//!
+//! Cipher text: BMODZ BXDNA BEKUD MUIXM MOUVI F
+//!
+//! Deciphering reverses the rules, but leaves the padding letters in place.
+
+use crate::cipher::{Cipher, KeyString, KeyedCipher};
+use crate::common::{self, AsciiUppercaseByte};
+use crate::errors::Error;
+use crate::polybius::{self, Grid, Merge};
+use std::convert::TryFrom;
+
+/// Enciphers `plain_text` with a square built from `keyphrase`, writing J as I and padding with X
+pub fn encipher(keyphrase: &[u8], plain_text: &[u8]) -> Result<String, Error> {
+    Playfair::with_key((Grid::square(5, keyphrase)?, Padding::default()))?.encipher(plain_text)
+}
+
+/// Deciphers `cipher_text` with a square built from `keyphrase`, writing J as I
+pub fn decipher(keyphrase: &[u8], cipher_text: &[u8]) -> Result<String, Error> {
+    Playfair::with_key((Grid::square(5, keyphrase)?, Padding::default()))?.decipher(cipher_text)
+}
+
+/// The letters used to split doubled letters and to complete the last digraph
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Padding {
+    /// The usual padding letter
+    pub filler: AsciiUppercaseByte,
+    /// The padding letter used next to `filler` itself
+    pub alternate: AsciiUppercaseByte,
+}
+
+impl Default for Padding {
+    /// X, or Q next to an X
+    fn default() -> Self {
+        Padding { filler: letter(b'X'), alternate: letter(b'Q') }
+    }
+}
+
+impl Padding {
+    // The padding letter that goes next to `letter`
+    fn next_to(&self, letter: AsciiUppercaseByte) -> AsciiUppercaseByte {
+        if letter == self.filler { self.alternate } else { self.filler }
+    }
+}
+
+/// Enciphers the digraph `(a, b)` with `square`
+pub fn encipher_digraph(square: &Grid, a: AsciiUppercaseByte, b: AsciiUppercaseByte) -> (AsciiUppercaseByte, AsciiUppercaseByte) {
+    shift_digraph(square, a, b, 1)
+}
+
+/// Deciphers the digraph `(a, b)` with `square`
+pub fn decipher_digraph(square: &Grid, a: AsciiUppercaseByte, b: AsciiUppercaseByte) -> (AsciiUppercaseByte, AsciiUppercaseByte) {
+    shift_digraph(square, a, b, 4)
+}
+
+// Applies the Playfair rules, moving `step` cells to the right (or down) along rows (or columns)
+fn shift_digraph(square: &Grid, a: AsciiUppercaseByte, b: AsciiUppercaseByte, step: usize) -> (AsciiUppercaseByte, AsciiUppercaseByte) {
+    let (row_a, column_a) = square.position(a);
+    let (row_b, column_b) = square.position(b);
+
+    let ((row_a, column_a), (row_b, column_b)) = if row_a == row_b {
+        ((row_a, (column_a + step) % 5), (row_b, (column_b + step) % 5))
+    } else if column_a == column_b {
+        (((row_a + step) % 5, column_a), ((row_b + step) % 5, column_b))
+    } else {
+        ((row_a, column_b), (row_b, column_a))
+    };
+
+    (square.at(row_a, column_a), square.at(row_b, column_b))
+}
+
+/// The Playfair cipher, for use through the [`Cipher`] trait
+///
+/// The key is the square and the padding letters. As a string, it is the keyphrase, with the
+/// settings `merge` (two letters: the letter left out of the square, then the letter it is
+/// written as, default `JI`) and `pad` (the padding letter, then the one used next to it,
+/// default `XQ`).
+pub struct Playfair {
+    square: Grid,
+    merge: Merge,
+    padding: Padding,
+}
+
+impl KeyedCipher for Playfair {
+    type Key = (Grid, Padding);
+
+    fn parse_key(key: &str) -> Result<Self::Key, Error> {
+        let key = KeyString::parse(key)?;
+
+        let merge = polybius::parse_merge(&key)?;
+
+        let padding = match key.setting("pad").map(|pad| pad.as_bytes()) {
+            None => Padding::default(),
+            Some(&[filler, alternate]) => Padding { filler: polybius::letter_setting(filler)?, alternate: polybius::letter_setting(alternate)? },
+            Some(_) => return Err(Error::KeyError("The pad setting should be two letters, e.g. XQ".to_string())),
+        };
+
+        Ok((Grid::letter_square(key.main.as_bytes(), merge)?, padding))
+    }
+
+    fn with_key((square, padding): Self::Key) -> Result<Self, Error> {
+        let merge = polybius::check_letter_square(&square)?;
+
+        if padding.filler == padding.alternate || [padding.filler, padding.alternate].contains(&merge.from) {
+            return Err(Error::KeyError("The padding letters must be two different letters of the square".to_string()));
+        }
+
+        Ok(Playfair { square, merge, padding })
+    }
+}
+
+impl Playfair {
+    /// The key square
+    pub fn square(&self) -> &Grid {
+        &self.square
+    }
+
+    /// Splits `plain_text` into digraphs, padding doubled letters and the last digraph
+    pub fn digraphs(&self, plain_text: &[AsciiUppercaseByte]) -> Vec<(AsciiUppercaseByte, AsciiUppercaseByte)> {
+        let text = plain_text.iter().map(|&letter| self.merge.apply(letter)).collect::<Vec<_>>();
+
+        let mut digraphs = Vec::with_capacity(text.len() / 2 + 1);
+        let mut i = 0;
+
+        while i < text.len() {
+            match text.get(i + 1) {
+                Some(&next) if next != text[i] => {
+                    digraphs.push((text[i], next));
+                    i += 2;
+                },
+                _ => {
+                    digraphs.push((text[i], self.padding.next_to(text[i])));
+                    i += 1;
+                },
+            }
+        }
+
+        digraphs
+    }
+}
+
+impl Cipher for Playfair {
+    fn encipher(&self, plain_text: &[u8]) -> Result<String, Error> {
+        let plain_text = common::sanitize_text(plain_text)?;
+
+        let cipher_text = self.digraphs(&plain_text)
+            .into_iter()
+            .flat_map(|(a, b)| {
+                let (a, b) = encipher_digraph(&self.square, a, b);
+                vec![a, b]
+            })
+            .collect();
+
+        Ok(common::format_output(cipher_text))
+    }
+
+    fn decipher(&self, cipher_text: &[u8]) -> Result<String, Error> {
+        let cipher_text = common::sanitize_text(cipher_text)?;
+
+        polybius::check_even(&cipher_text, "Playfair")?;
+
+        let plain_text = cipher_text.chunks(2)
+            .flat_map(|digraph| {
+                let (a, b) = decipher_digraph(&self.square, digraph[0], digraph[1]);
+                vec![a, b]
+            })
+            .collect();
+
+        Ok(common::format_output(plain_text))
+    }
+}
+
+fn letter(byte: u8) -> AsciiUppercaseByte {
+    AsciiUppercaseByte::try_from(byte).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cipher::{Cipher, KeyedCipher};
+    use crate::common;
+    use crate::playfair::{self, Padding, Playfair};
+    use crate::polybius::Grid;
+    use quickcheck::quickcheck;
+
+    #[test]
+    fn test_square() {
+        let playfair = Playfair::from_key_str("PLAYFAIR EXAMPLE").unwrap();
+
+        assert_eq!(playfair.square().to_string(), "P L A Y F\nI R E X M\nB C D G H\nK N O Q S\nT U V W Z\n");
+
+        let playfair = Playfair::from_key_str("PLAYFAIR EXAMPLE;merge=QK;pad=XZ").unwrap();
+
+        assert_eq!(playfair.square().to_string(), "P L A Y F\nI R E X M\nB C D G H\nJ K N O S\nT U V W Z\n");
+    }
+
+    #[test]
+    fn test_playfair() {
+        let enciphered = playfair::encipher(b"PLAYFAIR EXAMPLE", b"HIDE THE GOLD IN THE TREE STUMP").unwrap();
+
+        assert_eq!(enciphered, "BMODZ BXDNA BEKUD MUIXM MOUVI \nF");
+
+        let deciphered = playfair::decipher(b"PLAYFAIR EXAMPLE", enciphered.as_bytes()).unwrap();
+
+        assert_eq!(deciphered, "HIDET HEGOL DINTH ETREX ESTUM \nP");
+    }
+
+    #[test]
+    fn test_padding() {
+        let playfair = Playfair::from_key_str("PLAYFAIR EXAMPLE").unwrap();
+
+        let digraphs = playfair.digraphs(&common::sanitize_text(b"JAZZ EXXON").unwrap())
+            .into_iter()
+            .flat_map(|(a, b)| vec![a, b])
+            .collect();
+
+        // J is written as I, the doubled X is split with a Q, and the last N is padded
+        assert_eq!(common::format_output(digraphs), "IAZXZ EXQXO NX");
+
+        let playfair = Playfair::from_key_str("PLAYFAIR EXAMPLE;pad=QZ").unwrap();
+
+        let digraphs = playfair.digraphs(&common::sanitize_text(b"JAZZ").unwrap())
+            .into_iter()
+            .flat_map(|(a, b)| vec![a, b])
+            .collect();
+
+        assert_eq!(common::format_output(digraphs), "IAZQZ Q");
+    }
+
+    #[test]
+    fn test_invalid_keys() {
+        assert!(Playfair::from_key_str("KEY;merge=J").is_err());
+        assert!(Playfair::from_key_str("KEY;merge=II").is_err());
+        assert!(Playfair::from_key_str("KEY;pad=XX").is_err());
+        assert!(Playfair::from_key_str("KEY;pad=JQ").is_err());
+        assert!(Playfair::with_key((Grid::square(6, b"KEY").unwrap(), Padding::default())).is_err());
+        assert!(playfair::decipher(b"KEY", b"ABC").is_err());
+    }
+
+    quickcheck! {
+        fn playfair_round_trip(keyphrase: Vec<u8>, plain_text: Vec<u8>) -> bool {
+            let playfair = Playfair::with_key((Grid::square(5, &keyphrase).unwrap(), Padding::default())).unwrap();
+
+            let enciphered = playfair.encipher(&plain_text).unwrap();
+            let deciphered = playfair.decipher(enciphered.as_bytes()).unwrap();
+
+            // Deciphering gives back the padded digraphs
+
+            let digraphs = playfair.digraphs(&common::sanitize_text(&plain_text).unwrap())
+                .into_iter()
+                .flat_map(|(a, b)| vec![a, b])
+                .collect();
+
+            deciphered == common::format_output(digraphs)
+        }
+    }
+}
@@ -0,0 +1,544 @@
+//! # Polybius squares
+//!
+//! Several ciphers of the crate write a keyword-mixed [`Alphabet`] into a square, named after
+//! the signalling square Polybius described in the 2nd century BC. This module builds these
+//! [`Grid`]s, and holds the pieces of key handling the ciphers share.
+//!
+//! A square is built from a keyword: the letters of the keyword, repeated letters dropped,
+//! followed by the rest of the alphabet.
+//!
+//! The digraphic ciphers (Playfair, two-square and four-square) use 5×5 squares of letters. There
+//! are only 25 cells, so two letters must share one. Usually J is written as I, but some systems
+//! merge other letters, or drop Q altogether.
+//!
+//! Keyword: EXAMPLE
+//!
+//! ```text
+//! E X A M P
+//! L B C D F
+//! G H I K N
+//! O Q R S T
+//! U V W Y Z
+//! ```
+//!
+//! The fractionating ciphers (Bifid and Trifid) also use a 6×6 square of the letters and digits,
+//! or a 3×3×3 cube. They care about the _coordinates_ of each symbol more than about the symbol.
+//!
+//! The square is also a cipher of its own, the Polybius square: each letter is replaced by its
+//! row and column, counted from 1 ([`Polybius`]).
+//!
+//! ```text
+//!   1 2 3 4 5
+//! 1 E X A M P
+//! 2 L B C D F       HELLO = 32 11 21 21 41
+//! 3 G H I K N
+//! 4 O Q R S T
+//! 5 U V W Y Z
+//! ```
+
+use crate::alphabet::{Alphabet, Symbol};
+use crate::cipher::{Cipher, KeyString, KeyedCipher};
+use crate::common::{self, AsciiUppercaseByte};
+use crate::errors::Error;
+use std::convert::TryFrom;
+use std::fmt;
+
+/// The two letters that share a cell of the square
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Merge {
+    /// The letter that is left out of the square
+    pub from: AsciiUppercaseByte,
+    /// The letter `from` is written as
+    pub into: AsciiUppercaseByte,
+}
+
+impl Default for Merge {
+    /// J is written as I
+    fn default() -> Self {
+        Merge { from: letter(b'J'), into: letter(b'I') }
+    }
+}
+
+impl Merge {
+    /// Writes `letter` as it appears in the square
+    pub fn apply(&self, letter: AsciiUppercaseByte) -> AsciiUppercaseByte {
+        if letter == self.from { self.into } else { letter }
+    }
+}
+
+/// A keyword-mixed alphabet written into a square or a cube, row by row (and layer by layer)
+///
+/// A symbol's cell is its position in the mixed alphabet. Its coordinates are that position
+/// written in base `side`, most significant first: row and column in a square, layer, row and
+/// column in a cube. Coordinates count from 0.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Grid {
+    alphabet: Alphabet,
+    side: usize,
+    dimensions: usize,
+    // Cell of every letter from A to Z, usize::MAX if it is not in the grid. A merged letter has
+    // the cell of the letter it is written as.
+    cells: [usize; 26],
+    merge: Option<Merge>,
+}
+
+impl Grid {
+    /// Mixes `alphabet` with `keyword`, and writes it into a grid of `dimensions` dimensions
+    ///
+    /// Returns an error unless the alphabet exactly fills the grid
+    pub fn new(alphabet: &Alphabet, keyword: &[u8], dimensions: usize) -> Result<Grid, Error> {
+        let side = (1..=alphabet.len())
+            .find(|side| side.pow(dimensions as u32) >= alphabet.len())
+            .unwrap_or(0);
+
+        if dimensions == 0 || side.pow(dimensions as u32) != alphabet.len() {
+            return Err(Error::AlphabetError(format!(
+                "An alphabet of {} symbols does not fill a grid of {} dimensions", alphabet.len(), dimensions
+            )));
+        }
+
+        let alphabet = alphabet.keyed(keyword);
+
+        let mut cells = [usize::MAX; 26];
+        let mut merge = None;
+
+        for (cell, byte) in cells.iter_mut().zip(b'A'..=b'Z') {
+            if let Some(symbol) = alphabet.symbol_of(char::from(byte)) {
+                *cell = symbol.index();
+
+                match u8::try_from(alphabet.character(symbol)) {
+                    Ok(into) if into != byte && into.is_ascii_uppercase() => merge = Some(Merge { from: letter(byte), into: letter(into) }),
+                    _ => (),
+                }
+            }
+        }
+
+        Ok(Grid { alphabet, side, dimensions, cells, merge })
+    }
+
+    /// A 5×5 square of the letters, J written as I, or a 6×6 square of the letters and digits
+    pub fn square(side: usize, keyword: &[u8]) -> Result<Grid, Error> {
+        match side {
+            5 => Grid::letter_square(keyword, Merge::default()),
+            6 => Grid::new(&Alphabet::alphanumeric(), keyword, 2),
+            _ => Err(Error::KeyError(format!("A Polybius square is 5 or 6 cells wide, not {}", side))),
+        }
+    }
+
+    /// A 5×5 square of the letters, with `merge.from` written as `merge.into`, as used by the
+    /// digraphic ciphers
+    pub fn letter_square(keyword: &[u8], merge: Merge) -> Result<Grid, Error> {
+        if merge.from == merge.into {
+            return Err(Error::KeyError(format!("Cannot merge {} into itself", char::from(merge.from))));
+        }
+
+        let letters = (b'A'..=b'Z')
+            .filter(|&letter| letter != merge.from.get_byte())
+            .map(char::from)
+            .collect::<String>();
+
+        let alphabet = Alphabet::new(&letters)?.with_merge(char::from(merge.from), char::from(merge.into))?;
+
+        Grid::new(&alphabet, keyword, 2)
+    }
+
+    /// A 3×3×3 cube of the letters and +
+    pub fn cube(keyword: &[u8]) -> Grid {
+        Grid::new(&Alphabet::trifid(), keyword, 3).unwrap()
+    }
+
+    /// The mixed alphabet, in the order it fills the grid
+    pub fn alphabet(&self) -> &Alphabet {
+        &self.alphabet
+    }
+
+    /// Number of cells along each edge
+    pub fn side(&self) -> usize {
+        self.side
+    }
+
+    /// Number of coordinates of each symbol: 2 for a square, 3 for a cube
+    pub fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+
+    /// The coordinates of `symbol`, most significant first
+    pub fn coordinates(&self, symbol: Symbol) -> Vec<usize> {
+        (0..self.dimensions)
+            .rev()
+            .map(|d| symbol.index() / self.side.pow(d as u32) % self.side)
+            .collect()
+    }
+
+    /// The symbol at `coordinates`, most significant first
+    pub fn symbol(&self, coordinates: &[usize]) -> Symbol {
+        self.alphabet.symbol(coordinates.iter().fold(0, |index, &c| index * self.side + c))
+    }
+
+    /// The letter left out of a square of letters, and the letter it is written as, if any
+    pub fn merge(&self) -> Option<Merge> {
+        self.merge
+    }
+
+    /// The row and column of `letter` in a square
+    ///
+    /// # Panics
+    ///
+    /// Panics if `letter` is not in the grid
+    pub fn position(&self, letter: AsciiUppercaseByte) -> (usize, usize) {
+        let cell = self.cells[letter.index()];
+
+        assert_ne!(cell, usize::MAX, "Letter {} is not in the grid", char::from(letter));
+
+        (cell / self.side, cell % self.side)
+    }
+
+    /// The letter in row `row` and column `column` of a square
+    ///
+    /// # Panics
+    ///
+    /// Panics if that cell does not hold a letter A-Z
+    pub fn at(&self, row: usize, column: usize) -> AsciiUppercaseByte {
+        let character = self.alphabet.character(self.alphabet.symbol(row * self.side + column));
+
+        AsciiUppercaseByte::try_from(u8::try_from(character).unwrap()).unwrap()
+    }
+
+    /// Swaps the symbols in the cells `a` and `b`, counted row by row from 0
+    pub fn swap(&mut self, a: usize, b: usize) {
+        self.alphabet.swap(a, b);
+
+        for cell in self.cells.iter_mut() {
+            if *cell == a {
+                *cell = b;
+            } else if *cell == b {
+                *cell = a;
+            }
+        }
+    }
+}
+
+impl fmt::Display for Grid {
+    /// Writes the grid row by row, with a blank line between the layers of a cube
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbols = (0..self.alphabet.len()).map(|i| self.alphabet.symbol(i)).collect::<Vec<Symbol>>();
+
+        for (i, row) in symbols.chunks(self.side).enumerate() {
+            if i > 0 && (i * self.side).is_multiple_of(self.side * self.side) {
+                writeln!(f)?;
+            }
+
+            let row = self.alphabet.render(row).into_iter().map(String::from).collect::<Vec<String>>();
+
+            writeln!(f, "{}", row.join(" "))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Fractionates `text` with `grid`, `period` symbols at a time (all at once if `period` is 0)
+///
+/// For each block, the first coordinate of every symbol is written out, then the second, and so
+/// on. The digits are then read back, `grid.dimensions()` at a time, as the coordinates of new
+/// symbols. This is the heart of the Bifid and Trifid ciphers.
+pub fn fractionate(grid: &Grid, text: &[Symbol], period: usize) -> Vec<Symbol> {
+    blocks(text, period)
+        .flat_map(|block| {
+            let coordinates = block.iter().map(|&symbol| grid.coordinates(symbol)).collect::<Vec<_>>();
+
+            let digits = (0..grid.dimensions())
+                .flat_map(|d| coordinates.iter().map(move |c| c[d]))
+                .collect::<Vec<usize>>();
+
+            digits.chunks(grid.dimensions()).map(|c| grid.symbol(c)).collect::<Vec<Symbol>>()
+        })
+        .collect()
+}
+
+/// Reverses [`fractionate`]
+pub fn unfractionate(grid: &Grid, text: &[Symbol], period: usize) -> Vec<Symbol> {
+    blocks(text, period)
+        .flat_map(|block| {
+            let digits = block.iter().flat_map(|&symbol| grid.coordinates(symbol)).collect::<Vec<usize>>();
+
+            (0..block.len())
+                .map(|i| {
+                    let coordinates = (0..grid.dimensions()).map(|d| digits[d * block.len() + i]).collect::<Vec<usize>>();
+
+                    grid.symbol(&coordinates)
+                })
+                .collect::<Vec<Symbol>>()
+        })
+        .collect()
+}
+
+fn blocks(text: &[Symbol], period: usize) -> std::slice::Chunks<'_, Symbol> {
+    text.chunks(if period == 0 { text.len().max(1) } else { period })
+}
+
+/// The Polybius square, for use through the [`Cipher`] trait
+///
+/// Each letter is replaced by two digits, its row and column counted from 1. The key is the
+/// grid. As a string, it is the keyword, with the setting `size` (5 or 6, default 5).
+pub struct Polybius {
+    grid: Grid,
+}
+
+impl KeyedCipher for Polybius {
+    type Key = Grid;
+
+    fn parse_key(key: &str) -> Result<Self::Key, Error> {
+        let key = KeyString::parse(key)?;
+
+        Grid::square(key.parsed_setting("size", 5)?, key.main.as_bytes())
+    }
+
+    fn with_key(grid: Self::Key) -> Result<Self, Error> {
+        Ok(Polybius { grid })
+    }
+}
+
+impl Polybius {
+    /// The key square
+    pub fn grid(&self) -> &Grid {
+        &self.grid
+    }
+}
+
+impl Cipher for Polybius {
+    fn encipher(&self, plain_text: &[u8]) -> Result<String, Error> {
+        let digits = common::sanitize_text_with(plain_text, self.grid.alphabet())
+            .into_iter()
+            .flat_map(|symbol| self.grid.coordinates(symbol))
+            .map(|c| (b'1' + c as u8) as char)
+            .collect();
+
+        Ok(common::format_output(digits))
+    }
+
+    fn decipher(&self, cipher_text: &[u8]) -> Result<String, Error> {
+        let digits = cipher_text.iter()
+            .filter(|c| c.is_ascii_digit())
+            .map(|&c| match (c - b'0') as usize {
+                digit @ 1..=6 if digit <= self.grid.side() => Ok(digit - 1),
+                digit => Err(Error::DecipheringError(format!("{} is not a row or column of the square", digit))),
+            })
+            .collect::<Result<Vec<usize>, Error>>()?;
+
+        if !digits.len().is_multiple_of(2) {
+            return Err(Error::DecipheringError("Polybius cipher text must have an even number of digits".to_string()));
+        }
+
+        let symbols = digits.chunks(2).map(|c| self.grid.symbol(c)).collect::<Vec<Symbol>>();
+
+        Ok(common::format_output(self.grid.alphabet().render(&symbols)))
+    }
+}
+
+/// Reads the `merge` setting of `key`: two letters, the letter left out of the square and the
+/// letter it is written as (J as I if it is not given)
+pub fn parse_merge(key: &KeyString) -> Result<Merge, Error> {
+    match key.setting("merge").map(|merge| merge.as_bytes()) {
+        None => Ok(Merge::default()),
+        Some(&[from, into]) => Ok(Merge { from: letter_setting(from)?, into: letter_setting(into)? }),
+        Some(_) => Err(Error::KeyError("The merge setting should be two letters, e.g. JI".to_string())),
+    }
+}
+
+/// Splits `text` into digraphs of letters written as they appear in a square, completing the
+/// last digraph with `pad`
+pub fn digraphs(text: &[AsciiUppercaseByte], merge: Merge, pad: AsciiUppercaseByte) -> Vec<(AsciiUppercaseByte, AsciiUppercaseByte)> {
+    text.chunks(2)
+        .map(|digraph| (merge.apply(digraph[0]), digraph.get(1).map_or(pad, |&letter| merge.apply(letter))))
+        .collect()
+}
+
+/// The letters at the other two corners of the rectangle made by `a` in `square_a` and `b` in
+/// `square_b`: the letter of `into_a` in the row of `a` and the column of `b`, and the letter of
+/// `into_b` in the row of `b` and the column of `a`
+///
+/// Two-square and four-square encipher and decipher with this rule, only changing the squares.
+pub fn opposite_corners(
+    (square_a, a): (&Grid, AsciiUppercaseByte),
+    (square_b, b): (&Grid, AsciiUppercaseByte),
+    (into_a, into_b): (&Grid, &Grid),
+) -> (AsciiUppercaseByte, AsciiUppercaseByte) {
+    let (row_a, column_a) = square_a.position(a);
+    let (row_b, column_b) = square_b.position(b);
+
+    (into_a.at(row_a, column_b), into_b.at(row_b, column_a))
+}
+
+/// Reads the `pad` setting of `key`: the letter completing the last digraph (X if it is not given)
+pub(crate) fn parse_pad(key: &KeyString, merge: Merge) -> Result<AsciiUppercaseByte, Error> {
+    let pad = match key.setting("pad").map(|pad| pad.as_bytes()) {
+        None => letter(b'X'),
+        Some(&[pad]) => letter_setting(pad)?,
+        Some(_) => return Err(Error::KeyError("The pad setting should be one letter, e.g. X".to_string())),
+    };
+
+    check_pad(pad, merge)?;
+
+    Ok(pad)
+}
+
+/// Checks that `square` is a 5×5 square of the letters, two of them sharing a cell, and returns
+/// the letters it merges
+pub(crate) fn check_letter_square(square: &Grid) -> Result<Merge, Error> {
+    match square.merge() {
+        Some(merge) if square.side() == 5 && square.dimensions() == 2 && !square.cells.contains(&usize::MAX) => Ok(merge),
+        _ => Err(Error::KeyError("A digraphic cipher needs a 5×5 square of the letters".to_string())),
+    }
+}
+
+/// Checks that the two squares of a digraphic cipher merge the same letters, and that `pad` is in
+/// them, and returns the letters they merge
+pub(crate) fn check_squares(first: &Grid, second: &Grid, pad: AsciiUppercaseByte) -> Result<Merge, Error> {
+    let merge = check_letter_square(first)?;
+
+    if check_letter_square(second)? != merge {
+        return Err(Error::KeyError("Both squares must leave out the same letter".to_string()));
+    }
+
+    check_pad(pad, merge)?;
+
+    Ok(merge)
+}
+
+fn check_pad(pad: AsciiUppercaseByte, merge: Merge) -> Result<(), Error> {
+    if pad == merge.from {
+        return Err(Error::KeyError(format!("The padding letter {} is not in the square", char::from(pad))));
+    }
+
+    Ok(())
+}
+
+/// Checks that `cipher_text` splits into digraphs
+pub(crate) fn check_even(cipher_text: &[AsciiUppercaseByte], cipher: &str) -> Result<(), Error> {
+    if !cipher_text.len().is_multiple_of(2) {
+        return Err(Error::DecipheringError(format!("{} cipher text must have an even number of letters", cipher)));
+    }
+
+    Ok(())
+}
+
+pub(crate) fn letter_setting(byte: u8) -> Result<AsciiUppercaseByte, Error> {
+    AsciiUppercaseByte::try_from(byte.to_ascii_uppercase())
+}
+
+pub(crate) fn letter(byte: u8) -> AsciiUppercaseByte {
+    AsciiUppercaseByte::try_from(byte).unwrap()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::alphabet::Alphabet;
+    use crate::cipher::{Cipher, KeyedCipher};
+    use crate::common;
+    use crate::polybius::{self, Grid, Merge, Polybius};
+    use quickcheck::quickcheck;
+    use std::convert::TryFrom;
+    use crate::common::AsciiUppercaseByte;
+
+    #[test]
+    fn test_square() {
+        let square = Grid::square(5, b"EXAMPLE").unwrap();
+
+        assert_eq!(square.to_string(), "E X A M P\nL B C D F\nG H I K N\nO Q R S T\nU V W Y Z\n");
+        assert_eq!(square.merge(), Some(Merge::default()));
+
+        let j = AsciiUppercaseByte::try_from(b'J').unwrap();
+
+        assert_eq!(square.position(j), (2, 2));
+        assert_eq!(square.at(2, 2).get_byte(), b'I');
+
+        // Swapping I with E moves J along with it
+        let mut square = square;
+        square.swap(0, 12);
+
+        assert_eq!(square, Grid::square(5, b"IXAMPLBCDFGHEKNOQRSTUVWYZ").unwrap());
+        assert_eq!(square.position(j), (0, 0));
+    }
+
+    #[test]
+    fn test_letter_square() {
+        let merge = Merge { from: AsciiUppercaseByte::try_from(b'Q').unwrap(), into: AsciiUppercaseByte::try_from(b'K').unwrap() };
+        let square = Grid::letter_square(b"QUEEN", merge).unwrap();
+
+        assert_eq!(square.to_string(), "K U E N A\nB C D F G\nH I J L M\nO P R S T\nV W X Y Z\n");
+        assert_eq!(square.merge(), Some(merge));
+
+        assert!(polybius::check_letter_square(&square).is_ok());
+        assert!(polybius::check_letter_square(&Grid::square(6, b"").unwrap()).is_err());
+
+        let merge = Merge { from: merge.from, into: merge.from };
+
+        assert!(Grid::letter_square(b"KEY", merge).is_err());
+    }
+
+    #[test]
+    fn test_digraphs() {
+        let merge = Merge::default();
+        let pad = AsciiUppercaseByte::try_from(b'X').unwrap();
+
+        let digraphs = polybius::digraphs(&common::sanitize_text(b"JOLLY").unwrap(), merge, pad)
+            .into_iter()
+            .flat_map(|(a, b)| vec![a, b])
+            .collect();
+
+        assert_eq!(common::format_output(digraphs), "IOLLY X");
+    }
+
+    #[test]
+    fn test_grid() {
+        let grid = Grid::square(6, b"PROCESSOR 2000").unwrap();
+
+        assert_eq!(grid.to_string(), "P R O C E S\n2 0 A B D F\nG H I J K L\nM N Q T U V\nW X Y Z 1 3\n4 5 6 7 8 9\n");
+
+        let cube = Grid::cube(b"FELIX MARIE DELASTELLE");
+
+        assert_eq!(cube.to_string(), "F E L\nI X M\nA R D\n\nS T B\nC G H\nJ K N\n\nO P Q\nU V W\nY Z +\n");
+
+        let plus = cube.alphabet().symbol_of('+').unwrap();
+
+        assert_eq!(cube.coordinates(plus), vec![2, 2, 2]);
+        assert_eq!(cube.symbol(&[2, 2, 2]), plus);
+
+        assert!(Grid::square(7, b"KEY").is_err());
+        assert!(Grid::new(&Alphabet::standard(), b"KEY", 2).is_err());
+    }
+
+    #[test]
+    fn test_polybius() {
+        let polybius = Polybius::from_key_str("EXAMPLE").unwrap();
+
+        let enciphered = polybius.encipher(b"Hello").unwrap();
+
+        assert_eq!(enciphered, "32112 12141");
+
+        assert_eq!(polybius.decipher(enciphered.as_bytes()).unwrap(), "HELLO");
+
+        assert!(polybius.decipher(b"123").is_err());
+        assert!(polybius.decipher(b"16").is_err());
+    }
+
+    quickcheck! {
+        fn fractionate_round_trip(keyword: Vec<u8>, plain_text: Vec<u8>, period: u8) -> bool {
+            let grid = Grid::square(6, &keyword).unwrap();
+            let text = common::sanitize_text_with(&plain_text, grid.alphabet());
+
+            polybius::unfractionate(&grid, &polybius::fractionate(&grid, &text, period as usize), period as usize) == text
+        }
+
+        fn polybius_round_trip(keyword: Vec<u8>, plain_text: Vec<u8>) -> bool {
+            let polybius = Polybius::with_key(Grid::square(6, &keyword).unwrap()).unwrap();
+
+            let enciphered = polybius.encipher(&plain_text).unwrap();
+
+            polybius.decipher(enciphered.as_bytes()).unwrap()
+                == common::format_output(polybius.grid().alphabet().render(&common::sanitize_text_with(&plain_text, polybius.grid().alphabet())))
+        }
+    }
+}
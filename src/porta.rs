@@ -0,0 +1,121 @@
+//! # Implementation of the Porta cipher
+//!
+//! Giovanni Battista della Porta published this cipher in 1563. Its tableau has 13 alphabets,
+//! each picked by a pair of key letters (AB, CD... YZ). Every alphabet swaps the first half of
+//! the alphabet with the second half, slid by one more place from one row to the next:
+//!
+//! ```text
+//!     A B C D E F G H I J K L M N O P Q R S T U V W X Y Z
+//! AB  N O P Q R S T U V W X Y Z A B C D E F G H I J K L M
+//! CD  O P Q R S T U V W X Y Z N M A B C D E F G H I J K L
+//! EF  P Q R S T U V W X Y Z N O L M A B C D E F G H I J K
+//! ...
+//! ```
+//!
+//! As every alphabet swaps letters in pairs, the cipher is reciprocal: enciphering and
+//! deciphering are the same operation. No letter ever stands for itself, and a letter of one
+//! half of the alphabet always stands for a letter of the other half.
+//!
+//! The tableau is printed by `porta --show-tableau` (see [`crate::tableau`]).
+//!
+//! # Example:
+//!
+//! Key: FORTIFICATION
+//!
+//! Plain text: DEFEND THE EAST WALL OF THE CASTLE
+//!
+//! Cipher text: SYNNJ SCVRN RLAHU TUKUC VRYRL ANY
+
+use crate::cipher::{Cipher, KeyedCipher};
+use crate::common::{self, AsciiUppercaseByte};
+use crate::errors::Error;
+use crate::passthrough::KeyAdvance;
+use crate::tableau::{Tableau, TableauCipher};
+
+/// Encipher (or decipher) `text` with the Porta cipher under the key `key`
+pub fn encipher(key: &[u8], text: &[u8]) -> Result<String, Error> {
+    Porta::with_key(common::sanitize_text(key)?)?.encipher(text)
+}
+
+/// The Porta cipher, for use through the [`Cipher`] trait
+pub struct Porta(TableauCipher);
+
+impl KeyedCipher for Porta {
+    type Key = Vec<AsciiUppercaseByte>;
+
+    fn parse_key(key: &str) -> Result<Self::Key, Error> {
+        common::sanitize_text(key.as_bytes())
+    }
+
+    fn with_key(key: Self::Key) -> Result<Self, Error> {
+        Ok(Porta(TableauCipher::with_key((Tableau::porta(), key))?))
+    }
+}
+
+impl Cipher for Porta {
+    fn encipher(&self, plain_text: &[u8]) -> Result<String, Error> {
+        self.0.encipher(plain_text)
+    }
+
+    fn decipher(&self, cipher_text: &[u8]) -> Result<String, Error> {
+        self.encipher(cipher_text)
+    }
+
+    fn encipher_passthrough(&self, plain_text: &[u8], advance: KeyAdvance) -> Result<String, Error> {
+        self.0.encipher_passthrough(plain_text, advance)
+    }
+
+    fn decipher_passthrough(&self, cipher_text: &[u8], advance: KeyAdvance) -> Result<String, Error> {
+        self.encipher_passthrough(cipher_text, advance)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cipher::{Cipher, KeyedCipher};
+    use crate::common;
+    use crate::passthrough::KeyAdvance;
+    use crate::porta::{self, Porta};
+    use quickcheck::quickcheck;
+
+    #[test]
+    fn test_porta() {
+        let enciphered = porta::encipher(b"FORTIFICATION", b"DEFEND THE EAST WALL OF THE CASTLE").unwrap();
+
+        assert_eq!(enciphered, "SYNNJ SCVRN RLAHU TUKUC VRYRL \nANY");
+
+        let deciphered = porta::encipher(b"FORTIFICATION", enciphered.as_bytes()).unwrap();
+
+        assert_eq!(deciphered, "DEFEN DTHEE ASTWA LLOFT HECAS \nTLE");
+    }
+
+    #[test]
+    fn test_porta_passthrough() {
+        let porta = Porta::from_key_str("FORTIFICATION").unwrap();
+
+        let enciphered = porta.encipher_passthrough(b"Defend the east wall!", KeyAdvance::LettersOnly).unwrap();
+
+        assert_eq!(enciphered, "Synnjs cvr nrla hutu!");
+
+        assert_eq!(porta.decipher_passthrough(enciphered.as_bytes(), KeyAdvance::LettersOnly).unwrap(), "Defend the east wall!");
+    }
+
+    quickcheck! {
+        fn porta_is_reciprocal(key: Vec<u8>, plain_text: Vec<u8>) -> bool {
+            let enciphered = porta::encipher(&key, &plain_text).unwrap();
+            let deciphered = porta::encipher(&key, enciphered.as_bytes()).unwrap();
+
+            deciphered == common::format_output(common::sanitize_text(&plain_text).unwrap())
+        }
+    }
+
+    quickcheck! {
+        fn no_letter_stands_for_itself(plain_text: Vec<u8>) -> bool {
+            let plain_text = common::format_output(common::sanitize_text(&plain_text).unwrap());
+
+            let enciphered = porta::encipher(b"PORTA", plain_text.as_bytes()).unwrap();
+
+            enciphered.bytes().zip(plain_text.bytes()).all(|(c, p)| c != p || !c.is_ascii_uppercase())
+        }
+    }
+}
@@ -0,0 +1,297 @@
+//! # Implementation of the Quagmire ciphers
+//!
+//! The Quagmires are periodic ciphers like the Vigenere, but slide keyed alphabets against each
+//! other instead of straight ones. A plain alphabet sits above a cipher alphabet. For each letter
+//! of the _indicator_ keyword in turn, the cipher alphabet is slid along until the indicator
+//! letter stands under a fixed letter of the plain alphabet (the _position_), and the next letter
+//! of the message is enciphered with that slide.
+//!
+//! The four ciphers of the family differ in which alphabets are keyed (see
+//! [`Alphabet::keyed`](crate::alphabet::Alphabet::keyed)):
+//!
+//! | Cipher       | Plain alphabet       | Cipher alphabet       | Indicator under        |
+//! |--------------|----------------------|-----------------------|------------------------|
+//! | Quagmire I   | keyed                | straight              | A                      |
+//! | Quagmire II  | straight             | keyed                 | A                      |
+//! | Quagmire III | keyed                | keyed, same keyword   | the first plain letter |
+//! | Quagmire IV  | keyed                | keyed, other keyword  | the first plain letter |
+//!
+//! With both keywords left out, every Quagmire is the Vigenere cipher.
+//!
+//! # Example:
+//!
+//! The first part of the Kryptos sculpture is a Quagmire III.
+//!
+//! Keyword: KRYPTOS
+//!
+//! ```text
+//! Plain:   K R Y P T O S A B C D E F G H I J L M N Q U V W X Z
+//! Slide P: P T O S A B C D E F G H I J L M N Q U V W X Z K R Y
+//! ```
+//!
+//! Indicator: PALIMPSEST
+//!
+//! Plain text: BETWEEN SUBTLE SHADING
+//!
+//! Cipher text: EMUFP HZLRF AXYUS DJKZL
+
+use crate::alphabet::{Alphabet, Symbol};
+use crate::cipher::{Cipher, KeyString};
+use crate::common::{self, AsciiUppercaseByte};
+use crate::errors::Error;
+use crate::passthrough::KeyAdvance;
+use crate::vigenere_standard;
+use std::convert::TryFrom;
+
+/// Encipher `plain_text` with the Quagmire III cipher: both alphabets mixed with `keyword`, slid
+/// by the letters of `indicator`
+pub fn encipher(keyword: &[u8], indicator: &[u8], plain_text: &[u8]) -> Result<String, Error> {
+    let alphabet = keyed_alphabet(keyword);
+
+    Quagmire::new(alphabet.clone(), alphabet, common::sanitize_text(indicator)?, None)?.encipher(plain_text)
+}
+
+/// Reverses [`encipher`]
+pub fn decipher(keyword: &[u8], indicator: &[u8], cipher_text: &[u8]) -> Result<String, Error> {
+    let alphabet = keyed_alphabet(keyword);
+
+    Quagmire::new(alphabet.clone(), alphabet, common::sanitize_text(indicator)?, None)?.decipher(cipher_text)
+}
+
+/// The letters A-Z mixed with `keyword`: the letters of the keyword, repeats dropped, followed by
+/// the rest of the alphabet in order
+pub fn keyed_alphabet(keyword: &[u8]) -> Vec<AsciiUppercaseByte> {
+    let alphabet = Alphabet::standard().keyed(keyword);
+
+    (0..alphabet.len())
+        .map(|i| AsciiUppercaseByte::try_from(alphabet.character(alphabet.symbol(i)) as u8).unwrap())
+        .collect()
+}
+
+/// The four ciphers of the Quagmire family, which differ in which alphabets are keyed
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Kind {
+    /// The plain alphabet is keyed, and the indicator is read under A
+    I,
+    /// The cipher alphabet is keyed, and the indicator is read under A
+    II,
+    /// Both alphabets are keyed with the same keyword, and the indicator is read under the
+    /// first plain letter
+    III,
+    /// The alphabets are keyed with two keywords, and the indicator is read under the first
+    /// plain letter
+    IV,
+}
+
+/// Any cipher of the Quagmire family: a plain alphabet, a cipher alphabet, and an indicator
+/// keyword that selects the slides
+///
+/// The Quagmire I to IV ciphers build their alphabets from keywords (see [`Kind`] and
+/// [`Quagmire::parse`]).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Quagmire {
+    plain: Vec<AsciiUppercaseByte>,
+    cipher: Vec<AsciiUppercaseByte>,
+    indicator: Vec<AsciiUppercaseByte>,
+    // The index in the plain alphabet of the letter the indicator is read under
+    position: usize,
+}
+
+impl Quagmire {
+    /// Creates the cipher from its two alphabets, its indicator keyword and the plain letter the
+    /// indicator is read under (`None` for the first letter of the plain alphabet)
+    ///
+    /// Returns an error if an alphabet is not made of the 26 letters.
+    pub fn new(plain: Vec<AsciiUppercaseByte>, cipher: Vec<AsciiUppercaseByte>, indicator: Vec<AsciiUppercaseByte>, position: Option<AsciiUppercaseByte>) -> Result<Quagmire, Error> {
+        for alphabet in [&plain, &cipher].iter() {
+            if alphabet.len() != 26 || (b'A'..=b'Z').any(|letter| !alphabet.iter().any(|l| l.get_byte() == letter)) {
+                return Err(Error::KeyError("A Quagmire alphabet must hold each of the 26 letters once".to_string()));
+            }
+        }
+
+        let position = position.map_or(0, |letter| plain.iter().position(|&l| l == letter).unwrap());
+
+        Ok(Quagmire { plain, cipher, indicator, position })
+    }
+
+    /// Creates a cipher of the family `kind` from a key written as a string
+    ///
+    /// The key is the indicator keyword, with the settings `keyword` (mixing the plain alphabet,
+    /// or the cipher alphabet of the Quagmire II, or both of the Quagmire III), `cipher-keyword`
+    /// (mixing the cipher alphabet of the Quagmire IV) and `position` (the plain letter the
+    /// indicator is read under).
+    pub fn parse(kind: Kind, key: &str) -> Result<Quagmire, Error> {
+        let key = KeyString::parse(key)?;
+
+        let keyword = keyed_alphabet(setting(&key, "keyword"));
+        let straight = keyed_alphabet(b"");
+
+        match kind {
+            Kind::I => build(&key, keyword, straight, Some(b'A')),
+            Kind::II => build(&key, straight, keyword, Some(b'A')),
+            Kind::III => build(&key, keyword.clone(), keyword, None),
+            Kind::IV => build(&key, keyword, keyed_alphabet(setting(&key, "cipher-keyword")), None),
+        }
+    }
+
+    /// The plain alphabet
+    pub fn plain_alphabet(&self) -> &[AsciiUppercaseByte] {
+        &self.plain
+    }
+
+    /// The cipher alphabet
+    pub fn cipher_alphabet(&self) -> &[AsciiUppercaseByte] {
+        &self.cipher
+    }
+
+    // How far the cipher alphabet is slid against the plain alphabet under the indicator `letter`
+    fn slide(&self, letter: usize) -> usize {
+        (self.index(&self.cipher, letter) + 26 - self.position) % 26
+    }
+
+    fn index(&self, alphabet: &[AsciiUppercaseByte], letter: usize) -> usize {
+        alphabet.iter().position(|l| l.get_byte() - b'A' == letter as u8).unwrap()
+    }
+
+    fn encipher_letter(&self, letter: usize, indicator: usize) -> usize {
+        (self.cipher[(self.index(&self.plain, letter) + self.slide(indicator)) % 26].get_byte() - b'A') as usize
+    }
+
+    fn decipher_letter(&self, letter: usize, indicator: usize) -> usize {
+        (self.plain[(self.index(&self.cipher, letter) + 26 - self.slide(indicator)) % 26].get_byte() - b'A') as usize
+    }
+
+    // Runs `operation` over the letters of `text` and the repeated indicator
+    fn combine<F>(&self, text: &[u8], operation: F) -> Result<String, Error>
+        where F: Fn(usize, usize) -> usize {
+        let text = common::sanitize_text(text)?;
+
+        // If no indicator was passed, we don't encrypt
+        if self.indicator.is_empty() {
+            return Ok(common::format_output(text));
+        }
+
+        let indicator = vigenere_standard::repeat_key(self.indicator.clone(), text.len());
+
+        Ok(common::format_output(text.iter()
+            .zip(indicator)
+            .map(|(&letter, indicator)| AsciiUppercaseByte::from_index(operation(letter.index(), indicator.index())))
+            .collect::<Vec<AsciiUppercaseByte>>()))
+    }
+
+    fn passthrough<F>(&self, text: &[u8], advance: KeyAdvance, operation: F) -> String
+        where F: Fn(usize, usize) -> usize {
+        let alphabet = Alphabet::standard();
+
+        vigenere_standard::passthrough(&common::to_symbols(&self.indicator), text, advance, |letters: &[Symbol], indicator: &[Symbol]| {
+            letters.iter()
+                .zip(indicator)
+                .map(|(letter, indicator)| alphabet.symbol(operation(letter.index(), indicator.index())))
+                .collect()
+        })
+    }
+}
+
+impl Cipher for Quagmire {
+    fn encipher(&self, plain_text: &[u8]) -> Result<String, Error> {
+        self.combine(plain_text, |letter, indicator| self.encipher_letter(letter, indicator))
+    }
+
+    fn decipher(&self, cipher_text: &[u8]) -> Result<String, Error> {
+        self.combine(cipher_text, |letter, indicator| self.decipher_letter(letter, indicator))
+    }
+
+    fn encipher_passthrough(&self, plain_text: &[u8], advance: KeyAdvance) -> Result<String, Error> {
+        Ok(self.passthrough(plain_text, advance, |letter, indicator| self.encipher_letter(letter, indicator)))
+    }
+
+    fn decipher_passthrough(&self, cipher_text: &[u8], advance: KeyAdvance) -> Result<String, Error> {
+        Ok(self.passthrough(cipher_text, advance, |letter, indicator| self.decipher_letter(letter, indicator)))
+    }
+}
+
+fn setting<'a>(key: &KeyString<'a>, name: &str) -> &'a [u8] {
+    key.setting(name).unwrap_or("").as_bytes()
+}
+
+fn build(key: &KeyString, plain: Vec<AsciiUppercaseByte>, cipher: Vec<AsciiUppercaseByte>, default_position: Option<u8>) -> Result<Quagmire, Error> {
+    let position = match key.setting("position") {
+        Some(position) => match common::sanitize_text(position.as_bytes())?[..] {
+            [letter] => Some(letter),
+            _ => return Err(Error::KeyError(format!("The position should be a single letter, not '{}'", position))),
+        },
+        None => default_position.map(|letter| AsciiUppercaseByte::try_from(letter).unwrap()),
+    };
+
+    Quagmire::new(plain, cipher, common::sanitize_text(key.main.as_bytes())?, position)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::cipher::Cipher;
+    use crate::common;
+    use crate::passthrough::KeyAdvance;
+    use crate::quagmire::{self, Kind, Quagmire};
+    use crate::vigenere_standard;
+    use quickcheck::quickcheck;
+
+    #[test]
+    fn test_kryptos() {
+        // The first part of the Kryptos sculpture
+        let cipher_text = b"EMUFPHZLRFAXYUSDJKZLDKRNSHGNFIVJYQTQUXQBQVYUVLLTREVJYQTMKYRDMFD";
+
+        let plain_text = quagmire::decipher(b"KRYPTOS", b"PALIMPSEST", cipher_text).unwrap();
+
+        assert_eq!(plain_text.split_whitespace().collect::<String>(), "BETWEENSUBTLESHADINGANDTHEABSENCEOFLIGHTLIESTHENUANCEOFIQLUSION");
+
+        assert_eq!(Quagmire::parse(Kind::III, "PALIMPSEST;keyword=KRYPTOS").unwrap().encipher(b"Between subtle shading").unwrap(), "EMUFP HZLRF AXYUS DJKZL");
+    }
+
+    #[test]
+    fn test_straight_alphabets_are_vigenere() {
+        let plain_text = b"The indicator selects the slides";
+        let expected = vigenere_standard::encipher(b"FLOWER", plain_text).unwrap();
+
+        assert_eq!(Quagmire::parse(Kind::I, "FLOWER").unwrap().encipher(plain_text).unwrap(), expected);
+        assert_eq!(Quagmire::parse(Kind::II, "FLOWER").unwrap().encipher(plain_text).unwrap(), expected);
+        assert_eq!(Quagmire::parse(Kind::III, "FLOWER").unwrap().encipher(plain_text).unwrap(), expected);
+        assert_eq!(Quagmire::parse(Kind::IV, "FLOWER").unwrap().encipher(plain_text).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_alphabets() {
+        let quagmire = Quagmire::parse(Kind::IV, "GOLF;keyword=SPRING;cipher-keyword=FEVER;position=N").unwrap();
+
+        assert_eq!(quagmire.plain_alphabet().iter().map(|&l| char::from(l)).collect::<String>(), "SPRINGABCDEFHJKLMOQTUVWXYZ");
+        assert_eq!(quagmire.cipher_alphabet().iter().map(|&l| char::from(l)).collect::<String>(), "FEVRABCDGHIJKLMNOPQSTUWXYZ");
+
+        // Under the indicator letter G, the plain N enciphers to G
+        assert_eq!(quagmire.encipher(b"N").unwrap(), "G");
+        assert!(Quagmire::parse(Kind::I, "GOLF;position=NO").is_err());
+    }
+
+    #[test]
+    fn test_passthrough() {
+        let quagmire = Quagmire::parse(Kind::III, "PALIMPSEST;keyword=KRYPTOS").unwrap();
+
+        let enciphered = quagmire.encipher_passthrough(b"Between subtle shading.", KeyAdvance::LettersOnly).unwrap();
+
+        assert_eq!(enciphered, "Emufphz lrfaxy usdjkzl.");
+    }
+
+    quickcheck! {
+        fn quagmire_round_trip(keyword: Vec<u8>, cipher_keyword: Vec<u8>, indicator: Vec<u8>, plain_text: Vec<u8>) -> bool {
+            let key = format!("{};keyword={};cipher-keyword={}",
+                String::from_utf8_lossy(&common::sanitize_text(&indicator).unwrap().into_iter().map(|l| l.get_byte()).collect::<Vec<u8>>()),
+                String::from_utf8_lossy(&common::sanitize_text(&keyword).unwrap().into_iter().map(|l| l.get_byte()).collect::<Vec<u8>>()),
+                String::from_utf8_lossy(&common::sanitize_text(&cipher_keyword).unwrap().into_iter().map(|l| l.get_byte()).collect::<Vec<u8>>()));
+
+            let quagmire = Quagmire::parse(Kind::IV, &key).unwrap();
+
+            let enciphered = quagmire.encipher(&plain_text).unwrap();
+
+            quagmire.decipher(enciphered.as_bytes()).unwrap() == common::format_output(common::sanitize_text(&plain_text).unwrap())
+        }
+    }
+}
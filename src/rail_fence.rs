@@ -0,0 +1,164 @@
+//! # Implementation of the rail fence cipher
+//!
+//! The message is written in a zigzag across a number of _rails_, going down to the bottom
+//! rail and back up to the top one, over and over. The cipher text is the top rail, followed
+//! by the second rail, and so on.
+//!
+//! # Example:
+//!
+//! Message to encipher: "WE ARE DISCOVERED. RUN AT ONCE."
+//!
+//! Rails: 3
+//!
+//! ```text
+//! W . . . E . . . C . . . R . . . U . . . O . . .
+//! . E . R . D . S . O . E . E . R . N . T . N . E
+//! . . A . . . I . . . V . . . D . . . A . . . C .
+//! ```
+//!
+//! Cipher text: WECRU OERDS OEERN TNEAI VDAC
+//!
+//! The _offset_ starts the zigzag part of the way through its first cycle. With an offset of 1,
+//! the first letter is written on the second rail, going down. A cycle is `2 * (rails - 1)`
+//! letters long, so larger offsets wrap around.
+
+use crate::cipher::{Cipher, KeyString, KeyedCipher};
+use crate::common::{self, format_output};
+use crate::errors::Error;
+
+/// Enciphers `plain_text` on `rails` rails, starting `offset` letters into the zigzag
+pub fn encipher(rails: usize, offset: usize, plain_text: &[u8]) -> Result<String, Error> {
+    RailFence::with_key((rails, offset))?.encipher(plain_text)
+}
+
+/// Deciphers `cipher_text` written on `rails` rails, starting `offset` letters into the zigzag
+pub fn decipher(rails: usize, offset: usize, cipher_text: &[u8]) -> Result<String, Error> {
+    RailFence::with_key((rails, offset))?.decipher(cipher_text)
+}
+
+/// The rail fence cipher, for use through the [`Cipher`] trait
+///
+/// The key is the number of rails, and the offset. It is written `RAILS;offset=OFFSET`.
+pub struct RailFence {
+    rails: usize,
+    offset: usize,
+}
+
+impl KeyedCipher for RailFence {
+    type Key = (usize, usize);
+
+    fn parse_key(key: &str) -> Result<Self::Key, Error> {
+        let key = KeyString::parse(key)?;
+
+        let rails = key.main.parse::<usize>()
+            .map_err(|_| Error::KeyError(format!("The number of rails should be a number, not '{}'", key.main)))?;
+
+        Ok((rails, key.parsed_setting("offset", 0)?))
+    }
+
+    fn with_key((rails, offset): Self::Key) -> Result<Self, Error> {
+        let (rails, offset) = check_rails(rails, offset)?;
+
+        Ok(RailFence { rails, offset })
+    }
+}
+
+impl Cipher for RailFence {
+    fn encipher(&self, plain_text: &[u8]) -> Result<String, Error> {
+        let plain_text = common::sanitize_text(plain_text)?;
+
+        let order = reading_order(self.rails, self.offset, plain_text.len());
+
+        Ok(format_output(common::permute(&plain_text, &order)))
+    }
+
+    fn decipher(&self, cipher_text: &[u8]) -> Result<String, Error> {
+        let cipher_text = common::sanitize_text(cipher_text)?;
+
+        let order = reading_order(self.rails, self.offset, cipher_text.len());
+
+        Ok(format_output(common::unpermute(&cipher_text, &order)))
+    }
+}
+
+/// Length of one down-and-up cycle of the zigzag on `rails` rails
+pub fn cycle_length(rails: usize) -> usize {
+    (2 * rails).saturating_sub(2).max(1)
+}
+
+fn check_rails(rails: usize, offset: usize) -> Result<(usize, usize), Error> {
+    if rails == 0 {
+        return Err(Error::KeyError("The rail fence needs at least one rail".to_string()));
+    }
+
+    Ok((rails, offset % cycle_length(rails)))
+}
+
+// For every letter of the cipher text, its position in the plain text
+fn reading_order(rails: usize, offset: usize, length: usize) -> Vec<usize> {
+    let cycle = cycle_length(rails);
+
+    let rail = |i: usize| {
+        let position = (i + offset) % cycle;
+
+        if position < rails { position } else { cycle - position }
+    };
+
+    let mut order = (0..length).collect::<Vec<usize>>();
+
+    // A stable sort keeps the letters of each rail in order
+    order.sort_by_key(|&i| rail(i));
+
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::common;
+    use crate::rail_fence::{encipher, decipher};
+    use quickcheck::quickcheck;
+
+    #[test]
+    fn test_rail_fence() {
+        let enciphered = encipher(3, 0, b"WE ARE DISCOVERED. RUN AT ONCE.").unwrap();
+
+        assert_eq!(enciphered, "WECRU OERDS OEERN TNEAI VDAC");
+
+        let deciphered = decipher(3, 0, b"WECRU OERDS OEERN TNEAI VDAC").unwrap();
+
+        assert_eq!(deciphered, "WEARE DISCO VERED RUNAT ONCE");
+    }
+
+    #[test]
+    fn test_offset() {
+        // . . . . C . . . . . W .
+        // . . . A . K . . . A . N
+        // A . T . . . A . D . . .
+        // . T . . . . . T . . . .
+        let enciphered = encipher(4, 2, b"ATTACK AT DAWN").unwrap();
+
+        assert_eq!(enciphered, "CWAKA NATAD TT");
+
+        assert_eq!(decipher(4, 2, enciphered.as_bytes()).unwrap(), "ATTAC KATDA WN");
+
+        // Offsets wrap around the cycle
+        assert_eq!(encipher(3, 4, b"WE ARE DISCOVERED").unwrap(), encipher(3, 0, b"WE ARE DISCOVERED").unwrap());
+    }
+
+    #[test]
+    fn test_invalid_rails() {
+        assert!(encipher(0, 0, b"ATTACK AT DAWN").is_err());
+        assert_eq!(encipher(1, 0, b"ATTACK AT DAWN").unwrap(), "ATTAC KATDA WN");
+    }
+
+    quickcheck! {
+        fn rail_fence_round_trip(plain_text: Vec<u8>, rails: u8, offset: u8) -> bool {
+            let rails = rails as usize % 20 + 1;
+
+            let enciphered = encipher(rails, offset as usize, &plain_text).unwrap();
+
+            decipher(rails, offset as usize, enciphered.as_bytes()).unwrap()
+                == common::format_output(common::sanitize_text(&plain_text).unwrap())
+        }
+    }
+}
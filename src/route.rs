@@ -0,0 +1,238 @@
+//! # Implementation of route transposition
+//!
+//! The message is written into a rectangle, row by row, and read out along a _route_ through
+//! the rectangle. The key is the number of columns and the route.
+//!
+//! # Example:
+//!
+//! Message to encipher: "WE ARE DISCOVERED. FLEE AT ONCE"
+//!
+//! Columns: 6
+//!
+//! ```text
+//! W  E  A  R  E  D
+//! I  S  C  O  V  E
+//! R  E  D  F  L  E
+//! E  A  T  O  N  C
+//! E
+//! ```
+//!
+//! Read out along a clockwise spiral, starting in the top left corner:
+//!
+//! Cipher text: WEARE DEECE ERISC OVLNO TAEDF
+//!
+//! The last row is usually incomplete. Routes simply skip its empty cells.
+
+use crate::cipher::{Cipher, KeyString, KeyedCipher};
+use crate::common::{self, format_output};
+use crate::errors::Error;
+
+/// A way through the rectangle
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Route {
+    /// Clockwise around the edge, then inwards, starting in the top left corner
+    Spiral,
+    /// Along the rows, alternately left to right and right to left
+    Boustrophedon,
+    /// Along the diagonals that go down and to the left, starting in the top left corner
+    Diagonal,
+    /// Along the columns, alternately down and up
+    Snake,
+}
+
+/// Every route, in the order they are tried when the route is unknown
+pub const ROUTES: [Route; 4] = [Route::Spiral, Route::Boustrophedon, Route::Diagonal, Route::Snake];
+
+impl Route {
+    /// The name of the route, as written in keys
+    pub fn name(&self) -> &'static str {
+        match self {
+            Route::Spiral => "spiral",
+            Route::Boustrophedon => "boustrophedon",
+            Route::Diagonal => "diagonal",
+            Route::Snake => "snake",
+        }
+    }
+
+    /// Finds the route called `name`
+    pub fn from_name(name: &str) -> Result<Route, Error> {
+        ROUTES.iter()
+            .copied()
+            .find(|route| route.name() == name)
+            .ok_or_else(|| Error::KeyError(format!("Unknown route '{}'", name)))
+    }
+}
+
+/// Writes `plain_text` into a rectangle `columns` wide, and reads it out along `route`
+pub fn encipher(columns: usize, route: Route, plain_text: &[u8]) -> Result<String, Error> {
+    RouteTransposition::with_key((columns, route))?.encipher(plain_text)
+}
+
+/// Reverses [`encipher`]
+pub fn decipher(columns: usize, route: Route, cipher_text: &[u8]) -> Result<String, Error> {
+    RouteTransposition::with_key((columns, route))?.decipher(cipher_text)
+}
+
+/// Route transposition, for use through the [`Cipher`] trait
+///
+/// The key is the number of columns and the route. It is written `COLUMNS;path=ROUTE`.
+pub struct RouteTransposition {
+    columns: usize,
+    route: Route,
+}
+
+impl KeyedCipher for RouteTransposition {
+    type Key = (usize, Route);
+
+    fn parse_key(key: &str) -> Result<Self::Key, Error> {
+        let key = KeyString::parse(key)?;
+
+        let columns = key.main.parse::<usize>()
+            .map_err(|_| Error::KeyError(format!("The number of columns should be a number, not '{}'", key.main)))?;
+
+        let route = Route::from_name(key.setting("path").unwrap_or("spiral"))?;
+
+        Ok((columns, route))
+    }
+
+    fn with_key((columns, route): Self::Key) -> Result<Self, Error> {
+        Ok(RouteTransposition { columns: check_columns(columns)?, route })
+    }
+}
+
+impl Cipher for RouteTransposition {
+    fn encipher(&self, plain_text: &[u8]) -> Result<String, Error> {
+        let plain_text = common::sanitize_text(plain_text)?;
+
+        let order = reading_order(self.columns, self.route, plain_text.len());
+
+        Ok(format_output(common::permute(&plain_text, &order)))
+    }
+
+    fn decipher(&self, cipher_text: &[u8]) -> Result<String, Error> {
+        let cipher_text = common::sanitize_text(cipher_text)?;
+
+        let order = reading_order(self.columns, self.route, cipher_text.len());
+
+        Ok(format_output(common::unpermute(&cipher_text, &order)))
+    }
+}
+
+fn check_columns(columns: usize) -> Result<usize, Error> {
+    if columns == 0 {
+        return Err(Error::KeyError("The rectangle needs at least one column".to_string()));
+    }
+
+    Ok(columns)
+}
+
+// For every letter of the cipher text, its position in the plain text
+fn reading_order(columns: usize, route: Route, length: usize) -> Vec<usize> {
+    let rows = length.div_ceil(columns);
+
+    let cells: Vec<(usize, usize)> = match route {
+        Route::Spiral => spiral(rows, columns),
+        Route::Boustrophedon => {
+            (0..rows)
+                .flat_map(|row| {
+                    let cells = (0..columns).map(move |column| (row, column));
+
+                    if row % 2 == 0 { cells.collect::<Vec<_>>() } else { cells.rev().collect() }
+                })
+                .collect()
+        },
+        Route::Diagonal => {
+            (0..rows + columns)
+                .flat_map(|diagonal| {
+                    (0..rows).filter_map(move |row| {
+                        diagonal.checked_sub(row).filter(|&column| column < columns).map(|column| (row, column))
+                    })
+                })
+                .collect()
+        },
+        Route::Snake => {
+            (0..columns)
+                .flat_map(|column| {
+                    let cells = (0..rows).map(move |row| (row, column));
+
+                    if column % 2 == 0 { cells.collect::<Vec<_>>() } else { cells.rev().collect() }
+                })
+                .collect()
+        },
+    };
+
+    // The text was written in by rows, so a cell's position in the plain text is its row-major index
+    cells.into_iter()
+        .map(|(row, column)| row * columns + column)
+        .filter(|&i| i < length)
+        .collect()
+}
+
+// The cells of a `rows` by `columns` rectangle, clockwise from the outside in
+fn spiral(rows: usize, columns: usize) -> Vec<(usize, usize)> {
+    let mut cells = Vec::with_capacity(rows * columns);
+
+    let (mut top, mut left) = (0, 0);
+    let (mut bottom, mut right) = (rows, columns);
+
+    while top < bottom && left < right {
+        cells.extend((left..right).map(|column| (top, column)));
+        cells.extend((top + 1..bottom).map(|row| (row, right - 1)));
+
+        if top + 1 < bottom {
+            cells.extend((left..right - 1).rev().map(|column| (bottom - 1, column)));
+        }
+
+        if left + 1 < right {
+            cells.extend((top + 1..bottom - 1).rev().map(|row| (row, left)));
+        }
+
+        top += 1;
+        left += 1;
+        bottom -= 1;
+        right -= 1;
+    }
+
+    cells
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::common;
+    use crate::route::{encipher, decipher, Route, ROUTES};
+    use quickcheck::quickcheck;
+
+    #[test]
+    fn test_spiral() {
+        let enciphered = encipher(6, Route::Spiral, b"WE ARE DISCOVERED. FLEE AT ONCE").unwrap();
+
+        assert_eq!(enciphered, "WEARE DEECE ERISC OVLNO TAEDF");
+
+        assert_eq!(
+            decipher(6, Route::Spiral, enciphered.as_bytes()).unwrap(),
+            "WEARE DISCO VERED FLEEA TONCE"
+        );
+    }
+
+    #[test]
+    fn test_routes() {
+        // A T T A
+        // C K A T
+        // D A W N
+        assert_eq!(encipher(4, Route::Boustrophedon, b"ATTACK AT DAWN").unwrap(), "ATTAT AKCDA WN");
+        assert_eq!(encipher(4, Route::Diagonal, b"ATTACK AT DAWN").unwrap(), "ATCTK DAAAT WN");
+        assert_eq!(encipher(4, Route::Snake, b"ATTACK AT DAWN").unwrap(), "ACDAK TTAWN TA");
+    }
+
+    quickcheck! {
+        fn route_round_trip(plain_text: Vec<u8>, columns: u8, route: u8) -> bool {
+            let columns = columns as usize % 20 + 1;
+            let route = ROUTES[route as usize % ROUTES.len()];
+
+            let enciphered = encipher(columns, route, &plain_text).unwrap();
+
+            decipher(columns, route, enciphered.as_bytes()).unwrap()
+                == common::format_output(common::sanitize_text(&plain_text).unwrap())
+        }
+    }
+}
@@ -0,0 +1,203 @@
+//! # Implementation of the running key cipher
+//!
+//! The running key cipher is the Vigenere cipher with a key as long as the message, so that it
+//! never repeats. The key is usually a passage of a book both parties own, starting at an agreed
+//! page and line (the _offset_). If _P_ is the plain text, _C_ the cipher text and _K_ the key
+//! text, enciphering is:
+//!
+//! `C = P + K`
+//!
+//! And deciphering is:
+//!
+//! `P = C - K`
+//!
+//! Unlike with [`crate::vigenere_autokey`], the key does not depend on the message. With no
+//! period to find, the Kasiski and index of coincidence attacks fail. But the key is itself
+//! English, and that is enough to break it (see [`crate::crack::running_key`]).
+//!
+//! # Example:
+//!
+//! Key text: IT IS A TRUTH UNIVERSALLY ACKNOWLEDGED (offset 5)
+//!
+//! Plain text: ATTACK AT DAWN
+//!
+//! Cipher text: TKNTJ ENBYE NF
+
+use crate::alphabet::Alphabet;
+use crate::cipher::{Cipher, KeyString, KeyedCipher};
+use crate::common::{self, AsciiUppercaseByte};
+use crate::errors::Error;
+use crate::passthrough::{KeyAdvance, MaskedText};
+use crate::vigenere_standard::{self, add_bytes, subtract_bytes};
+use std::fs;
+
+/// Encipher `plain_text` with the running key cipher, the key being the letters of `key_text`
+/// from the `offset`th on (counting from 0)
+pub fn encipher(key_text: &[u8], offset: usize, plain_text: &[u8]) -> Result<String, Error> {
+    RunningKey::with_key(key_stream(key_text, offset)?)?.encipher(plain_text)
+}
+
+/// Decipher `cipher_text` with the running key cipher, the key being the letters of `key_text`
+/// from the `offset`th on (counting from 0)
+pub fn decipher(key_text: &[u8], offset: usize, cipher_text: &[u8]) -> Result<String, Error> {
+    RunningKey::with_key(key_stream(key_text, offset)?)?.decipher(cipher_text)
+}
+
+/// The running key cipher, for use through the [`Cipher`] trait
+///
+/// The key is the key text itself, or the setting `key-file` naming a file to read it from, with
+/// the setting `offset` (the number of letters of the key text skipped, 0 by default).
+pub struct RunningKey {
+    key: Vec<AsciiUppercaseByte>,
+}
+
+impl KeyedCipher for RunningKey {
+    type Key = Vec<AsciiUppercaseByte>;
+
+    fn parse_key(key: &str) -> Result<Self::Key, Error> {
+        let key = KeyString::parse(key)?;
+
+        let offset = key.parsed_setting("offset", 0)?;
+
+        match key.setting("key-file") {
+            Some(path) => {
+                let key_text = fs::read(path)
+                    .map_err(|error| Error::KeyError(format!("Could not read the key file '{}': {}", path, error)))?;
+
+                key_stream(&key_text, offset)
+            },
+            None => key_stream(key.main.as_bytes(), offset),
+        }
+    }
+
+    fn with_key(key: Self::Key) -> Result<Self, Error> {
+        Ok(RunningKey { key })
+    }
+}
+
+impl RunningKey {
+    // Checks that the key covers the `length` letters (or characters) of a message
+    fn check_length(&self, length: usize) -> Result<(), String> {
+        if length > self.key.len() {
+            return Err(format!("The key text has {} letters left after the offset, but the message needs {}", self.key.len(), length));
+        }
+
+        Ok(())
+    }
+}
+
+impl Cipher for RunningKey {
+    fn encipher(&self, plain_text: &[u8]) -> Result<String, Error> {
+        let plain_text = common::sanitize_text(plain_text)?;
+
+        self.check_length(plain_text.len()).map_err(Error::EncipheringError)?;
+
+        Ok(common::format_output(add_bytes(&plain_text, &self.key[..plain_text.len()])))
+    }
+
+    fn decipher(&self, cipher_text: &[u8]) -> Result<String, Error> {
+        let cipher_text = common::sanitize_text(cipher_text)?;
+
+        self.check_length(cipher_text.len()).map_err(Error::DecipheringError)?;
+
+        Ok(common::format_output(subtract_bytes(&cipher_text, &self.key[..cipher_text.len()])))
+    }
+
+    fn encipher_passthrough(&self, plain_text: &[u8], advance: KeyAdvance) -> Result<String, Error> {
+        self.check_length(MaskedText::new(&Alphabet::standard(), plain_text).key_length(advance)).map_err(Error::EncipheringError)?;
+
+        Ok(vigenere_standard::passthrough(&common::to_symbols(&self.key), plain_text, advance, add_bytes))
+    }
+
+    fn decipher_passthrough(&self, cipher_text: &[u8], advance: KeyAdvance) -> Result<String, Error> {
+        self.check_length(MaskedText::new(&Alphabet::standard(), cipher_text).key_length(advance)).map_err(Error::DecipheringError)?;
+
+        Ok(vigenere_standard::passthrough(&common::to_symbols(&self.key), cipher_text, advance, subtract_bytes))
+    }
+}
+
+// The letters of `key_text`, from the `offset`th on
+fn key_stream(key_text: &[u8], offset: usize) -> Result<Vec<AsciiUppercaseByte>, Error> {
+    let letters = common::sanitize_text(key_text)?;
+
+    if offset > letters.len() {
+        return Err(Error::KeyError(format!("The offset {} is past the end of the key text, which has {} letters", offset, letters.len())));
+    }
+
+    Ok(letters[offset..].to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cipher::{Cipher, KeyedCipher};
+    use crate::common;
+    use crate::passthrough::KeyAdvance;
+    use crate::running_key::{self, RunningKey};
+    use crate::vigenere_standard;
+    use quickcheck::quickcheck;
+
+    const KEY_TEXT: &[u8] = b"It is a truth universally acknowledged";
+
+    #[test]
+    fn test_running_key() {
+        let enciphered = running_key::encipher(KEY_TEXT, 5, b"ATTACK AT DAWN").unwrap();
+
+        assert_eq!(enciphered, "TKNTJ ENBYE NF");
+
+        // The same as the Vigenere cipher under the part of the key text that was used
+        assert_eq!(enciphered, vigenere_standard::encipher(b"TRUTHUNIVERSAL", b"ATTACK AT DAWN").unwrap());
+
+        assert_eq!(running_key::decipher(KEY_TEXT, 5, enciphered.as_bytes()).unwrap(), "ATTAC KATDA WN");
+    }
+
+    #[test]
+    fn test_key_too_short() {
+        assert!(running_key::encipher(b"SHORT", 0, b"A LONGER MESSAGE").is_err());
+        assert!(running_key::encipher(KEY_TEXT, 30, b"ATTACK AT DAWN").is_err());
+        assert!(running_key::encipher(KEY_TEXT, 100, b"").is_err());
+
+        let cipher = RunningKey::from_key_str("TRUTH").unwrap();
+
+        assert!(cipher.encipher_passthrough(b"Attack", KeyAdvance::LettersOnly).is_err());
+        assert!(cipher.encipher_passthrough(b"At tac", KeyAdvance::EveryCharacter).is_err());
+        assert_eq!(cipher.encipher_passthrough(b"At-t!", KeyAdvance::EveryCharacter).unwrap(), "Tk-m!");
+    }
+
+    #[test]
+    fn test_key_file() {
+        let path = std::env::temp_dir().join(format!("codebreakers-running-key-{}.txt", std::process::id()));
+
+        std::fs::write(&path, KEY_TEXT).unwrap();
+
+        let cipher = RunningKey::from_key_str(&format!(";key-file={};offset=5", path.display())).unwrap();
+
+        assert_eq!(cipher.encipher(b"ATTACK AT DAWN").unwrap(), "TKNTJ ENBYE NF");
+
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(RunningKey::from_key_str(&format!(";key-file={}", path.display())).is_err());
+    }
+
+    #[test]
+    fn test_passthrough() {
+        let cipher = RunningKey::from_key_str(&format!("{};offset=5", String::from_utf8_lossy(KEY_TEXT))).unwrap();
+
+        let enciphered = cipher.encipher_passthrough(b"Attack at dawn!", KeyAdvance::LettersOnly).unwrap();
+
+        assert_eq!(enciphered, "Tkntje nb yenf!");
+        assert_eq!(cipher.decipher_passthrough(enciphered.as_bytes(), KeyAdvance::LettersOnly).unwrap(), "Attack at dawn!");
+    }
+
+    quickcheck! {
+        fn running_key_round_trip(key_text: Vec<u8>, plain_text: Vec<u8>) -> bool {
+            // Make sure the key is long enough
+            let mut key_text = common::sanitize_text(&key_text).unwrap().into_iter().map(|l| l.get_byte()).collect::<Vec<u8>>();
+            key_text.extend(plain_text.iter().map(|&b| b'A' + b % 26));
+
+            let enciphered = running_key::encipher(&key_text, 0, &plain_text).unwrap();
+            let deciphered = running_key::decipher(&key_text, 0, enciphered.as_bytes()).unwrap();
+
+            deciphered == common::format_output(common::sanitize_text(&plain_text).unwrap())
+        }
+    }
+}
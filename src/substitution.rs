@@ -0,0 +1,297 @@
+//! # Implementation of simple monoalphabetic substitution
+//!
+//! Every letter of the plain text is replaced by the letter below it in a _cipher alphabet_,
+//! which is any re-ordering of A-Z:
+//!
+//! ```text
+//! Plain:  ABCDEFGHIJKLMNOPQRSTUVWXYZ
+//! Cipher: ZEBRASCDFGHIJKLMNOPQTUVWXY
+//! ```
+//!
+//! # Keyword-mixed alphabets
+//!
+//! A random cipher alphabet is hard to remember, so they are usually built from a keyword.
+//! The keyword is written out with repeated letters dropped, followed by the rest of the alphabet.
+//! The keyword ZEBRAS gives the cipher alphabet above.
+//!
+//! A _column-mixed_ alphabet goes one step further: the keyword-mixed alphabet is written in rows
+//! under the keyword, and the columns are read off in the alphabetical order of the keyword letters.
+//!
+//! ```text
+//! Z E B R A
+//! ---------
+//! Z E B R A
+//! C D F G H
+//! I J K L M
+//! N O P Q S
+//! T U V W X
+//! Y
+//! ```
+//!
+//! gives AHMSX BFKPV EDJOU RGLQW ZCINTY
+//!
+//! The American Cryptogram Association names three ways of using a mixed alphabet:
+//!
+//! - K1: the plain alphabet is mixed and the cipher alphabet is straight
+//! - K2: the plain alphabet is straight and the cipher alphabet is mixed
+//! - K3: both alphabets are the same mixed alphabet, with the cipher alphabet shifted along
+
+use crate::alphabet::{Alphabet, Symbol};
+use crate::cipher::{Cipher, KeyString, KeyedCipher};
+use crate::column_transposition;
+use crate::common::{self, AsciiUppercaseByte};
+use crate::errors::Error;
+use crate::passthrough::{KeyAdvance, MaskedText};
+use std::collections::HashSet;
+use std::convert::TryFrom;
+
+/// How a keyword mixes the alphabet
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Mixing {
+    /// The keyword followed by the rest of the alphabet
+    Keyword,
+    /// The keyword-mixed alphabet, transposed by columns under the keyword
+    Columnar,
+}
+
+/// Where the mixed alphabet is used
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Arrangement {
+    /// Mixed plain alphabet, straight cipher alphabet
+    K1,
+    /// Straight plain alphabet, mixed cipher alphabet
+    K2,
+    /// Mixed plain alphabet, and the same mixed alphabet shifted along by some amount as the cipher alphabet
+    K3(usize),
+}
+
+/// Enciphers `plain_text` with the cipher alphabet `cipher_alphabet`
+pub fn encipher(cipher_alphabet: &[u8], plain_text: &[u8]) -> Result<String, Error> {
+    Substitution::from_key_str(&String::from_utf8_lossy(cipher_alphabet))?.encipher(plain_text)
+}
+
+/// Deciphers `cipher_text` with the cipher alphabet `cipher_alphabet`
+pub fn decipher(cipher_alphabet: &[u8], cipher_text: &[u8]) -> Result<String, Error> {
+    Substitution::from_key_str(&String::from_utf8_lossy(cipher_alphabet))?.decipher(cipher_text)
+}
+
+/// Creates a mixed alphabet out of `keyword`
+pub fn mixed_alphabet(keyword: &[u8], mixing: Mixing) -> Result<Vec<AsciiUppercaseByte>, Error> {
+    let keyed = Alphabet::standard().keyed(keyword);
+
+    let alphabet = (0..keyed.len())
+                    .map(|i| AsciiUppercaseByte::try_from(keyed.character(keyed.symbol(i)) as u8))
+                    .collect::<Result<Vec<AsciiUppercaseByte>, Error>>()?;
+
+    // The keyword, repeats dropped, starts the keyed alphabet
+    let keyword_len = common::sanitize_text(keyword)?.into_iter().collect::<HashSet<AsciiUppercaseByte>>().len();
+
+    match mixing {
+        Mixing::Keyword => Ok(alphabet),
+        Mixing::Columnar if keyword_len == 0 => Ok(alphabet),
+        Mixing::Columnar => {
+            let key = column_transposition::create_key(&alphabet[..keyword_len]);
+            Ok(column_transposition::transpose(&key, &alphabet))
+        },
+    }
+}
+
+/// Creates the cipher alphabet for the keyword `keyword`
+///
+/// The result lists the cipher letter for each plain letter from A to Z
+pub fn keyword_cipher_alphabet(keyword: &[u8], mixing: Mixing, arrangement: Arrangement) -> Result<Vec<AsciiUppercaseByte>, Error> {
+    let mixed = mixed_alphabet(keyword, mixing)?;
+
+    let straight = common::sanitize_text(b"ABCDEFGHIJKLMNOPQRSTUVWXYZ")?;
+
+    let mut cipher_alphabet = straight.clone();
+
+    for i in 0..26 {
+        let (plain, cipher) = match arrangement {
+            Arrangement::K1 => (mixed[i], straight[i]),
+            Arrangement::K2 => (straight[i], mixed[i]),
+            Arrangement::K3(shift) => (mixed[i], mixed[(i + shift) % 26]),
+        };
+
+        cipher_alphabet[plain.index()] = cipher;
+    }
+
+    Ok(cipher_alphabet)
+}
+
+/// Simple substitution, for use through the [`Cipher`] trait
+///
+/// The key is the cipher alphabet. As a string, it is either the 26 letters of the cipher
+/// alphabet, or the settings `keyword`, `mixing` (`keyword` or `column`),
+/// `arrangement` (`k1`, `k2` or `k3`) and `shift` (for K3).
+pub struct Substitution {
+    cipher_alphabet: Vec<AsciiUppercaseByte>,
+    plain_alphabet: Vec<AsciiUppercaseByte>,
+}
+
+impl KeyedCipher for Substitution {
+    type Key = Vec<AsciiUppercaseByte>;
+
+    fn parse_key(key: &str) -> Result<Self::Key, Error> {
+        let key = KeyString::parse(key)?;
+
+        match key.setting("keyword") {
+            Some(keyword) => {
+                let mixing = match key.setting("mixing") {
+                    None | Some("keyword") => Mixing::Keyword,
+                    Some("column") => Mixing::Columnar,
+                    Some(other) => return Err(Error::KeyError(format!("Unknown mixing '{}'", other))),
+                };
+
+                let arrangement = match key.setting("arrangement") {
+                    Some("k1") => Arrangement::K1,
+                    None | Some("k2") => Arrangement::K2,
+                    Some("k3") => Arrangement::K3(key.parsed_setting("shift", 1)?),
+                    Some(other) => return Err(Error::KeyError(format!("Unknown arrangement '{}'", other))),
+                };
+
+                keyword_cipher_alphabet(keyword.as_bytes(), mixing, arrangement)
+            },
+            None => common::sanitize_text(key.main.as_bytes()),
+        }
+    }
+
+    fn with_key(cipher_alphabet: Self::Key) -> Result<Self, Error> {
+        let mut sorted = cipher_alphabet.clone();
+        sorted.sort_by_key(|letter| letter.get_byte());
+        sorted.dedup();
+
+        if cipher_alphabet.len() != 26 || sorted.len() != 26 {
+            return Err(Error::KeyError("A cipher alphabet must contain each letter A-Z exactly once".to_string()));
+        }
+
+        let mut plain_alphabet = cipher_alphabet.clone();
+
+        for (i, &cipher) in cipher_alphabet.iter().enumerate() {
+            plain_alphabet[cipher.index()] = AsciiUppercaseByte::from_index(i);
+        }
+
+        Ok(Substitution { cipher_alphabet, plain_alphabet })
+    }
+}
+
+impl Substitution {
+    /// Returns the cipher alphabet, listing the cipher letter for each plain letter from A to Z
+    pub fn cipher_alphabet(&self) -> &[AsciiUppercaseByte] {
+        &self.cipher_alphabet
+    }
+
+    fn substitute(alphabet: &[AsciiUppercaseByte], text: &[AsciiUppercaseByte]) -> Vec<AsciiUppercaseByte> {
+        text.iter().map(|&letter| alphabet[letter.index()]).collect()
+    }
+
+    fn substitute_passthrough(alphabet: &[AsciiUppercaseByte], text: &[u8]) -> String {
+        let standard = Alphabet::standard();
+
+        let text = MaskedText::new(&standard, text);
+
+        let substituted = text.letters()
+                            .into_iter()
+                            .map(|symbol| standard.symbol((alphabet[symbol.index()].get_byte() - b'A') as usize))
+                            .collect::<Vec<Symbol>>();
+
+        text.unmask(&standard, &substituted)
+    }
+}
+
+impl Cipher for Substitution {
+    fn encipher(&self, plain_text: &[u8]) -> Result<String, Error> {
+        let plain_text = common::sanitize_text(plain_text)?;
+
+        Ok(common::format_output(Substitution::substitute(&self.cipher_alphabet, &plain_text)))
+    }
+
+    fn decipher(&self, cipher_text: &[u8]) -> Result<String, Error> {
+        let cipher_text = common::sanitize_text(cipher_text)?;
+
+        Ok(common::format_output(Substitution::substitute(&self.plain_alphabet, &cipher_text)))
+    }
+
+    // A monoalphabetic cipher has no key to advance, so `advance` makes no difference
+    fn encipher_passthrough(&self, plain_text: &[u8], _advance: KeyAdvance) -> Result<String, Error> {
+        Ok(Substitution::substitute_passthrough(&self.cipher_alphabet, plain_text))
+    }
+
+    fn decipher_passthrough(&self, cipher_text: &[u8], _advance: KeyAdvance) -> Result<String, Error> {
+        Ok(Substitution::substitute_passthrough(&self.plain_alphabet, cipher_text))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::cipher::{Cipher, KeyedCipher};
+    use crate::common;
+    use crate::passthrough::KeyAdvance;
+    use crate::substitution::{self, Arrangement, Mixing, Substitution};
+    use quickcheck::quickcheck;
+
+    #[test]
+    fn test_mixed_alphabet() {
+        let alphabet = substitution::mixed_alphabet(b"ZEBRAS", Mixing::Keyword).unwrap();
+
+        assert_eq!(common::format_output(alphabet), "ZEBRA SCDFG HIJKL MNOPQ TUVWX \nY");
+
+        let alphabet = substitution::mixed_alphabet(b"ZEBRA", Mixing::Columnar).unwrap();
+
+        assert_eq!(common::format_output(alphabet), "AHMSX BFKPV EDJOU RGLQW ZCINT \nY");
+    }
+
+    #[test]
+    fn test_substitution() {
+        let cipher = Substitution::from_key_str(";keyword=ZEBRAS").unwrap();
+
+        let enciphered = cipher.encipher_passthrough(b"flee at once. we are discovered!", KeyAdvance::LettersOnly).unwrap();
+
+        assert_eq!(enciphered, "siaa zq lkba. va zoa rfpbluaoar!");
+
+        assert_eq!(
+            substitution::decipher(b"ZEBRASCDFGHIJKLMNOPQTUVWXY", b"SIAAZ QLKBA").unwrap(),
+            "FLEEA TONCE"
+        );
+    }
+
+    #[test]
+    fn test_arrangements() {
+        // K1: the mixed alphabet is the plain alphabet, so Z enciphers to A
+        let k1 = substitution::keyword_cipher_alphabet(b"ZEBRAS", Mixing::Keyword, Arrangement::K1).unwrap();
+
+        assert_eq!(common::format_output(k1), "ECGHB IJKLM NOPQR STDFU VWXYZ \nA");
+
+        // K3: each letter of the mixed alphabet enciphers to the one `shift` places after it
+        let k3 = substitution::keyword_cipher_alphabet(b"ZEBRAS", Mixing::Keyword, Arrangement::K3(2)).unwrap();
+
+        assert_eq!(k3[25].get_byte(), b'B');
+        assert_eq!(k3[24].get_byte(), b'E');
+    }
+
+    #[test]
+    fn test_invalid_cipher_alphabet() {
+        assert!(Substitution::from_key_str("ABC").is_err());
+        assert!(Substitution::with_key(common::sanitize_text(b"ABC").unwrap()).is_err());
+        assert!(Substitution::from_key_str("AACDEFGHIJKLMNOPQRSTUVWXYZ").is_err());
+        assert!(Substitution::from_key_str(";keyword=ZEBRAS;arrangement=k9").is_err());
+    }
+
+    quickcheck! {
+        fn deciphering_reverses_enciphering(keyword: Vec<u8>, shift: usize, plain_text: Vec<u8>) -> bool {
+            let mixings = [Mixing::Keyword, Mixing::Columnar];
+            let arrangements = [Arrangement::K1, Arrangement::K2, Arrangement::K3(shift % 25 + 1)];
+
+            mixings.iter().all(|&mixing| arrangements.iter().all(|&arrangement| {
+                let alphabet = substitution::keyword_cipher_alphabet(&keyword, mixing, arrangement).unwrap();
+                let alphabet = common::format_output(alphabet);
+
+                let enciphered = substitution::encipher(alphabet.as_bytes(), &plain_text).unwrap();
+                let deciphered = substitution::decipher(alphabet.as_bytes(), enciphered.as_bytes()).unwrap();
+
+                deciphered == common::format_output(common::sanitize_text(&plain_text).unwrap())
+            }))
+        }
+    }
+}
@@ -0,0 +1,392 @@
+//! # Polyalphabetic ciphers written as a tableau
+//!
+//! Most polyalphabetic ciphers of the 16th to 19th centuries come down to a table of cipher
+//! alphabets, one row per key letter or group of key letters. Every letter of the message is
+//! looked up in the plain alphabet along the top, and replaced by the letter under it in the row
+//! picked by the key letter over it. The key repeats as with the Vigenere cipher.
+//!
+//! A [`Tableau`] holds such a table as data, so a cipher is a matter of writing its rows down:
+//! the Vigenere, Beaufort, variant Beaufort and Porta tableaux are built in, and any other table
+//! can be given row by row ([`Tableau::parse`]).
+//!
+//! # Example:
+//!
+//! The first rows of the Vigenere tableau:
+//!
+//! ```text
+//!    A B C D E F G H I J K L M N O P Q R S T U V W X Y Z
+//! A  A B C D E F G H I J K L M N O P Q R S T U V W X Y Z
+//! B  B C D E F G H I J K L M N O P Q R S T U V W X Y Z A
+//! C  C D E F G H I J K L M N O P Q R S T U V W X Y Z A B
+//! ```
+//!
+//! A table whose every row swaps letters in pairs is _reciprocal_: enciphering and deciphering
+//! are the same operation ([`Tableau::is_reciprocal`]).
+
+use crate::alphabet::{Alphabet, Symbol};
+use crate::cipher::{Cipher, KeyString, KeyedCipher};
+use crate::common::{self, AsciiUppercaseByte};
+use crate::errors::Error;
+use crate::passthrough::KeyAdvance;
+use crate::vigenere_standard;
+use std::fmt;
+
+/// A row of a tableau: the key letters that pick it, and the cipher letter under every plain letter
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Row {
+    keys: Vec<AsciiUppercaseByte>,
+    cipher: [usize; 26],
+    plain: [usize; 26],
+}
+
+/// A table of cipher alphabets, each picked by one or more key letters
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tableau {
+    rows: Vec<Row>,
+    // The row picked by every key letter
+    row_of_key: [usize; 26],
+}
+
+impl Tableau {
+    /// Creates a tableau from its rows: the key letters picking a row, and the row's cipher
+    /// alphabet, written under the plain letters A to Z
+    ///
+    /// Returns an error if a cipher alphabet is not made of the 26 letters, or if a key letter
+    /// picks no row or more than one.
+    pub fn new(rows: Vec<(Vec<AsciiUppercaseByte>, Vec<AsciiUppercaseByte>)>) -> Result<Tableau, Error> {
+        let mut row_of_key = [None; 26];
+
+        let rows = rows.into_iter()
+            .enumerate()
+            .map(|(i, (keys, alphabet))| {
+                for &key in &keys {
+                    if row_of_key[key.index()].replace(i).is_some() {
+                        return Err(Error::KeyError(format!("The key letter {} picks more than one row of the tableau", char::from(key))));
+                    }
+                }
+
+                let mut cipher = [0; 26];
+                let mut plain = [None; 26];
+
+                if alphabet.len() != 26 {
+                    return Err(Error::KeyError(format!("A row of the tableau should have 26 letters, not {}", alphabet.len())));
+                }
+
+                for (letter, &substitute) in alphabet.iter().enumerate() {
+                    if plain[substitute.index()].replace(letter).is_some() {
+                        return Err(Error::KeyError(format!("The letter {} is in a row of the tableau twice", char::from(substitute))));
+                    }
+
+                    cipher[letter] = substitute.index();
+                }
+
+                Ok(Row { keys, cipher, plain: plain.map(Option::unwrap) })
+            })
+            .collect::<Result<Vec<Row>, Error>>()?;
+
+        match row_of_key.iter().position(Option::is_none) {
+            Some(key) => Err(Error::KeyError(format!("The key letter {} picks no row of the tableau", char::from(AsciiUppercaseByte::from_index(key))))),
+            None => Ok(Tableau { rows, row_of_key: row_of_key.map(Option::unwrap) }),
+        }
+    }
+
+    /// Reads a tableau written one row per line (or separated by commas): the key letters
+    /// picking the row, a colon, then the 26 letters of its cipher alphabet
+    ///
+    /// ```
+    /// use codebreakers::tableau::Tableau;
+    ///
+    /// let atbash = Tableau::parse("ABCDEFGHIJKLMNOPQRSTUVWXYZ: ZYXWVUTSRQPONMLKJIHGFEDCBA").unwrap();
+    ///
+    /// assert!(atbash.is_reciprocal());
+    /// ```
+    pub fn parse(text: &str) -> Result<Tableau, Error> {
+        let rows = text.split(['\n', ','])
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                let mut parts = line.splitn(2, ':');
+
+                match (parts.next(), parts.next()) {
+                    (Some(keys), Some(alphabet)) => Ok((common::sanitize_text(keys.as_bytes())?, common::sanitize_text(alphabet.as_bytes())?)),
+                    _ => Err(Error::KeyError(format!("A row of the tableau should look like KEYS:ALPHABET, not '{}'", line.trim()))),
+                }
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        Tableau::new(rows)
+    }
+
+    /// The Vigenere tableau: under the key letter K, the plain letter P enciphers as P + K
+    pub fn vigenere() -> Tableau {
+        Tableau::shifted(|plain, key| plain + key)
+    }
+
+    /// The Beaufort tableau: under the key letter K, the plain letter P enciphers as K - P
+    pub fn beaufort() -> Tableau {
+        Tableau::shifted(|plain, key| key + 26 - plain)
+    }
+
+    /// The variant Beaufort tableau: under the key letter K, the plain letter P enciphers as P - K
+    pub fn variant_beaufort() -> Tableau {
+        Tableau::shifted(|plain, key| plain + 26 - key)
+    }
+
+    /// Porta's tableau: 13 rows, each picked by two key letters (AB, CD...), that swap the first
+    /// half of the alphabet with the second half slid by one more place on every row
+    pub fn porta() -> Tableau {
+        let rows = (0..13)
+            .map(|row| {
+                let keys = vec![AsciiUppercaseByte::from_index(2 * row), AsciiUppercaseByte::from_index(2 * row + 1)];
+
+                let alphabet = (0..26)
+                    .map(|plain| if plain < 13 { 13 + (plain + row) % 13 } else { (plain + 26 - row) % 13 })
+                    .map(AsciiUppercaseByte::from_index)
+                    .collect();
+
+                (keys, alphabet)
+            })
+            .collect();
+
+        Tableau::new(rows).unwrap()
+    }
+
+    // A tableau of 26 rows, one per key letter, whose cipher letters are `combine(plain, key)` (modulo 26)
+    fn shifted<F>(combine: F) -> Tableau
+        where F: Fn(usize, usize) -> usize {
+        let rows = (0..26)
+            .map(|key| (vec![AsciiUppercaseByte::from_index(key)], (0..26).map(|plain| AsciiUppercaseByte::from_index(combine(plain, key) % 26)).collect()))
+            .collect();
+
+        Tableau::new(rows).unwrap()
+    }
+
+    /// Whether deciphering with the tableau is the same as enciphering
+    pub fn is_reciprocal(&self) -> bool {
+        self.rows.iter().all(|row| row.cipher == row.plain)
+    }
+
+    /// Enciphers the plain letter at `letter` (A being 0) under the key letter at `key`
+    pub fn encipher_letter(&self, key: usize, letter: usize) -> usize {
+        self.rows[self.row_of_key[key]].cipher[letter]
+    }
+
+    /// Deciphers the cipher letter at `letter` (A being 0) under the key letter at `key`
+    pub fn decipher_letter(&self, key: usize, letter: usize) -> usize {
+        self.rows[self.row_of_key[key]].plain[letter]
+    }
+
+    // Combines the letters of `text` with the repeated `key`, one letter at a time
+    fn combine<F>(&self, key: &[AsciiUppercaseByte], text: &[u8], operation: F) -> Result<String, Error>
+        where F: Fn(usize, usize) -> usize {
+        vigenere_standard::combine(key, text, |text, key| {
+            text.iter()
+                .zip(key)
+                .map(|(&letter, &key)| AsciiUppercaseByte::from_index(operation(key.index(), letter.index())))
+                .collect()
+        })
+    }
+
+    fn passthrough<F>(&self, key: &[AsciiUppercaseByte], text: &[u8], advance: KeyAdvance, operation: F) -> String
+        where F: Fn(usize, usize) -> usize {
+        let alphabet = Alphabet::standard();
+
+        vigenere_standard::passthrough(&common::to_symbols(key), text, advance, |text: &[Symbol], key: &[Symbol]| {
+            text.iter()
+                .zip(key)
+                .map(|(letter, key)| alphabet.symbol(operation(key.index(), letter.index())))
+                .collect()
+        })
+    }
+}
+
+impl fmt::Display for Tableau {
+    /// Writes the plain alphabet, then every row under it, led by its key letters
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self.rows.iter().map(|row| row.keys.len()).max().unwrap_or(0);
+
+        let letters = |alphabet: &mut dyn Iterator<Item = usize>| alphabet.map(|letter| char::from(AsciiUppercaseByte::from_index(letter)).to_string()).collect::<Vec<String>>().join(" ");
+
+        writeln!(f, "{:width$}  {}", "", letters(&mut (0..26)), width = width)?;
+
+        for row in &self.rows {
+            let keys = row.keys.iter().map(|&key| char::from(key)).collect::<String>();
+
+            writeln!(f, "{:width$}  {}", keys, letters(&mut row.cipher.iter().cloned()), width = width)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Any tableau cipher, for use through the [`Cipher`] trait
+///
+/// The key is the keyword, with the setting `tableau` (`vigenere`, `beaufort`,
+/// `variant-beaufort` or `porta`, by default `vigenere`), or the setting `rows` giving a table
+/// of its own, written as for [`Tableau::parse`] with the rows separated by commas.
+pub struct TableauCipher {
+    tableau: Tableau,
+    key: Vec<AsciiUppercaseByte>,
+}
+
+impl KeyedCipher for TableauCipher {
+    type Key = (Tableau, Vec<AsciiUppercaseByte>);
+
+    fn parse_key(key: &str) -> Result<Self::Key, Error> {
+        let key = KeyString::parse(key)?;
+
+        let tableau = match (key.setting("rows"), key.setting("tableau")) {
+            (Some(rows), None) => Tableau::parse(rows)?,
+            (None, Some("vigenere")) | (None, None) => Tableau::vigenere(),
+            (None, Some("beaufort")) => Tableau::beaufort(),
+            (None, Some("variant-beaufort")) => Tableau::variant_beaufort(),
+            (None, Some("porta")) => Tableau::porta(),
+            (None, Some(other)) => return Err(Error::KeyError(format!("Unknown tableau '{}'", other))),
+            (Some(_), Some(_)) => return Err(Error::KeyError("Give either a named tableau or its rows, not both".to_string())),
+        };
+
+        Ok((tableau, common::sanitize_text(key.main.as_bytes())?))
+    }
+
+    fn with_key((tableau, key): Self::Key) -> Result<Self, Error> {
+        Ok(TableauCipher { tableau, key })
+    }
+}
+
+impl TableauCipher {
+    /// The tableau the cipher looks its letters up in
+    pub fn tableau(&self) -> &Tableau {
+        &self.tableau
+    }
+}
+
+impl Cipher for TableauCipher {
+    fn encipher(&self, plain_text: &[u8]) -> Result<String, Error> {
+        self.tableau.combine(&self.key, plain_text, |key, letter| self.tableau.encipher_letter(key, letter))
+    }
+
+    fn decipher(&self, cipher_text: &[u8]) -> Result<String, Error> {
+        self.tableau.combine(&self.key, cipher_text, |key, letter| self.tableau.decipher_letter(key, letter))
+    }
+
+    fn encipher_passthrough(&self, plain_text: &[u8], advance: KeyAdvance) -> Result<String, Error> {
+        Ok(self.tableau.passthrough(&self.key, plain_text, advance, |key, letter| self.tableau.encipher_letter(key, letter)))
+    }
+
+    fn decipher_passthrough(&self, cipher_text: &[u8], advance: KeyAdvance) -> Result<String, Error> {
+        Ok(self.tableau.passthrough(&self.key, cipher_text, advance, |key, letter| self.tableau.decipher_letter(key, letter)))
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use crate::beaufort::{self, Beaufort, VariantBeaufort};
+    use crate::cipher::{Cipher, KeyedCipher};
+    use crate::common;
+    use crate::passthrough::KeyAdvance;
+    use crate::tableau::{Tableau, TableauCipher};
+    use crate::vigenere_standard;
+    use quickcheck::quickcheck;
+
+    #[test]
+    fn test_built_in_tableaux() {
+        let plain_text = b"DEFEND THE EAST WALL OF THE CASTLE";
+
+        let vigenere = TableauCipher::from_key_str("FORTIFICATION").unwrap();
+        assert_eq!(vigenere.encipher(plain_text).unwrap(), vigenere_standard::encipher(b"FORTIFICATION", plain_text).unwrap());
+
+        let beaufort = TableauCipher::from_key_str("FORTIFICATION;tableau=beaufort").unwrap();
+        assert_eq!(beaufort.encipher(plain_text).unwrap(), beaufort::encipher(b"FORTIFICATION", plain_text).unwrap());
+
+        let variant = TableauCipher::from_key_str("FORTIFICATION;tableau=variant-beaufort").unwrap();
+        assert_eq!(variant.encipher(plain_text).unwrap(), beaufort::encipher_variant(b"FORTIFICATION", plain_text).unwrap());
+
+        assert!(!Tableau::vigenere().is_reciprocal());
+        assert!(Tableau::beaufort().is_reciprocal());
+        assert!(Tableau::porta().is_reciprocal());
+    }
+
+    #[test]
+    fn test_custom_tableau() {
+        // Atbash under A to M, the Caesar shift of 3 under N to Z
+        let key = "AN;rows=ABCDEFGHIJKLM:ZYXWVUTSRQPONMLKJIHGFEDCBA, NOPQRSTUVWXYZ:DEFGHIJKLMNOPQRSTUVWXYZABC";
+        let cipher = TableauCipher::from_key_str(key).unwrap();
+
+        let enciphered = cipher.encipher(b"HELLO").unwrap();
+
+        assert_eq!(enciphered, "SHOOL");
+        assert_eq!(cipher.decipher(enciphered.as_bytes()).unwrap(), "HELLO");
+        assert!(!cipher.tableau().is_reciprocal());
+
+        // Every key letter must pick exactly one row, and every row must hold the 26 letters
+        assert!(Tableau::parse("ABCDEFGHIJKLM:ZYXWVUTSRQPONMLKJIHGFEDCBA").is_err());
+        assert!(Tableau::parse("ABCDEFGHIJKLMNOPQRSTUVWXYZ:ZYXWVUTSRQPONMLKJIHGFEDCBA, A:ABCDEFGHIJKLMNOPQRSTUVWXYZ").is_err());
+        assert!(Tableau::parse("ABCDEFGHIJKLMNOPQRSTUVWXYZ:AACDEFGHIJKLMNOPQRSTUVWXYZ").is_err());
+        assert!(Tableau::parse("ABCDEFGHIJKLMNOPQRSTUVWXYZ").is_err());
+        assert!(TableauCipher::from_key_str("KEY;tableau=trithemius").is_err());
+    }
+
+    #[test]
+    fn test_display() {
+        let tableau = Tableau::porta().to_string();
+        let lines = tableau.lines().collect::<Vec<&str>>();
+
+        assert_eq!(lines.len(), 14);
+        assert_eq!(lines[0], "    A B C D E F G H I J K L M N O P Q R S T U V W X Y Z");
+        assert_eq!(lines[1], "AB  N O P Q R S T U V W X Y Z A B C D E F G H I J K L M");
+        assert_eq!(lines[2], "CD  O P Q R S T U V W X Y Z N M A B C D E F G H I J K L");
+    }
+
+    #[test]
+    fn test_passthrough() {
+        let cipher = TableauCipher::from_key_str("LEMON").unwrap();
+
+        let enciphered = cipher.encipher_passthrough(b"Meet me at 10, by the gate!", KeyAdvance::LettersOnly).unwrap();
+
+        assert_eq!(enciphered, vigenere_standard::encipher_passthrough(b"LEMON", b"Meet me at 10, by the gate!", KeyAdvance::LettersOnly).unwrap());
+        assert_eq!(cipher.decipher_passthrough(enciphered.as_bytes(), KeyAdvance::LettersOnly).unwrap(), "Meet me at 10, by the gate!");
+    }
+
+    quickcheck! {
+        fn vigenere_tableau_is_the_vigenere_cipher(key: Vec<u8>, text: Vec<u8>) -> bool {
+            let cipher = TableauCipher::with_key((Tableau::vigenere(), common::sanitize_text(&key).unwrap())).unwrap();
+            let advance = KeyAdvance::LettersOnly;
+
+            cipher.encipher(&text).ok() == vigenere_standard::encipher(&key, &text).ok()
+                && cipher.decipher(&text).ok() == vigenere_standard::decipher(&key, &text).ok()
+                && cipher.encipher_passthrough(&text, advance).ok() == vigenere_standard::encipher_passthrough(&key, &text, advance).ok()
+                && cipher.decipher_passthrough(&text, advance).ok() == vigenere_standard::decipher_passthrough(&key, &text, advance).ok()
+        }
+
+        fn beaufort_tableaux_are_the_beaufort_ciphers(key: Vec<u8>, text: Vec<u8>) -> bool {
+            let key = common::sanitize_text(&key).unwrap();
+            let advance = KeyAdvance::LettersOnly;
+
+            let pairs: [(TableauCipher, Box<dyn Cipher>); 2] = [
+                (TableauCipher::with_key((Tableau::beaufort(), key.clone())).unwrap(), Box::new(Beaufort::with_key(key.clone()).unwrap())),
+                (TableauCipher::with_key((Tableau::variant_beaufort(), key.clone())).unwrap(), Box::new(VariantBeaufort::with_key(key).unwrap())),
+            ];
+
+            pairs.iter().all(|(tableau, cipher)| {
+                tableau.encipher(&text).ok() == cipher.encipher(&text).ok()
+                    && tableau.decipher(&text).ok() == cipher.decipher(&text).ok()
+                    && tableau.encipher_passthrough(&text, advance).ok() == cipher.encipher_passthrough(&text, advance).ok()
+                    && tableau.decipher_passthrough(&text, advance).ok() == cipher.decipher_passthrough(&text, advance).ok()
+            })
+        }
+    }
+
+    quickcheck! {
+        fn tableau_round_trip(key: Vec<u8>, plain_text: Vec<u8>, tableau: u8) -> bool {
+            let tableau = ["vigenere", "beaufort", "variant-beaufort", "porta"][tableau as usize % 4];
+            let key = format!("{};tableau={}", String::from_utf8_lossy(&common::sanitize_text(&key).unwrap().into_iter().map(|l| l.get_byte()).collect::<Vec<u8>>()), tableau);
+
+            let cipher = TableauCipher::from_key_str(&key).unwrap();
+
+            let enciphered = cipher.encipher(&plain_text).unwrap();
+
+            cipher.decipher(enciphered.as_bytes()).unwrap() == common::format_output(common::sanitize_text(&plain_text).unwrap())
+        }
+    }
+}
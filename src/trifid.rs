@@ -0,0 +1,140 @@
+//! # Implementation of Delastelle's Trifid cipher
+//!
+//! The Trifid is the Bifid (see [`bifid`](crate::bifid)) in three dimensions. The 26 letters and
+//! a + fill a 3×3×3 cube, so each symbol has three coordinates: its layer, row and column.
+//!
+//! The message is split into blocks of a fixed length, the _period_. Under each block, the layer,
+//! the row and the column of every symbol are written out, one below the other. The digits are
+//! read along the rows, three at a time, and each triple becomes the symbol at that layer, row
+//! and column.
+//!
+//! # Example:
+//!
+//! Keyphrase: FELIX MARIE DELASTELLE
+//!
+//! ```text
+//! Layer 1   Layer 2   Layer 3
+//! F E L     S T B     O P Q
+//! I X M     C G H     U V W
+//! A R D     J K N     Y Z +
+//! ```
+//!
+//! Message to encipher: "AIDE-TOI, LE CIEL T'AIDERA", with a period of 5
+//!
+//! ```text
+//!         A I D E T  O I L E C  I E L T A  I D E R A
+//! Layer:  1 1 1 1 2  3 1 1 1 2  1 1 1 2 1  1 1 1 1 1
+//! Row:    3 2 3 1 1  1 2 1 1 2  2 1 1 1 3  2 3 1 3 3
+//! Column: 1 1 3 2 2  1 1 3 2 1  1 2 3 2 1  1 3 2 2 1
+//! ```
+//!
+//! Cipher text: FMJFV OISSU FTFPU FEQQC
+
+use crate::alphabet::Alphabet;
+use crate::cipher::{Cipher, KeyString, KeyedCipher};
+use crate::common;
+use crate::errors::Error;
+use crate::polybius::{self, Grid};
+
+/// The period Delastelle used in his own examples
+pub const DEFAULT_PERIOD: usize = 5;
+
+/// Enciphers `plain_text` with the cube built from `keyphrase`, `period` symbols at a time (the
+/// whole message at once if `period` is 0)
+pub fn encipher(keyphrase: &[u8], period: usize, plain_text: &[u8]) -> Result<String, Error> {
+    Trifid::with_key((Grid::cube(keyphrase), period))?.encipher(plain_text)
+}
+
+/// Reverses [`encipher`]
+pub fn decipher(keyphrase: &[u8], period: usize, cipher_text: &[u8]) -> Result<String, Error> {
+    Trifid::with_key((Grid::cube(keyphrase), period))?.decipher(cipher_text)
+}
+
+/// The Trifid cipher, for use through the [`Cipher`] trait
+///
+/// The key is the cube and the period. As a string, it is the keyphrase, with the setting
+/// `period` (default [`DEFAULT_PERIOD`], 0 for the whole message at once).
+pub struct Trifid {
+    grid: Grid,
+    period: usize,
+}
+
+impl KeyedCipher for Trifid {
+    type Key = (Grid, usize);
+
+    fn parse_key(key: &str) -> Result<Self::Key, Error> {
+        let key = KeyString::parse(key)?;
+
+        Ok((Grid::new(&Alphabet::trifid(), key.main.as_bytes(), 3)?, key.parsed_setting("period", DEFAULT_PERIOD)?))
+    }
+
+    fn with_key((grid, period): Self::Key) -> Result<Self, Error> {
+        Ok(Trifid { grid, period })
+    }
+}
+
+impl Trifid {
+    /// The key cube
+    pub fn grid(&self) -> &Grid {
+        &self.grid
+    }
+}
+
+impl Cipher for Trifid {
+    fn encipher(&self, plain_text: &[u8]) -> Result<String, Error> {
+        let plain_text = common::sanitize_text_with(plain_text, self.grid.alphabet());
+
+        let cipher_text = polybius::fractionate(&self.grid, &plain_text, self.period);
+
+        Ok(common::format_output(self.grid.alphabet().render(&cipher_text)))
+    }
+
+    fn decipher(&self, cipher_text: &[u8]) -> Result<String, Error> {
+        let cipher_text = common::sanitize_text_with(cipher_text, self.grid.alphabet());
+
+        let plain_text = polybius::unfractionate(&self.grid, &cipher_text, self.period);
+
+        Ok(common::format_output(self.grid.alphabet().render(&plain_text)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::alphabet::Alphabet;
+    use crate::cipher::{Cipher, KeyedCipher};
+    use crate::common;
+    use crate::trifid::{self, Trifid};
+    use quickcheck::quickcheck;
+
+    #[test]
+    fn test_trifid() {
+        let enciphered = trifid::encipher(b"FELIX MARIE DELASTELLE", 5, b"AIDE-TOI, LE CIEL T'AIDERA").unwrap();
+
+        assert_eq!(enciphered, "FMJFV OISSU FTFPU FEQQC");
+
+        assert_eq!(
+            trifid::decipher(b"FELIX MARIE DELASTELLE", 5, enciphered.as_bytes()).unwrap(),
+            "AIDET OILEC IELTA IDERA"
+        );
+    }
+
+    #[test]
+    fn test_plus() {
+        let trifid = Trifid::from_key_str("FELIX MARIE DELASTELLE;period=0").unwrap();
+
+        let enciphered = trifid.encipher(b"END+").unwrap();
+
+        assert_eq!(trifid.decipher(enciphered.as_bytes()).unwrap(), "END+");
+    }
+
+    quickcheck! {
+        fn trifid_round_trip(keyphrase: Vec<u8>, period: u8, plain_text: Vec<u8>) -> bool {
+            let enciphered = trifid::encipher(&keyphrase, period as usize, &plain_text).unwrap();
+            let deciphered = trifid::decipher(&keyphrase, period as usize, enciphered.as_bytes()).unwrap();
+
+            let alphabet = Alphabet::trifid();
+
+            deciphered == common::format_output(alphabet.render(&common::sanitize_text_with(&plain_text, &alphabet)))
+        }
+    }
+}
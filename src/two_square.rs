@@ -0,0 +1,271 @@
+//! # Implementation of Wheatstone's two-square cipher
+//!
+//! The two-square enciphers digraphs with two key squares (see [`polybius`]). In the _vertical_
+//! two-square, the first square sits above the second:
+//!
+//! ```text
+//! E X A M P
+//! L B C D F
+//! G H I J K
+//! N O R S T
+//! U V W Y Z
+//!
+//! K E Y W O
+//! R D A B C
+//! F G H I J
+//! L M N P S
+//! T U V X Z
+//! ```
+//!
+//! The first letter of a digraph is found in the top square and the second in the bottom square.
+//! They are two corners of a rectangle, and the digraph is replaced by the letters at the other two
+//! corners: first the one in the top square, then the one in the bottom square. When both letters
+//! are in the same column, the digraph is left as it is.
+//!
+//! In the _horizontal_ two-square, the first square is on the left and the second on the right.
+//! The first cipher letter is the corner in the right square, the second the corner in the left
+//! square, so a digraph whose letters share a row is simply reversed.
+//!
+//! The vertical two-square is its own inverse. The horizontal one is not: deciphering finds the
+//! cipher letters in the swapped squares.
+//!
+//! # Example:
+//!
+//! Keyphrases: EXAMPLE and KEYWORD, with Q written as K (the squares above)
+//!
+//! Message to encipher: "HELP ME OBI WAN KENOBI"
+//!
+//! Digraphs: HE LP ME OB IW AN KE NO BI
+//!
+//! Cipher text (vertical): HEDLX WSDJY ANHOT KDG
+//!
+//! Deciphering gives back the digraphs, with the merged letter written as in the square.
+
+use crate::cipher::{Cipher, KeyString, KeyedCipher};
+use crate::common::{self, AsciiUppercaseByte};
+use crate::errors::Error;
+use crate::polybius::{self, Grid, Merge};
+
+/// Enciphers `plain_text` with the vertical two-square, writing J as I and padding with X
+pub fn encipher(first_keyphrase: &[u8], second_keyphrase: &[u8], plain_text: &[u8]) -> Result<String, Error> {
+    TwoSquare::with_key(default_key(first_keyphrase, second_keyphrase)?)?.encipher(plain_text)
+}
+
+/// Deciphers `cipher_text` with the vertical two-square, writing J as I
+pub fn decipher(first_keyphrase: &[u8], second_keyphrase: &[u8], cipher_text: &[u8]) -> Result<String, Error> {
+    TwoSquare::with_key(default_key(first_keyphrase, second_keyphrase)?)?.decipher(cipher_text)
+}
+
+/// Enciphers `plain_text` with the horizontal two-square, writing J as I and padding with X
+pub fn encipher_horizontal(first_keyphrase: &[u8], second_keyphrase: &[u8], plain_text: &[u8]) -> Result<String, Error> {
+    HorizontalTwoSquare::with_key(default_key(first_keyphrase, second_keyphrase)?)?.encipher(plain_text)
+}
+
+/// Deciphers `cipher_text` with the horizontal two-square, writing J as I
+pub fn decipher_horizontal(first_keyphrase: &[u8], second_keyphrase: &[u8], cipher_text: &[u8]) -> Result<String, Error> {
+    HorizontalTwoSquare::with_key(default_key(first_keyphrase, second_keyphrase)?)?.decipher(cipher_text)
+}
+
+/// The vertical two-square, for use through the [`Cipher`] trait
+///
+/// The key is the two squares and the padding letter. As a string, it is the first keyphrase,
+/// with the settings `second` (the second keyphrase), `merge` (the letter left out of the
+/// squares, then the letter it is written as, default `JI`) and `pad` (default `X`).
+pub struct TwoSquare {
+    first: Grid,
+    second: Grid,
+    merge: Merge,
+    pad: AsciiUppercaseByte,
+}
+
+impl KeyedCipher for TwoSquare {
+    type Key = (Grid, Grid, AsciiUppercaseByte);
+
+    fn parse_key(key: &str) -> Result<Self::Key, Error> {
+        let key = KeyString::parse(key)?;
+
+        let merge = polybius::parse_merge(&key)?;
+
+        Ok((
+            Grid::letter_square(key.main.as_bytes(), merge)?,
+            Grid::letter_square(key.setting("second").unwrap_or("").as_bytes(), merge)?,
+            polybius::parse_pad(&key, merge)?,
+        ))
+    }
+
+    fn with_key((first, second, pad): Self::Key) -> Result<Self, Error> {
+        let merge = polybius::check_squares(&first, &second, pad)?;
+
+        Ok(TwoSquare { first, second, merge, pad })
+    }
+}
+
+impl TwoSquare {
+    /// The first square (on top, or on the left)
+    pub fn first(&self) -> &Grid {
+        &self.first
+    }
+
+    /// The second square (below, or on the right)
+    pub fn second(&self) -> &Grid {
+        &self.second
+    }
+
+    // Splits `text` into digraphs, replaces each with `substitute` and formats the result
+    fn substitute<F>(&self, text: &[AsciiUppercaseByte], substitute: F) -> String
+        where F: Fn(AsciiUppercaseByte, AsciiUppercaseByte) -> (AsciiUppercaseByte, AsciiUppercaseByte)
+    {
+        let text = polybius::digraphs(text, self.merge, self.pad)
+            .into_iter()
+            .flat_map(|(a, b)| {
+                let (a, b) = substitute(a, b);
+                vec![a, b]
+            })
+            .collect();
+
+        common::format_output(text)
+    }
+}
+
+impl Cipher for TwoSquare {
+    fn encipher(&self, plain_text: &[u8]) -> Result<String, Error> {
+        let plain_text = common::sanitize_text(plain_text)?;
+
+        Ok(self.substitute(&plain_text, |a, b| {
+            polybius::opposite_corners((&self.first, a), (&self.second, b), (&self.first, &self.second))
+        }))
+    }
+
+    fn decipher(&self, cipher_text: &[u8]) -> Result<String, Error> {
+        let cipher_text = common::sanitize_text(cipher_text)?;
+
+        polybius::check_even(&cipher_text, "Two-square")?;
+
+        // The vertical two-square is its own inverse
+        Ok(self.substitute(&cipher_text, |a, b| {
+            polybius::opposite_corners((&self.first, a), (&self.second, b), (&self.first, &self.second))
+        }))
+    }
+}
+
+/// The horizontal two-square, for use through the [`Cipher`] trait
+///
+/// The key is written as for [`TwoSquare`].
+pub struct HorizontalTwoSquare(TwoSquare);
+
+impl KeyedCipher for HorizontalTwoSquare {
+    type Key = (Grid, Grid, AsciiUppercaseByte);
+
+    fn parse_key(key: &str) -> Result<Self::Key, Error> {
+        TwoSquare::parse_key(key)
+    }
+
+    fn with_key(key: Self::Key) -> Result<Self, Error> {
+        Ok(HorizontalTwoSquare(TwoSquare::with_key(key)?))
+    }
+}
+
+impl Cipher for HorizontalTwoSquare {
+    fn encipher(&self, plain_text: &[u8]) -> Result<String, Error> {
+        let plain_text = common::sanitize_text(plain_text)?;
+
+        let TwoSquare { first: left, second: right, .. } = &self.0;
+
+        Ok(self.0.substitute(&plain_text, |a, b| polybius::opposite_corners((left, a), (right, b), (right, left))))
+    }
+
+    fn decipher(&self, cipher_text: &[u8]) -> Result<String, Error> {
+        let cipher_text = common::sanitize_text(cipher_text)?;
+
+        polybius::check_even(&cipher_text, "Two-square")?;
+
+        let TwoSquare { first: left, second: right, .. } = &self.0;
+
+        Ok(self.0.substitute(&cipher_text, |a, b| polybius::opposite_corners((right, a), (left, b), (left, right))))
+    }
+}
+
+fn default_key(first_keyphrase: &[u8], second_keyphrase: &[u8]) -> Result<(Grid, Grid, AsciiUppercaseByte), Error> {
+    Ok((Grid::square(5, first_keyphrase)?, Grid::square(5, second_keyphrase)?, polybius::letter(b'X')))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cipher::{Cipher, KeyedCipher};
+    use crate::common;
+    use crate::two_square::{self, HorizontalTwoSquare, TwoSquare};
+    use quickcheck::quickcheck;
+
+    #[test]
+    fn test_vertical() {
+        let two_square = TwoSquare::from_key_str("EXAMPLE;second=KEYWORD;merge=QK").unwrap();
+
+        let enciphered = two_square.encipher(b"HELP ME OBI WAN KENOBI").unwrap();
+
+        assert_eq!(enciphered, "HEDLX WSDJY ANHOT KDG");
+
+        assert_eq!(two_square.decipher(enciphered.as_bytes()).unwrap(), "HELPM EOBIW ANKEN OBI");
+    }
+
+    #[test]
+    fn test_horizontal() {
+        let two_square = HorizontalTwoSquare::from_key_str("EXAMPLE;second=KEYWORD;merge=QK").unwrap();
+
+        // H and F sit in the same row of the two squares, so HF is reversed
+        assert_eq!(two_square.encipher(b"HF").unwrap(), "FH");
+        assert_eq!(two_square.encipher(b"HE").unwrap(), "GX");
+
+        let enciphered = two_square.encipher(b"HELP ME OBI WAN KENOBI").unwrap();
+
+        assert_eq!(two_square.decipher(enciphered.as_bytes()).unwrap(), "HELPM EOBIW ANKEN OBI");
+    }
+
+    #[test]
+    fn test_padding() {
+        let enciphered = two_square::encipher(b"EXAMPLE", b"KEYWORD", b"JOE").unwrap();
+
+        assert_eq!(two_square::decipher(b"EXAMPLE", b"KEYWORD", enciphered.as_bytes()).unwrap(), "IOEX");
+
+        let two_square = TwoSquare::from_key_str("EXAMPLE;second=KEYWORD;pad=Z").unwrap();
+
+        assert_eq!(two_square.decipher(two_square.encipher(b"JOE").unwrap().as_bytes()).unwrap(), "IOEZ");
+    }
+
+    #[test]
+    fn test_invalid_keys() {
+        assert!(TwoSquare::from_key_str("KEY;pad=XQ").is_err());
+        assert!(TwoSquare::from_key_str("KEY;pad=J").is_err());
+        assert!(TwoSquare::from_key_str("KEY;merge=QQ").is_err());
+        assert!(two_square::decipher(b"KEY", b"WORD", b"ABC").is_err());
+    }
+
+    quickcheck! {
+        fn vertical_round_trip(first: Vec<u8>, second: Vec<u8>, plain_text: Vec<u8>) -> bool {
+            let enciphered = two_square::encipher(&first, &second, &plain_text).unwrap();
+            let deciphered = two_square::decipher(&first, &second, enciphered.as_bytes()).unwrap();
+
+            deciphered == padded(&plain_text)
+        }
+
+        fn horizontal_round_trip(first: Vec<u8>, second: Vec<u8>, plain_text: Vec<u8>) -> bool {
+            let enciphered = two_square::encipher_horizontal(&first, &second, &plain_text).unwrap();
+            let deciphered = two_square::decipher_horizontal(&first, &second, enciphered.as_bytes()).unwrap();
+
+            deciphered == padded(&plain_text)
+        }
+    }
+
+    // The letters of `plain_text` as they come back from deciphering: J written as I, and padded with X
+    fn padded(plain_text: &[u8]) -> String {
+        let mut text = common::sanitize_text(plain_text).unwrap()
+            .into_iter()
+            .map(|letter| if char::from(letter) == 'J' { 'I' } else { char::from(letter) })
+            .collect::<Vec<char>>();
+
+        if !text.len().is_multiple_of(2) {
+            text.push('X');
+        }
+
+        common::format_output(text)
+    }
+}
@@ -9,40 +9,274 @@
 //! 
 //! The enciphering proceeds as normal
 //! 
-//! The autokey is technically safer than the standard poly-alphabetic cipher 
-//! because the key doesn't repeat. So it is immune to the sneaky Kasiski techniques.
+//! In passthrough mode with [`KeyAdvance::EveryCharacter`], the key moves on at every character
+//! of the message, letter or not, and still runs on from the priming key into the letters of the
+//! message. A character that is not enciphered has no letter to give the key, so it passes on the
+//! key letter it stood under instead:
+//!
+//! ```text
+//! Plain text:  Go, go!
+//! Key:         KEGOGOG (priming key KE)
+//! Cipher text: Qs, mc!
+//! ```
+//!
+//! In the _ciphertext autokey_ system, the key is the priming key followed by the cipher text
+//! instead:
+//!
+//! K = K0 || CIPHER TEXT
+//!
+//! The key never repeats, so the Kasiski examination and the index of coincidence find no
+//! period. The system falls all the same (see [`crate::crack::autokey`]):
+//!
+//! - Each letter of the priming key starts a chain of plain letters, every one deciphering the
+//!   next, so the priming key can be found a letter at a time, by how much the text reads like
+//!   English.
+//! - A probable word dragged through the cipher text gives the letters of the key under it,
+//!   which are plain text too. A word at least as long as the priming key gives away the whole
+//!   message.
+//! - The ciphertext autokey hands the key over with the message: past the priming key, every
+//!   plain letter is its cipher letter minus the one a priming key length before.
 
 use crate::errors::Error;
+use crate::alphabet::{Alphabet, Symbol};
+use crate::cipher::{Cipher, KeyedCipher};
+use crate::common::AsciiUppercaseByte;
 use crate::common;
+use crate::passthrough::{KeyAdvance, MaskedText};
 use crate::vigenere_standard;
+use std::ops::{Add, Sub};
 
 /// Enciphers the `plain_text` with `priming_key` using the Vigenere autokey system 
 pub fn encipher(priming_key: &[u8], plain_text: &[u8]) -> Result<String, Error> {
-    let mut key = common::sanitize_text(priming_key)?;
+    encipher_with_key(common::sanitize_text(priming_key)?, plain_text)
+}
+
+/// Deciphers `cipher_text` with `priming_key` using the Vigenere autokey system
+pub fn decipher(priming_key: &[u8], cipher_text: &[u8]) -> Result<String, Error> {
+    decipher_with_key(common::sanitize_text(priming_key)?, cipher_text)
+}
+
+/// Enciphers the `plain_text` with `priming_key` using the Vigenere autokey system over the symbols of `alphabet`
+pub fn encipher_in(alphabet: &Alphabet, priming_key: &[u8], plain_text: &[u8]) -> Result<String, Error> {
+    let enciphered = encipher_symbols(common::sanitize_text_with(priming_key, alphabet), common::sanitize_text_with(plain_text, alphabet));
+
+    Ok(common::format_output(alphabet.render(&enciphered)))
+}
+
+/// Deciphers `cipher_text` with `priming_key` using the Vigenere autokey system over the symbols of `alphabet`
+pub fn decipher_in(alphabet: &Alphabet, priming_key: &[u8], cipher_text: &[u8]) -> Result<String, Error> {
+    let deciphered = decipher_symbols(common::sanitize_text_with(priming_key, alphabet), common::sanitize_text_with(cipher_text, alphabet));
+
+    Ok(common::format_output(alphabet.render(&deciphered)))
+}
+
+/// Enciphers the letters of `plain_text` with `priming_key` using the Vigenere autokey system,
+/// leaving everything else in place
+pub fn encipher_passthrough(priming_key: &[u8], plain_text: &[u8], advance: KeyAdvance) -> Result<String, Error> {
+    Ok(passthrough(&common::sanitize_text_with(priming_key, &Alphabet::standard()), plain_text, advance, true, Feedback::PlainText))
+}
+
+/// Deciphers the letters of `cipher_text` with `priming_key` using the Vigenere autokey system,
+/// leaving everything else in place
+pub fn decipher_passthrough(priming_key: &[u8], cipher_text: &[u8], advance: KeyAdvance) -> Result<String, Error> {
+    Ok(passthrough(&common::sanitize_text_with(priming_key, &Alphabet::standard()), cipher_text, advance, false, Feedback::PlainText))
+}
+
+/// Enciphers the `plain_text` with `priming_key` using the ciphertext autokey system
+pub fn encipher_ciphertext_autokey(priming_key: &[u8], plain_text: &[u8]) -> Result<String, Error> {
+    CiphertextAutokeyVigenere::with_key(common::sanitize_text(priming_key)?)?.encipher(plain_text)
+}
+
+/// Deciphers `cipher_text` with `priming_key` using the ciphertext autokey system
+pub fn decipher_ciphertext_autokey(priming_key: &[u8], cipher_text: &[u8]) -> Result<String, Error> {
+    CiphertextAutokeyVigenere::with_key(common::sanitize_text(priming_key)?)?.decipher(cipher_text)
+}
+
+/// The Vigenere autokey system, for use through the [`Cipher`] trait
+///
+/// The key is the priming key
+pub struct AutokeyVigenere {
+    priming_key: Vec<AsciiUppercaseByte>,
+}
+
+impl KeyedCipher for AutokeyVigenere {
+    type Key = Vec<AsciiUppercaseByte>;
+
+    fn parse_key(key: &str) -> Result<Self::Key, Error> {
+        common::sanitize_text(key.as_bytes())
+    }
+
+    fn with_key(priming_key: Self::Key) -> Result<Self, Error> {
+        Ok(AutokeyVigenere { priming_key })
+    }
+}
+
+impl Cipher for AutokeyVigenere {
+    fn encipher(&self, plain_text: &[u8]) -> Result<String, Error> {
+        encipher_with_key(self.priming_key.clone(), plain_text)
+    }
+
+    fn decipher(&self, cipher_text: &[u8]) -> Result<String, Error> {
+        decipher_with_key(self.priming_key.clone(), cipher_text)
+    }
+
+    fn encipher_passthrough(&self, plain_text: &[u8], advance: KeyAdvance) -> Result<String, Error> {
+        Ok(passthrough(&common::to_symbols(&self.priming_key), plain_text, advance, true, Feedback::PlainText))
+    }
+
+    fn decipher_passthrough(&self, cipher_text: &[u8], advance: KeyAdvance) -> Result<String, Error> {
+        Ok(passthrough(&common::to_symbols(&self.priming_key), cipher_text, advance, false, Feedback::PlainText))
+    }
+}
+
+/// The ciphertext autokey system, for use through the [`Cipher`] trait
+///
+/// The key is the priming key
+pub struct CiphertextAutokeyVigenere {
+    priming_key: Vec<AsciiUppercaseByte>,
+}
+
+impl KeyedCipher for CiphertextAutokeyVigenere {
+    type Key = Vec<AsciiUppercaseByte>;
+
+    fn parse_key(key: &str) -> Result<Self::Key, Error> {
+        common::sanitize_text(key.as_bytes())
+    }
+
+    fn with_key(priming_key: Self::Key) -> Result<Self, Error> {
+        Ok(CiphertextAutokeyVigenere { priming_key })
+    }
+}
+
+impl Cipher for CiphertextAutokeyVigenere {
+    fn encipher(&self, plain_text: &[u8]) -> Result<String, Error> {
+        let plain_text = common::sanitize_text(plain_text)?;
+
+        if self.priming_key.is_empty() {
+            return Ok(common::format_output(plain_text));
+        }
+
+        let mut cipher_text: Vec<AsciiUppercaseByte> = Vec::new();
+
+        for (i, &letter) in plain_text.iter().enumerate() {
+            let key = if i < self.priming_key.len() { self.priming_key[i] } else { cipher_text[i - self.priming_key.len()] };
+
+            cipher_text.push(letter + key);
+        }
+
+        Ok(common::format_output(cipher_text))
+    }
 
+    fn decipher(&self, cipher_text: &[u8]) -> Result<String, Error> {
+        let cipher_text = common::sanitize_text(cipher_text)?;
+
+        Ok(common::format_output(decipher_ciphertext_symbols(&self.priming_key, &cipher_text)))
+    }
+
+    fn encipher_passthrough(&self, plain_text: &[u8], advance: KeyAdvance) -> Result<String, Error> {
+        Ok(passthrough(&common::to_symbols(&self.priming_key), plain_text, advance, true, Feedback::CipherText))
+    }
+
+    fn decipher_passthrough(&self, cipher_text: &[u8], advance: KeyAdvance) -> Result<String, Error> {
+        Ok(passthrough(&common::to_symbols(&self.priming_key), cipher_text, advance, false, Feedback::CipherText))
+    }
+}
+
+// Which text follows the priming key in the key
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Feedback {
+    PlainText,
+    CipherText,
+}
+
+fn encipher_with_key(key: Vec<AsciiUppercaseByte>, plain_text: &[u8]) -> Result<String, Error> {
     let plain_text = common::sanitize_text(plain_text)?;
 
-    if key.len() == 0 {
-        return Ok(common::format_output(plain_text));
+    Ok(common::format_output(encipher_symbols(key, plain_text)))
+}
+
+fn decipher_with_key(key: Vec<AsciiUppercaseByte>, cipher_text: &[u8]) -> Result<String, Error> {
+    let cipher_text = common::sanitize_text(cipher_text)?;
+
+    Ok(common::format_output(decipher_symbols(key, cipher_text)))
+}
+
+fn passthrough(priming_key: &[Symbol], text: &[u8], advance: KeyAdvance, encipher: bool, feedback: Feedback) -> String {
+    let alphabet = Alphabet::standard();
+
+    let text = MaskedText::new(&alphabet, text);
+
+    let letters = text.letters();
+
+    if priming_key.is_empty() {
+        return text.unmask(&alphabet, &letters);
+    }
+
+    let mut positions = text.key_positions(advance).into_iter().zip(letters).peekable();
+
+    // What each position of the text feeds back into the key, filled in as we go: the plain (or
+    // cipher) letter, or for a character that is not enciphered, the key letter it stood under
+    let mut fed_back = Vec::with_capacity(text.key_length(advance));
+
+    let mut output = Vec::new();
+
+    for i in 0..text.key_length(advance) {
+        let key = if i < priming_key.len() { priming_key[i] } else { fed_back[i - priming_key.len()] };
+
+        let letter = match positions.next_if(|&(position, _)| position == i) {
+            Some((_, letter)) => letter,
+            None => {
+                fed_back.push(key);
+                continue;
+            }
+        };
+
+        let (result, plain_letter) = if encipher {
+            (letter + key, letter)
+        } else {
+            (letter - key, letter - key)
+        };
+
+        fed_back.push(match (feedback, encipher) {
+            (Feedback::PlainText, _) => plain_letter,
+            (Feedback::CipherText, true) => result,
+            (Feedback::CipherText, false) => letter,
+        });
+
+        output.push(result);
+    }
+
+    text.unmask(&alphabet, &output)
+}
+
+fn encipher_symbols<T: Copy + Add<Output = T>>(mut key: Vec<T>, plain_text: Vec<T>) -> Vec<T> {
+    if key.is_empty() {
+        return plain_text;
     }
 
     key.append(&mut plain_text.clone());
 
     key.truncate(plain_text.len());
 
-    let enciphered = vigenere_standard::add_bytes(&plain_text, &key);
-
-    Ok(common::format_output(enciphered))
+    vigenere_standard::add_bytes(&plain_text, &key)
 }
 
-/// Deciphers `cipher_text` with `priming_key` using the Vigenere autokey system
-pub fn decipher(priming_key: &[u8], cipher_text: &[u8]) -> Result<String, Error> {
-    let mut key = common::sanitize_text(priming_key)?;
+// Deciphers the ciphertext autokey: past the priming key, every letter is keyed by the cipher letter a priming key
+// length before
+pub(crate) fn decipher_ciphertext_symbols<T: Copy + Sub<Output = T>>(priming_key: &[T], cipher_text: &[T]) -> Vec<T> {
+    if priming_key.is_empty() {
+        return cipher_text.to_vec();
+    }
 
-    let cipher_text = common::sanitize_text(cipher_text)?;
+    cipher_text.iter()
+        .enumerate()
+        .map(|(i, &letter)| letter - if i < priming_key.len() { priming_key[i] } else { cipher_text[i - priming_key.len()] })
+        .collect()
+}
 
-    if key.len() == 0 {
-        return Ok(common::format_output(cipher_text));
+pub(crate) fn decipher_symbols<T: Copy + Sub<Output = T>>(mut key: Vec<T>, cipher_text: Vec<T>) -> Vec<T> {
+    if key.is_empty() {
+        return cipher_text;
     }
 
     let mut deciphered = Vec::new();
@@ -55,13 +289,18 @@ pub fn decipher(priming_key: &[u8], cipher_text: &[u8]) -> Result<String, Error>
         key.push(plain_char);
     }
 
-    Ok(common::format_output(deciphered))
+    deciphered
 }
 
 
 #[cfg(test)]
 mod tests {
-    use crate::vigenere_autokey;
+    use crate::vigenere_autokey::{self, AutokeyVigenere, CiphertextAutokeyVigenere};
+    use crate::alphabet::Alphabet;
+    use crate::cipher::{Cipher, KeyedCipher};
+    use crate::common;
+    use crate::passthrough::KeyAdvance;
+    use quickcheck::quickcheck;
 
     #[test]
     fn test_vigenere_autokey() {
@@ -75,4 +314,109 @@ mod tests {
 
         assert_eq!("AAAAA A", plain_text);
     }
+
+    #[test]
+    fn test_vigenere_autokey_passthrough() {
+        let plain_text = b"Attack at dawn!";
+
+        let cipher_text = vigenere_autokey::encipher_passthrough(b"QUEENLY", plain_text, KeyAdvance::LettersOnly).unwrap();
+
+        let deciphered = vigenere_autokey::decipher_passthrough(b"QUEENLY", cipher_text.as_bytes(), KeyAdvance::LettersOnly).unwrap();
+
+        assert_eq!(deciphered.as_bytes(), &plain_text[..]);
+
+        assert_eq!(cipher_text, "Qnxepv yt wtwp!");
+        assert_eq!(vigenere_autokey::encipher(b"QUEENLY", plain_text).unwrap(), "QNXEP VYTWT WP");
+    }
+
+    #[test]
+    fn test_ciphertext_autokey() {
+        let cipher_text = vigenere_autokey::encipher_ciphertext_autokey(b"QUEENLY", b"ATTACK AT DAWN").unwrap();
+
+        // The first seven letters are keyed by the priming key, the rest by the cipher text
+        assert_eq!(cipher_text, "QNXEP VYJQX AC");
+        assert_eq!(&cipher_text[..5], &vigenere_autokey::encipher(b"QUEENLY", b"ATTAC").unwrap()[..]);
+
+        let plain_text = vigenere_autokey::decipher_ciphertext_autokey(b"QUEENLY", cipher_text.as_bytes()).unwrap();
+
+        assert_eq!(plain_text, "ATTAC KATDA WN");
+
+        let cipher = CiphertextAutokeyVigenere::from_key_str("QUEENLY").unwrap();
+
+        let enciphered = cipher.encipher_passthrough(b"Attack at dawn!", KeyAdvance::LettersOnly).unwrap();
+
+        assert_eq!(cipher.decipher_passthrough(enciphered.as_bytes(), KeyAdvance::LettersOnly).unwrap(), "Attack at dawn!");
+
+        assert_eq!(enciphered, "Qnxepv yj qxac!");
+    }
+
+    #[test]
+    fn test_passthrough_enciphers_every_letter() {
+        let plain_text = b"b c d e f g h i";
+
+        let autokey = AutokeyVigenere::from_key_str("K").unwrap();
+        let ciphertext_autokey = CiphertextAutokeyVigenere::from_key_str("K").unwrap();
+
+        // Spaces are kept, but each letter goes through the key: K, then B, C, D... or L, N, Q...
+        assert_eq!(autokey.encipher_passthrough(plain_text, KeyAdvance::LettersOnly).unwrap(), "l d f h j l n p");
+        assert_eq!(ciphertext_autokey.encipher_passthrough(plain_text, KeyAdvance::LettersOnly).unwrap(), "l n q u z f m u");
+
+        // Moving on over the spaces too, every letter is still enciphered
+        let enciphered = autokey.encipher_passthrough(plain_text, KeyAdvance::EveryCharacter).unwrap();
+
+        assert!(enciphered.split(' ').zip(String::from_utf8_lossy(plain_text).split(' ')).all(|(c, p)| c != p));
+        assert_eq!(autokey.decipher_passthrough(enciphered.as_bytes(), KeyAdvance::EveryCharacter).unwrap(), "b c d e f g h i");
+
+        let enciphered = ciphertext_autokey.encipher_passthrough(plain_text, KeyAdvance::EveryCharacter).unwrap();
+
+        assert!(enciphered.split(' ').zip(String::from_utf8_lossy(plain_text).split(' ')).all(|(c, p)| c != p));
+        assert_eq!(ciphertext_autokey.decipher_passthrough(enciphered.as_bytes(), KeyAdvance::EveryCharacter).unwrap(), "b c d e f g h i");
+    }
+
+    #[test]
+    fn test_passthrough_every_character() {
+        // The key is KE, then the plain text a character at a time, with the key letter under
+        // each non-letter standing in for it: K E G O G O G
+        assert_eq!(vigenere_autokey::encipher_passthrough(b"KE", b"Go, go!", KeyAdvance::EveryCharacter).unwrap(), "Qs, mc!");
+        assert_eq!(vigenere_autokey::decipher_passthrough(b"KE", b"Qs, mc!", KeyAdvance::EveryCharacter).unwrap(), "Go, go!");
+
+        let cipher = CiphertextAutokeyVigenere::from_key_str("QUEENLY").unwrap();
+
+        let enciphered = cipher.encipher_passthrough(b"Attack at dawn, then hold the bridge.", KeyAdvance::EveryCharacter).unwrap();
+
+        assert_eq!(cipher.decipher_passthrough(enciphered.as_bytes(), KeyAdvance::EveryCharacter).unwrap(), "Attack at dawn, then hold the bridge.");
+        assert_ne!(enciphered, cipher.encipher_passthrough(b"Attack at dawn, then hold the bridge.", KeyAdvance::LettersOnly).unwrap());
+    }
+
+    quickcheck! {
+        fn passthrough_round_trips(priming_key: Vec<u8>, plain_text: String, letters_only: bool) -> bool {
+            let advance = if letters_only { KeyAdvance::LettersOnly } else { KeyAdvance::EveryCharacter };
+
+            let enciphered = vigenere_autokey::encipher_passthrough(&priming_key, plain_text.as_bytes(), advance).unwrap();
+
+            vigenere_autokey::decipher_passthrough(&priming_key, enciphered.as_bytes(), advance).unwrap() == plain_text
+        }
+    }
+
+    quickcheck! {
+        fn ciphertext_autokey_round_trip(priming_key: Vec<u8>, plain_text: Vec<u8>) -> bool {
+            let enciphered = vigenere_autokey::encipher_ciphertext_autokey(&priming_key, &plain_text).unwrap();
+            let deciphered = vigenere_autokey::decipher_ciphertext_autokey(&priming_key, enciphered.as_bytes()).unwrap();
+
+            deciphered == common::format_output(common::sanitize_text(&plain_text).unwrap())
+        }
+    }
+
+    #[test]
+    fn test_vigenere_autokey_other_alphabets() {
+        let german = Alphabet::german();
+
+        let cipher_text = vigenere_autokey::encipher_in(&german, b"B", "Größe".as_bytes()).unwrap();
+
+        assert_eq!("HXOÄD", cipher_text);
+
+        let plain_text = vigenere_autokey::decipher_in(&german, b"B", cipher_text.as_bytes()).unwrap();
+
+        assert_eq!("GRÖßE", plain_text);
+    }
 }
\ No newline at end of file
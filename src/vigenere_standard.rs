@@ -8,49 +8,164 @@
 //! And deciphering is:
 //! 
 //! `P = C - K`
+//!
+//! The `_in` functions work over any [`Alphabet`], with the addition and subtraction
+//! taken modulo the size of the alphabet.
+//!
+//! The `_passthrough` functions only touch the letters of the text (see [`crate::passthrough`]).
 
 use crate::errors::Error;
+use crate::alphabet::{Alphabet, Symbol};
+use crate::cipher::{Cipher, KeyedCipher};
 use crate::common::AsciiUppercaseByte;
 use crate::common;
+use crate::passthrough::{KeyAdvance, MaskedText};
+use std::ops::{Add, Sub};
 
 /// Encipher `plain_text` with the Vigenere cipher under the key `key`
 pub fn encipher(key: &[u8], plain_text: &[u8])-> Result<String, Error> {
-    let key = common::sanitize_text(key)?;
+    encipher_with_key(common::sanitize_text(key)?, plain_text)
+}
+
+/// Decipher `cipher_text` with the Vigenere cipher under the key `key`
+pub fn decipher(key: &[u8], cipher_text: &[u8]) -> Result<String, Error> {
+    decipher_with_key(common::sanitize_text(key)?, cipher_text)
+}
+
+/// Encipher `plain_text` with the Vigenere cipher under the key `key`, using the symbols of `alphabet`
+pub fn encipher_in(alphabet: &Alphabet, key: &[u8], plain_text: &[u8]) -> Result<String, Error> {
+    let enciphered = encipher_symbols(common::sanitize_text_with(key, alphabet), common::sanitize_text_with(plain_text, alphabet));
+
+    Ok(common::format_output(alphabet.render(&enciphered)))
+}
+
+/// Decipher `cipher_text` with the Vigenere cipher under the key `key`, using the symbols of `alphabet`
+pub fn decipher_in(alphabet: &Alphabet, key: &[u8], cipher_text: &[u8]) -> Result<String, Error> {
+    let deciphered = decipher_symbols(common::sanitize_text_with(key, alphabet), common::sanitize_text_with(cipher_text, alphabet));
+
+    Ok(common::format_output(alphabet.render(&deciphered)))
+}
+
+/// Encipher the letters of `plain_text` with the Vigenere cipher under the key `key`, leaving
+/// everything else in place
+pub fn encipher_passthrough(key: &[u8], plain_text: &[u8], advance: KeyAdvance) -> Result<String, Error> {
+    Ok(passthrough(&common::sanitize_text_with(key, &Alphabet::standard()), plain_text, advance, add_bytes))
+}
+
+/// Decipher the letters of `cipher_text` with the Vigenere cipher under the key `key`, leaving
+/// everything else in place
+pub fn decipher_passthrough(key: &[u8], cipher_text: &[u8], advance: KeyAdvance) -> Result<String, Error> {
+    Ok(passthrough(&common::sanitize_text_with(key, &Alphabet::standard()), cipher_text, advance, subtract_bytes))
+}
+
+/// The standard Vigenere cipher, for use through the [`Cipher`] trait
+pub struct StandardVigenere {
+    key: Vec<AsciiUppercaseByte>,
+}
+
+impl KeyedCipher for StandardVigenere {
+    type Key = Vec<AsciiUppercaseByte>;
+
+    fn parse_key(key: &str) -> Result<Self::Key, Error> {
+        common::sanitize_text(key.as_bytes())
+    }
+
+    fn with_key(key: Self::Key) -> Result<Self, Error> {
+        Ok(StandardVigenere { key })
+    }
+}
+
+impl Cipher for StandardVigenere {
+    fn encipher(&self, plain_text: &[u8]) -> Result<String, Error> {
+        encipher_with_key(self.key.clone(), plain_text)
+    }
+
+    fn decipher(&self, cipher_text: &[u8]) -> Result<String, Error> {
+        decipher_with_key(self.key.clone(), cipher_text)
+    }
+
+    fn encipher_passthrough(&self, plain_text: &[u8], advance: KeyAdvance) -> Result<String, Error> {
+        Ok(passthrough(&common::to_symbols(&self.key), plain_text, advance, add_bytes))
+    }
+
+    fn decipher_passthrough(&self, cipher_text: &[u8], advance: KeyAdvance) -> Result<String, Error> {
+        Ok(passthrough(&common::to_symbols(&self.key), cipher_text, advance, subtract_bytes))
+    }
+}
 
+fn encipher_with_key(key: Vec<AsciiUppercaseByte>, plain_text: &[u8]) -> Result<String, Error> {
     let plain_text = common::sanitize_text(plain_text)?;
-    
+
+    Ok(common::format_output(encipher_symbols(key, plain_text)))
+}
+
+fn decipher_with_key(key: Vec<AsciiUppercaseByte>, cipher_text: &[u8]) -> Result<String, Error> {
+    let cipher_text = common::sanitize_text(cipher_text)?;
+
+    Ok(common::format_output(decipher_symbols(key, cipher_text)))
+}
+
+// Combines the letters of `text` with the repeated `key` using `operation`
+pub(crate) fn combine<F>(key: &[AsciiUppercaseByte], text: &[u8], operation: F) -> Result<String, Error>
+    where F: Fn(&[AsciiUppercaseByte], &[AsciiUppercaseByte]) -> Vec<AsciiUppercaseByte> {
+    let text = common::sanitize_text(text)?;
+
     // If no key was passed, we don't encrypt
-    if key.len() == 0 {
-        return Ok(common::format_output(plain_text));
+    if key.is_empty() {
+        return Ok(common::format_output(text));
     }
 
-    let key = repeat_key(key, plain_text.len());
+    let key = repeat_key(key.to_vec(), text.len());
 
-    let enciphered = add_bytes(&plain_text, &key);
+    Ok(common::format_output(operation(&text, &key)))
+}
 
-    Ok(common::format_output(enciphered))
+// Combines the letters of `text` with the repeated `key` using `operation`, leaving everything else in place
+pub(crate) fn passthrough<F>(key: &[Symbol], text: &[u8], advance: KeyAdvance, operation: F) -> String
+    where F: Fn(&[Symbol], &[Symbol]) -> Vec<Symbol> {
+    let alphabet = Alphabet::standard();
+
+    let text = MaskedText::new(&alphabet, text);
+
+    let letters = text.letters();
+
+    // If no key was passed, we don't encrypt
+    if key.is_empty() {
+        return text.unmask(&alphabet, &letters);
+    }
+
+    let key = text.key_positions(advance)
+                .into_iter()
+                .map(|position| key[position % key.len()])
+                .collect::<Vec<Symbol>>();
+
+    text.unmask(&alphabet, &operation(&letters, &key))
 }
 
-/// Decipher `cipher_text` with the Vigenere cipher under the key `key`
-pub fn decipher(key: &[u8], cipher_text: &[u8]) -> Result<String, Error> {
-    let key = common::sanitize_text(key)?;
+fn encipher_symbols<T: Copy + Add<Output = T>>(key: Vec<T>, plain_text: Vec<T>) -> Vec<T> {
+    // If no key was passed, we don't encrypt
+    if key.is_empty() {
+        return plain_text;
+    }
 
-    let cipher_text = common::sanitize_text(cipher_text)?;
+    let key = repeat_key(key, plain_text.len());
 
+    add_bytes(&plain_text, &key)
+}
+
+fn decipher_symbols<T: Copy + Sub<Output = T>>(key: Vec<T>, cipher_text: Vec<T>) -> Vec<T> {
     // If no key was passed, we don't decrypt
-    if key.len() == 0 {
-        return Ok(common::format_output(cipher_text));
+    if key.is_empty() {
+        return cipher_text;
     }
 
     let key = repeat_key(key, cipher_text.len());
 
-    let deciphered = subtract_bytes(&cipher_text, &key);
-
-    Ok(common::format_output(deciphered))
+    subtract_bytes(&cipher_text, &key)
 }
 
 // Repeat the key so that its length matches `target_length`
-fn repeat_key(mut key: Vec<AsciiUppercaseByte>, target_length: usize) -> Vec<AsciiUppercaseByte> {
+pub(crate) fn repeat_key<T: Copy>(mut key: Vec<T>, target_length: usize) -> Vec<T> {
     if target_length == key.len() {
         key
     }
@@ -75,7 +190,7 @@ fn repeat_key(mut key: Vec<AsciiUppercaseByte>, target_length: usize) -> Vec<Asc
 /// # Panics
 /// 
 /// Panics of `left` and `right` are not of the same length
-pub fn add_bytes(left: &[AsciiUppercaseByte], right: &[AsciiUppercaseByte]) -> Vec<AsciiUppercaseByte> {
+pub fn add_bytes<T: Copy + Add<Output = T>>(left: &[T], right: &[T]) -> Vec<T> {
     if left.len() != right.len() {
         panic!("Key length is not equal to plain text length");
     }
@@ -94,7 +209,7 @@ pub fn add_bytes(left: &[AsciiUppercaseByte], right: &[AsciiUppercaseByte]) -> V
 /// # Panics
 /// 
 /// Panics if `left` and `right` are not of the same length
-pub fn subtract_bytes(left: &[AsciiUppercaseByte], right: &[AsciiUppercaseByte]) -> Vec<AsciiUppercaseByte> {
+pub fn subtract_bytes<T: Copy + Sub<Output = T>>(left: &[T], right: &[T]) -> Vec<T> {
     if left.len() != right.len() {
         panic!("Key length is not equal to plain text length");
     }
@@ -111,7 +226,9 @@ pub fn subtract_bytes(left: &[AsciiUppercaseByte], right: &[AsciiUppercaseByte])
 #[cfg(test)]
 mod tests {
     use crate::vigenere_standard;
+    use crate::alphabet::Alphabet;
     use crate::common;
+    use crate::passthrough::KeyAdvance;
     use quickcheck::quickcheck;
 
     #[test]
@@ -149,6 +266,55 @@ mod tests {
         assert_eq!(deciphered, "NOWIS THETI MEFOR ALLGO ODMEN");
     }
 
+    #[test]
+    fn test_vigenere_other_alphabets() {
+        let spanish = Alphabet::spanish();
+
+        // N + B wraps onto Ñ rather than O
+        let enciphered = vigenere_standard::encipher_in(&spanish, b"B", b"NOYZ").unwrap();
+
+        assert_eq!(enciphered, "ÑPZA");
+
+        let deciphered = vigenere_standard::decipher_in(&spanish, b"B", enciphered.as_bytes()).unwrap();
+
+        assert_eq!(deciphered, "NOYZ");
+
+        let latin = Alphabet::latin();
+
+        let enciphered = vigenere_standard::encipher_in(&latin, b"CAESAR", b"Gallia est omnis divisa in partes tres").unwrap();
+        let deciphered = vigenere_standard::decipher_in(&latin, b"CAESAR", enciphered.as_bytes()).unwrap();
+
+        assert_eq!(deciphered, "GALLI AESTO MNISD IVISA INPAR \nTESTR ES");
+    }
+
+    #[test]
+    fn test_vigenere_passthrough() {
+        let plain_text = b"Meet me at 10, by the gate!";
+
+        let enciphered = vigenere_standard::encipher_passthrough(b"LEMON", plain_text, KeyAdvance::LettersOnly).unwrap();
+
+        assert_eq!(enciphered, "Xiqh zp ef 10, pl elq unei!");
+
+        let enciphered = vigenere_standard::encipher_passthrough(b"LEMON", plain_text, KeyAdvance::EveryCharacter).unwrap();
+
+        assert_eq!(enciphered, "Xiqh xi og 10, mc hup sogp!");
+
+        // Characters that only uppercase to a letter are left alone
+        assert_eq!(vigenere_standard::encipher_passthrough(b"B", "ıſ".as_bytes(), KeyAdvance::LettersOnly).unwrap(), "ıſ");
+    }
+
+    quickcheck! {
+        fn passthrough_round_trips(key: Vec<u8>, plain_text: String, letters_only: bool) -> bool {
+            let advance = if letters_only { KeyAdvance::LettersOnly } else { KeyAdvance::EveryCharacter };
+
+            let enciphered = vigenere_standard::encipher_passthrough(&key, plain_text.as_bytes(), advance).unwrap();
+
+            let deciphered = vigenere_standard::decipher_passthrough(&key, enciphered.as_bytes(), advance).unwrap();
+
+            deciphered == plain_text
+        }
+    }
+
     quickcheck! {
         fn deciphering_does_nothing_when_key_is_a(cipher_text: Vec<u8>) -> bool {
             let res = vigenere_standard::decipher(b"A", &cipher_text).unwrap();
@@ -167,6 +333,7 @@ mod tests {
     }
 
     quickcheck! {
+        #[allow(clippy::manual_range_contains)]
         fn output_is_uppercased_alphabetic(key: Vec<u8>, plain_text: Vec<u8>) -> bool {
             let res = vigenere_standard::encipher(&key, &plain_text).unwrap();
 